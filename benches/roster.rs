@@ -0,0 +1,106 @@
+//! Timings for a roster the size of a large company: 100,000 people in 1,000 departments.
+//!
+//! Run with `cargo bench`, or `cargo bench -- --quick` for a rough figure.
+use std::rc::Rc;
+
+use chrono::{Duration, NaiveDate};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+use depart_mental::data_handling::ProgramData;
+use depart_mental::personnel::Person;
+use depart_mental::storage::Snapshot;
+
+const PEOPLE: usize = 100_000;
+const DEPARTMENTS: usize = 1_000;
+
+const LAST_NAMES: [&str; 12] = ["Lee", "Smith", "García", "Müller", "Nguyen", "Okafor", "Kowalski", "Tanaka",
+                                "O'Brien", "Larsen", "Ivanova", "Haddad"];
+const FIRST_NAMES: [&str; 10] = ["Ann", "Bo", "Carlos", "Dana", "Émile", "Fatima", "Gus", "Hana", "Ivo", "Jun"];
+
+fn hired() -> NaiveDate {
+    NaiveDate::from_ymd(2000, 1, 1)
+}
+
+/// A roster with `people` people spread evenly over `departments` departments.  Names repeat, so
+/// there are many namesakes, as in a real company of this size.
+fn roster(people: usize, departments: usize) -> ProgramData {
+    let mut data = ProgramData::init();
+
+    let depts: Vec<_> = (0..departments)
+        .map(|i| data.add_dept(&format!("d{}", i), &format!("Department {}", i)).unwrap())
+        .collect();
+
+    for i in 0..people {
+        let mut builder = Person::builder();
+        builder.last_name(&format!("{}{}", LAST_NAMES[i % LAST_NAMES.len()], i % 997))
+            .first_name(FIRST_NAMES[i / LAST_NAMES.len() % FIRST_NAMES.len()])
+            .date_of_hire(hired())
+            .department(Rc::clone(&depts[i % departments]));
+        data.add_person(&format!("p{}", i), builder.build().unwrap()).unwrap();
+    }
+
+    data
+}
+
+fn build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build");
+    group.sample_size(10);
+    group.bench_function("add 100k people to 1k departments", |b| b.iter(|| roster(PEOPLE, DEPARTMENTS)));
+    group.finish();
+}
+
+fn transfers(c: &mut Criterion) {
+    let data = roster(PEOPLE, DEPARTMENTS);
+    let people: Vec<_> = data.employee_list().map(|e| e.pointer()).collect();
+    let depts: Vec<_> = data.dept_list().map(|d| d.clone_pointer()).collect();
+    let mut n = 0;
+
+    c.bench_function("transfer one person", |b| b.iter(|| {
+        n += 1;
+        let person = &people[n % people.len()];
+        let current = person.borrow().department().borrow().id() as usize;
+        let target = Rc::clone(&depts[current % depts.len()]);
+        let date = person.borrow().department_since() + Duration::days(1);
+        person.borrow_mut().transfer(target, date).unwrap();
+    }));
+}
+
+fn listing(c: &mut Criterion) {
+    let data = roster(PEOPLE, DEPARTMENTS);
+    let dept = data.dept_list().next().unwrap().clone_pointer();
+    let other = data.dept_list().nth(1).unwrap().clone_pointer();
+    let people = dept.borrow().employees().clone();
+    let mut n = 0;
+
+    c.bench_function("list one department after a change", |b| b.iter(|| {
+        // Move someone in or out so any cached order has to be brought up to date.  The people
+        // take turns, so no one's department history grows long enough to slow the transfer.
+        n += 1;
+        let person = &people[n % people.len()];
+        let target = if Rc::ptr_eq(&person.borrow().department(), &dept) { &other } else { &dept };
+        let date = person.borrow().department_since() + Duration::days(1);
+        person.borrow_mut().transfer(Rc::clone(target), date).unwrap();
+        dept.borrow().employees().iter().map(|p| p.borrow().id()).sum::<u32>()
+    }));
+
+    let mut group = c.benchmark_group("list");
+    group.sample_size(10);
+    group.bench_function("list 100k people by name", |b| b.iter(|| data.employees_by_name().len()));
+    group.bench_function("verify 100k people", |b| b.iter(|| data.verify().len()));
+    group.finish();
+}
+
+fn storage(c: &mut Criterion) {
+    let data = roster(PEOPLE, DEPARTMENTS);
+
+    let mut group = c.benchmark_group("storage");
+    group.sample_size(10);
+    group.bench_function("snapshot 100k people", |b| b.iter(|| Snapshot::take(&data)));
+    group.bench_function("restore 100k people", |b| {
+        b.iter_batched(|| Snapshot::take(&data), |saved| saved.restore().unwrap(), BatchSize::LargeInput)
+    });
+    group.finish();
+}
+
+criterion_group!(benches, build, transfers, listing, storage);
+criterion_main!(benches);
@@ -0,0 +1,318 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+use crate::personnel::{Name, NameOrder, Person};
+
+/// Language rules for comparing text.  All but Binary ignore case and accents when deciding
+/// order, so "Ávila" sorts with "Avila" and "de Vries" with "De Vries".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    /// Plain byte-wise comparison: uppercase before lowercase, accented letters after "z".
+    Binary,
+    /// Language-neutral ordering suitable for most Western European names.
+    #[default]
+    Root,
+    /// German phonebook ordering: "ä" sorts as "ae", "ö" as "oe", "ü" as "ue".
+    German,
+    /// Swedish and Finnish ordering: "å", "ä" and "ö" are separate letters after "z".
+    Swedish,
+    /// Spanish ordering: "ñ" is a separate letter after "n".
+    Spanish,
+}
+
+impl Locale {
+    /// Letters this locale sorts differently from Root, and what they sort as.  Characters above
+    /// 'z' in the replacement place a letter after the end of the Latin alphabet.
+    fn tailoring(&self, c: char) -> Option<&'static str> {
+        match (self, c) {
+            (Locale::German, 'ä') => Some("ae"),
+            (Locale::German, 'ö') => Some("oe"),
+            (Locale::German, 'ü') => Some("ue"),
+            (Locale::Swedish, 'å') => Some("{"),
+            (Locale::Swedish, 'ä') | (Locale::Swedish, 'æ') => Some("|"),
+            (Locale::Swedish, 'ö') | (Locale::Swedish, 'ø') => Some("}"),
+            (Locale::Spanish, 'ñ') => Some("n~"),
+            _ => None,
+        }
+    }
+
+    /// Reduce text to the form used for its primary ordering: lowercase, with accents and other
+    /// marks removed, except where the locale treats an accented letter as a letter of its own.
+    pub fn fold(&self, text: &str) -> String {
+        let mut folded = String::with_capacity(text.len());
+
+        for c in text.chars().flat_map(char::to_lowercase) {
+            if let Some(replacement) = self.tailoring(c) {
+                folded.push_str(replacement);
+                continue;
+            }
+
+            match c {
+                'ß' => folded.push_str("ss"),
+                'æ' => folded.push_str("ae"),
+                'œ' => folded.push_str("oe"),
+                'ø' => folded.push('o'),
+                'đ' | 'ð' => folded.push('d'),
+                'ł' => folded.push('l'),
+                'þ' => folded.push_str("th"),
+                'ı' => folded.push('i'),
+                _ => folded.extend(c.nfd().filter(|m| !is_combining_mark(*m))),
+            };
+        }
+
+        folded
+    }
+}
+
+impl FromStr for Locale {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Locale, ()> {
+        match s.to_lowercase().as_str() {
+            "binary" => Ok(Locale::Binary),
+            "root" => Ok(Locale::Root),
+            "de" | "german" => Ok(Locale::German),
+            "sv" | "fi" | "swedish" => Ok(Locale::Swedish),
+            "es" | "spanish" => Ok(Locale::Spanish),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Locale::Binary => write!(f, "binary"),
+            Locale::Root => write!(f, "root"),
+            Locale::German => write!(f, "de"),
+            Locale::Swedish => write!(f, "sv"),
+            Locale::Spanish => write!(f, "es"),
+        }
+    }
+}
+
+/// Which part of a name is compared first when sorting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortBy {
+    /// Family name, then given name: "Smith, Anne" before "Smith, John".
+    #[default]
+    Family,
+    /// Given name, then family name.
+    Given,
+}
+
+/// Rules for putting names in order.  Every comparison is a total ordering: names that tie on the
+/// configured keys are further ordered by every other part of the name, so two names only compare
+/// equal if they are identical.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Collation {
+    pub sort_by: SortBy,
+    /// Sort by preferred name in place of the given name, where one is set.
+    pub preferred: bool,
+    pub locale: Locale,
+}
+
+impl Collation {
+    pub fn compare_names(&self, a: &Name, b: &Name) -> Ordering {
+        let given = |name: &Name| -> String {
+            match (&name.preferred, self.preferred) {
+                (Some(preferred), true) => preferred.clone(),
+                _ => name.first.clone(),
+            }
+        };
+
+        let primary = match self.sort_by {
+            SortBy::Family => self.compare_text(&a.last, &b.last)
+                .then_with(|| self.compare_text(&given(a), &given(b))),
+            SortBy::Given => self.compare_text(&given(a), &given(b))
+                .then_with(|| self.compare_text(&a.last, &b.last)),
+        };
+
+        primary
+            .then_with(|| self.compare_text(&a.first, &b.first))
+            .then_with(|| self.compare_lists(&a.middle, &b.middle))
+            .then_with(|| self.compare_optional(&a.suffix, &b.suffix))
+            .then_with(|| self.compare_optional(&a.prefix, &b.prefix))
+            .then_with(|| self.compare_optional(&a.preferred, &b.preferred))
+            .then_with(|| a.order.cmp(&b.order))
+    }
+
+    /// Compare two people by name.  Different people with identical names are ordered by id.
+    pub fn compare_people(&self, a: &Person, b: &Person) -> Ordering {
+        self.compare_names(a.name(), b.name())
+            .then_with(|| a.id().cmp(&b.id()))
+    }
+
+    /// Whether two names belong to the same family and given names, ignoring case, accents and
+    /// every other part of the name.  Used to warn about possible duplicate entries.
+    pub fn same_name(&self, a: &Name, b: &Name) -> bool {
+        self.compare_primary(&a.last, &b.last) == Ordering::Equal
+            && self.compare_primary(&a.first, &b.first) == Ordering::Equal
+    }
+
+    /// Compare two strings under this collation's locale.  Strings that only differ in case or
+    /// accents are ordered by those differences afterward, so the ordering stays total.
+    pub fn compare_text(&self, a: &str, b: &str) -> Ordering {
+        if self.locale == Locale::Binary {
+            return a.cmp(b);
+        }
+
+        self.compare_primary(a, b)
+            // Accents next, ignoring case: unaccented letters sort first.
+            .then_with(|| a.to_lowercase().nfd().cmp(b.to_lowercase().nfd()))
+            // Then case: lowercase letters sort first.
+            .then_with(|| a.chars().map(char::is_uppercase).cmp(b.chars().map(char::is_uppercase)))
+            .then_with(|| a.cmp(b))
+    }
+
+    /// Compare two strings ignoring case and accents, for matching rather than sorting.
+    pub fn compare_primary(&self, a: &str, b: &str) -> Ordering {
+        self.fold(a).cmp(&self.fold(b))
+    }
+
+    /// Text reduced to its primary sort key.  Binary collation only ignores case.
+    pub fn fold(&self, text: &str) -> String {
+        match self.locale {
+            Locale::Binary => text.to_lowercase(),
+            locale => locale.fold(text),
+        }
+    }
+
+    /// A sort key for `text` that orders the same way as compare_text(), so that lists can be
+    /// kept in a BTreeMap or sorted without folding the same text over and over.
+    pub fn text_key(&self, text: &str) -> TextKey {
+        if self.locale == Locale::Binary {
+            return TextKey { primary: String::from(text), ..TextKey::default() };
+        }
+
+        TextKey {
+            primary: self.fold(text),
+            accents: text.to_lowercase().nfd().collect(),
+            case: text.chars().map(char::is_uppercase).collect(),
+            text: String::from(text),
+        }
+    }
+
+    /// A sort key for `name` that orders the same way as compare_names().
+    pub fn name_key(&self, name: &Name) -> NameKey {
+        let given = match (&name.preferred, self.preferred) {
+            (Some(preferred), true) => preferred,
+            _ => &name.first,
+        };
+        let (family, given) = (self.text_key(&name.last), self.text_key(given));
+        let optional = |part: &Option<String>| part.as_ref().map(|p| self.text_key(p));
+
+        NameKey {
+            primary: match self.sort_by {
+                SortBy::Family => (family, given),
+                SortBy::Given => (given, family),
+            },
+            first: self.text_key(&name.first),
+            middle: name.middle.iter().map(|m| self.text_key(m)).collect(),
+            suffix: optional(&name.suffix),
+            prefix: optional(&name.prefix),
+            preferred: optional(&name.preferred),
+            order: name.order,
+        }
+    }
+
+    /// A sort key for `person` that orders the same way as compare_people().
+    pub fn person_key(&self, person: &Person) -> PersonKey {
+        PersonKey {
+            name: self.name_key(person.name()),
+            id: person.id(),
+        }
+    }
+
+    fn compare_optional(&self, a: &Option<String>, b: &Option<String>) -> Ordering {
+        match (a, b) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Less,
+            (Some(_), None) => Ordering::Greater,
+            (Some(a), Some(b)) => self.compare_text(a, b),
+        }
+    }
+
+    fn compare_lists(&self, a: &[String], b: &[String]) -> Ordering {
+        for (x, y) in a.iter().zip(b) {
+            let ordering = self.compare_text(x, y);
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+
+        a.len().cmp(&b.len())
+    }
+}
+
+/// See Collation::text_key().  Keys are only comparable with others made by the same Collation.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct TextKey {
+    primary: String,
+    accents: String,
+    case: Vec<bool>,
+    text: String,
+}
+
+/// See Collation::name_key().
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NameKey {
+    primary: (TextKey, TextKey),
+    first: TextKey,
+    middle: Vec<TextKey>,
+    suffix: Option<TextKey>,
+    prefix: Option<TextKey>,
+    preferred: Option<TextKey>,
+    order: NameOrder,
+}
+
+/// See Collation::person_key().
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PersonKey {
+    name: NameKey,
+    id: u32,
+}
+
+impl FromStr for Collation {
+    type Err = String;
+
+    /// Reads the words written by Display, in any order: FAMILY or GIVEN, optionally PREFERRED,
+    /// and a locale.  Anything not mentioned takes its default.  Err holds the first word that
+    /// wasn't understood.
+    fn from_str(s: &str) -> Result<Collation, String> {
+        let mut collation = Collation::default();
+
+        for word in s.split_whitespace() {
+            match word.to_lowercase().as_str() {
+                "family" => collation.sort_by = SortBy::Family,
+                "given" => collation.sort_by = SortBy::Given,
+                "preferred" => collation.preferred = true,
+                other => match Locale::from_str(other) {
+                    Ok(locale) => collation.locale = locale,
+                    Err(_) => return Err(String::from(word)),
+                },
+            };
+        }
+
+        Ok(collation)
+    }
+}
+
+impl fmt::Display for Collation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.sort_by {
+            SortBy::Family => write!(f, "family")?,
+            SortBy::Given => write!(f, "given")?,
+        };
+
+        if self.preferred {
+            write!(f, " preferred")?;
+        }
+
+        write!(f, " {}", self.locale)
+    }
+}
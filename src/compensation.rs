@@ -0,0 +1,459 @@
+//! Pay records, department budgets and payroll cost reports.
+//!
+//! Amounts are held in whole cents to avoid rounding errors.  Figures in different currencies are
+//! never converted or added together; reports list each currency separately.  Totals stop at the
+//! largest amount an i64 can hold rather than overflowing.
+use std::rc::Rc;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::fmt;
+use std::error::Error;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::naive::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::data_handling::ProgramData;
+use crate::reports::csv_field;
+
+/// Paid hours in a full-time year, used to turn hourly rates into annual cost.
+pub const HOURS_PER_YEAR: i64 = 2080;
+
+/// An amount of money in a single currency.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Money {
+    pub cents: i64,
+    /// ISO 4217 code, e.g. "USD".
+    pub currency: String,
+}
+
+impl Money {
+    pub fn new(cents: i64, currency: &str) -> Self {
+        Money {
+            cents,
+            currency: currency.to_uppercase(),
+        }
+    }
+
+    /// Read an amount such as "85000", "85,000.00" or "23.5" in the given currency.
+    pub fn parse(amount: &str, currency: &str) -> Result<Money, CompensationError> {
+        let invalid = || CompensationError::InvalidAmount(String::from(amount));
+
+        if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(CompensationError::InvalidCurrency(String::from(currency)));
+        }
+
+        let cleaned: String = amount.trim().chars().filter(|c| *c != ',').collect();
+        let (whole, fraction) = match cleaned.find('.') {
+            Some(i) => (&cleaned[..i], &cleaned[i + 1..]),
+            None => (cleaned.as_str(), ""),
+        };
+
+        // Digits only, so there's no sign: amounts are never negative
+        if whole.is_empty() || fraction.len() > 2
+            || !whole.chars().all(|c| c.is_ascii_digit()) || !fraction.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+
+        let whole = i64::from_str(whole).map_err(|_| invalid())?;
+        let fraction = format!("{:0<2}", fraction);
+        let fraction = i64::from_str(&fraction).map_err(|_| invalid())?;
+
+        let cents = whole.checked_mul(100)
+            .and_then(|cents| cents.checked_add(fraction))
+            .ok_or_else(invalid)?;
+
+        Ok(Money::new(cents, currency))
+    }
+
+    pub fn zero(currency: &str) -> Self {
+        Money::new(0, currency)
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.cents < 0 { "-" } else { "" };
+        let cents = self.cents.unsigned_abs();
+
+        write!(f, "{}{}.{:02} {}", sign, cents / 100, cents % 100, self.currency)
+    }
+}
+
+/// Whether an amount is a yearly salary or an hourly rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PayBasis {
+    /// Pay per year, as actually paid: part-time salaries are already prorated.
+    Annual,
+    /// Pay per hour worked.  Annual cost assumes HOURS_PER_YEAR hours scaled by FTE.
+    Hourly,
+}
+
+impl FromStr for PayBasis {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<PayBasis, ()> {
+        match s.to_lowercase().as_str() {
+            "annual" | "salary" | "yearly" => Ok(PayBasis::Annual),
+            "hourly" | "hour" => Ok(PayBasis::Hourly),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for PayBasis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PayBasis::Annual => write!(f, "annual"),
+            PayBasis::Hourly => write!(f, "hourly"),
+        }
+    }
+}
+
+/// A rate of pay in effect from the given date until the next entry in the person's history.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompensationEntry {
+    pub pay: Money,
+    pub basis: PayBasis,
+    pub date: NaiveDate,
+    /// Why the pay changed, e.g. "Annual review" or "Promotion".
+    pub reason: String,
+}
+
+impl CompensationEntry {
+    /// Cost of this pay over a year, for someone working the given FTE.
+    pub fn annual_cost(&self, fte: f64) -> Money {
+        let cents = match self.basis {
+            PayBasis::Annual => self.pay.cents,
+            PayBasis::Hourly => (self.pay.cents as f64 * HOURS_PER_YEAR as f64 * fte).round() as i64,
+        };
+
+        Money::new(cents, &self.pay.currency)
+    }
+}
+
+impl fmt::Display for CompensationEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.pay, self.basis)?;
+
+        if !self.reason.is_empty() {
+            write!(f, " ({})", self.reason)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Rounds of PBKDF2 used for new passphrase hashes.
+const PASSPHRASE_ROUNDS: u32 = 100_000;
+
+/// Hash an HR passphrase for storage, with PBKDF2-HMAC-SHA256 and a new salt.  The result holds
+/// everything needed to check it: "pbkdf2-sha256$ROUNDS$SALT$HASH", salt and hash in hex.  See
+/// ProgramData::set_hr_passphrase().
+pub fn hash_passphrase(passphrase: &str) -> String {
+    let salt = new_salt();
+    let hash = pbkdf2_sha256(passphrase.as_bytes(), &salt, PASSPHRASE_ROUNDS);
+
+    format!("pbkdf2-sha256${}${}${}", PASSPHRASE_ROUNDS, hex(&salt), hex(&hash))
+}
+
+/// Whether `passphrase` matches a hash made by hash_passphrase(), or a bare SHA-256 hash as kept
+/// by earlier versions.
+pub fn check_passphrase(passphrase: &str, stored: &str) -> bool {
+    let expected = match stored.split('$').collect::<Vec<&str>>().as_slice() {
+        ["pbkdf2-sha256", rounds, salt, _] => {
+            let (rounds, salt) = match (u32::from_str(rounds), unhex(salt)) {
+                (Ok(rounds), Some(salt)) if rounds > 0 => (rounds, salt),
+                _ => return false,
+            };
+            format!("pbkdf2-sha256${}${}${}", rounds, hex(&salt), hex(&pbkdf2_sha256(passphrase.as_bytes(), &salt, rounds)))
+        },
+        [_] => hex(&Sha256::digest(passphrase.as_bytes())),
+        _ => return false,
+    };
+
+    // Compare every byte, so the time taken doesn't give away how much of the hash matched.
+    expected.len() == stored.len() && expected.bytes().zip(stored.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Whether a stored hash is in the unsalted form kept by earlier versions, and should be replaced.
+pub fn is_legacy_hash(stored: &str) -> bool {
+    !stored.starts_with("pbkdf2-sha256$")
+}
+
+/// Sixteen bytes that differ from one call to the next.  A salt only has to be unique, not
+/// secret, so the randomly keyed hasher of the standard library is enough.
+fn new_salt() -> [u8; 16] {
+    let mut salt = [0u8; 16];
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos());
+
+    for chunk in salt.chunks_mut(8) {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u128(now);
+        chunk.copy_from_slice(&hasher.finish().to_le_bytes());
+    }
+
+    salt
+}
+
+/// PBKDF2 (RFC 8018) with HMAC-SHA256, giving a single 32 byte block.
+fn pbkdf2_sha256(passphrase: &[u8], salt: &[u8], rounds: u32) -> [u8; 32] {
+    // HMAC keys longer than a block are hashed first; shorter ones are padded with zeros.
+    let mut key = [0u8; 64];
+    if passphrase.len() > key.len() {
+        key[..32].copy_from_slice(&Sha256::digest(passphrase));
+    } else {
+        key[..passphrase.len()].copy_from_slice(passphrase);
+    }
+
+    let mut inner = Sha256::new();
+    inner.update(key.iter().map(|b| b ^ 0x36).collect::<Vec<u8>>());
+    let mut outer = Sha256::new();
+    outer.update(key.iter().map(|b| b ^ 0x5c).collect::<Vec<u8>>());
+
+    let hmac = |parts: &[&[u8]]| -> [u8; 32] {
+        let mut hash = inner.clone();
+        for part in parts {
+            hash.update(part);
+        }
+        let mut result = outer.clone();
+        result.update(hash.finalize());
+        result.finalize().into()
+    };
+
+    let mut block = hmac(&[salt, &1u32.to_be_bytes()]);
+    let mut result = block;
+    for _ in 1..rounds {
+        block = hmac(&[&block]);
+        for (r, b) in result.iter_mut().zip(block.iter()) {
+            *r ^= b;
+        }
+    }
+
+    result
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if text.len() % 2 != 0 || !text.is_ascii() {
+        return None;
+    }
+
+    (0..text.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Payroll figures for one department.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PayrollLine {
+    pub department: String,
+    pub headcount: u32,
+    pub fte: f64,
+    /// Annual cost of current pay, one entry per currency, sorted by currency.
+    pub costs: Vec<Money>,
+    pub budget: Option<Money>,
+    /// Employees with no pay recorded, who are left out of costs.
+    pub missing: u32,
+}
+
+impl PayrollLine {
+    /// Total cost in one currency.
+    pub fn actual_in(&self, currency: &str) -> Money {
+        self.costs.iter()
+            .find(|m| m.currency == currency)
+            .cloned()
+            .unwrap_or_else(|| Money::zero(currency))
+    }
+
+    /// Budget less actual cost in the budget's currency.  Negative if over budget.
+    pub fn variance(&self) -> Option<Money> {
+        self.budget.as_ref().map(|budget| {
+            let actual = self.actual_in(&budget.currency);
+            Money::new(budget.cents.saturating_sub(actual.cents), &budget.currency)
+        })
+    }
+}
+
+/// Annual payroll cost per department at a given date, with budget comparisons.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PayrollReport {
+    as_of: NaiveDate,
+    lines: Vec<PayrollLine>,
+}
+
+impl PayrollReport {
+    pub fn build(data: &ProgramData, as_of: NaiveDate) -> Self {
+        let mut lines = Vec::new();
+
+        for dept in data.dept_list() {
+            let mut line = PayrollLine {
+                department: dept.alias().clone(),
+                headcount: 0,
+                fte: 0.0,
+                costs: Vec::new(),
+                budget: dept.borrow_pointer().borrow().budget().cloned(),
+                missing: 0,
+            };
+
+            for entry in data.employee_list() {
+                let person = entry.pointer();
+                let person = person.borrow();
+
+                // People split between departments are counted in their primary department, with
+                // their FTE and cost shared according to their allocations
+                let allocations = person.allocations_on(as_of);
+                let (n, share) = match allocations.iter().position(|(d, _)| Rc::ptr_eq(d, dept.borrow_pointer())) {
+                    Some(n) => (n, f64::from(allocations[n].1) / 100.0),
+                    None => continue,
+                };
+
+                let fte = person.fte_on(as_of);
+                line.headcount += u32::from(n == 0);
+                line.fte += fte * share;
+
+                let mut cost = match person.compensation_on(as_of) {
+                    Some(pay) => pay.annual_cost(fte),
+                    None => {
+                        line.missing += u32::from(n == 0);
+                        continue;
+                    },
+                };
+                cost.cents = (cost.cents as f64 * share).round() as i64;
+
+                match line.costs.iter_mut().find(|m| m.currency == cost.currency) {
+                    Some(total) => total.cents = total.cents.saturating_add(cost.cents),
+                    None => line.costs.push(cost),
+                }
+            }
+
+            line.costs.sort_by(|a, b| a.currency.cmp(&b.currency));
+            lines.push(line);
+        }
+
+        PayrollReport { as_of, lines }
+    }
+
+    pub fn as_of(&self) -> NaiveDate {
+        self.as_of
+    }
+
+    pub fn lines(&self) -> &Vec<PayrollLine> {
+        &self.lines
+    }
+
+    /// Company-wide cost, one entry per currency.
+    pub fn totals(&self) -> Vec<Money> {
+        let mut totals: Vec<Money> = Vec::new();
+
+        for cost in self.lines.iter().flat_map(|l| &l.costs) {
+            match totals.iter_mut().find(|m| m.currency == cost.currency) {
+                Some(total) => total.cents = total.cents.saturating_add(cost.cents),
+                None => totals.push(cost.clone()),
+            }
+        }
+
+        totals.sort_by(|a, b| a.currency.cmp(&b.currency));
+        totals
+    }
+
+    /// Column headers matching the cells returned by table_rows().
+    pub fn headers() -> Vec<String> {
+        ["dept", "heads", "fte", "annual cost", "budget", "variance", "no pay"].iter()
+            .map(|h| String::from(*h))
+            .collect()
+    }
+
+    pub fn table_rows(&self) -> Vec<Vec<String>> {
+        let optional = |m: Option<Money>| m.map(|m| m.to_string()).unwrap_or_default();
+
+        self.lines.iter()
+            .map(|l| vec![
+                l.department.clone(),
+                l.headcount.to_string(),
+                format!("{:.2}", l.fte),
+                l.costs.iter().map(|m| m.to_string()).collect::<Vec<String>>().join(", "),
+                optional(l.budget.clone()),
+                optional(l.variance()),
+                l.missing.to_string(),
+            ])
+            .collect()
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let money = |m: &Money| json!({ "amount": m.cents as f64 / 100.0, "currency": m.currency });
+
+        let lines: Vec<serde_json::Value> = self.lines.iter()
+            .map(|l| json!({
+                "department": l.department,
+                "headcount": l.headcount,
+                "fte": l.fte,
+                "costs": l.costs.iter().map(money).collect::<Vec<serde_json::Value>>(),
+                "budget": l.budget.as_ref().map(money),
+                "variance": l.variance().as_ref().map(money),
+                "missing_pay": l.missing,
+            }))
+            .collect();
+
+        json!({
+            "as_of": self.as_of.to_string(),
+            "departments": lines,
+            "totals": self.totals().iter().map(money).collect::<Vec<serde_json::Value>>(),
+        })
+    }
+
+    /// One line per department and currency.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("department,currency,headcount,fte,annual_cost,budget,variance,missing_pay\n");
+
+        for l in &self.lines {
+            let mut currencies: Vec<&String> = l.costs.iter().map(|m| &m.currency).collect();
+            if let Some(budget) = &l.budget {
+                if !currencies.contains(&&budget.currency) {
+                    currencies.push(&budget.currency);
+                }
+            }
+
+            for currency in currencies {
+                let budget = l.budget.as_ref().filter(|b| &b.currency == currency);
+                let amount = |cents: i64| format!("{:.2}", cents as f64 / 100.0);
+
+                csv.push_str(&format!("{},{},{},{:.2},{},{},{},{}\n",
+                    csv_field(&l.department), currency, l.headcount, l.fte,
+                    amount(l.actual_in(currency).cents),
+                    budget.map(|b| amount(b.cents)).unwrap_or_default(),
+                    budget.and(l.variance()).map(|v| amount(v.cents)).unwrap_or_default(),
+                    l.missing));
+            }
+        }
+
+        csv
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompensationError {
+    InvalidAmount(String),
+    InvalidCurrency(String),
+    BeforeHire,
+    Terminated,
+}
+
+impl fmt::Display for CompensationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompensationError::InvalidAmount(a) => write!(f, "\"{}\" is not a valid amount", a),
+            CompensationError::InvalidCurrency(c) => write!(f, "\"{}\" is not a three letter currency code", c),
+            CompensationError::BeforeHire => write!(f, "Pay change is dated before the date of hire"),
+            CompensationError::Terminated => write!(f, "Person is no longer employed"),
+        }
+    }
+}
+
+impl Error for CompensationError {}
@@ -1,12 +1,29 @@
-use crate::personnel::{ Person, PersonError };
+use crate::personnel::{ EmploymentType, Name, Person, PersonError, Position, Status, TransferErr };
 use crate::department::{ Department, DeptErr };
+use crate::search::{ SearchResult, Searcher };
+use crate::query::{ Query, QueryError, QueryResult };
+use crate::reports::{ GroupReport, HeadcountReport, Period, PositionReport };
+use crate::tenure::TenureReport;
+use crate::dates::DateFormat;
+use crate::collation::{Collation, TextKey};
+use crate::ids::IdFormat;
+use crate::storage::{Snapshot, StorageError};
+use crate::fields::{ CONTACT_FIELDS, FieldError, FieldType, Schema };
+use crate::organization::{ CostCenter, Grouping, Location, valid_code };
+use crate::compensation::{ CompensationEntry, Money, PayBasis, PayrollReport, check_passphrase, hash_passphrase,
+                           is_legacy_hash };
+use crate::scheduling::PendingTransfer;
+use crate::integrity::{ Violation, check_lists };
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::collections::btree_map;
 use std::rc::Rc;
-use std::cell::{RefCell};
+use std::cell::RefCell;
 use std::fmt;
 use std::error::Error;
 use std::ops::Deref;
+use std::path::Path;
+use std::sync::atomic::{self, AtomicU64};
 
 use chrono::naive::NaiveDate;
 use chrono::prelude::*;
@@ -16,36 +33,64 @@ pub type Result<T> = std::result::Result<T, DataError>;
 
 /// ProgramData and its related methods represent the main API for managing personnel and department
 /// data.
+///
+/// Every person and department has a main alias, and may have any number of other aliases.  No
+/// alias is used twice, whether by a person or a department.  `departments` and `personnel` index
+/// every alias, so an entity can be found by any of its aliases; `dept_aliases` and
+/// `person_aliases` hold one entry per entity, sorted by main alias.
 pub struct ProgramData {
-    dept_aliases:     Vec<DepartmentAlias>,
-    person_aliases:   Vec<PersonAlias>,
+    dept_aliases:     AliasIndex<DepartmentAlias>,
+    person_aliases:   AliasIndex<PersonAlias>,
     departments:      HashMap<String, Rc<RefCell<Department>>>,
     personnel:        HashMap<String, Rc<RefCell<Person>>>,
     employee_count:   u32,
     department_count: u32,
+    person_count:     u32,
+    date_format:      DateFormat,
+    collation:        Collation,
+    id_format:        IdFormat,
+    hr_passphrase:    Option<String>,
+    schema:           Schema,
+    locations:        Vec<Location>,
+    cost_centers:     Vec<CostCenter>,
+    /// Transfers waiting for their effective date, sorted by date.
+    pending:          Vec<PendingTransfer>,
+    transfer_count:   u32,
+    /// Positions in person_aliases of every person in name order, with the generation of
+    /// person_aliases they were worked out from.
+    by_name:          RefCell<Option<(u64, Vec<usize>)>>,
 }
 
-// TODO -- Impl Serde and SQLite functionality to store and retrieve data from filesystem.
 impl ProgramData {
 
-    /// initialize an empty container struct for program data.  All fields will be empty.  In
-    /// the future, it will be possible to populate this struct with saved data from previous
-    /// sessions.
+    /// initialize an empty container struct for program data.  All fields will be empty.  Use
+    /// load() to populate it with saved data from a previous session instead.
     pub fn init() -> Self {
         ProgramData {
-            dept_aliases:     Vec::new(),
-            person_aliases:   Vec::new(),
+            dept_aliases:     AliasIndex::new(),
+            person_aliases:   AliasIndex::new(),
             departments:      HashMap::new(),
             personnel:        HashMap::new(),
             employee_count:   0,
             department_count: 0,
+            person_count:     0,
+            date_format:      DateFormat::default(),
+            collation:        Collation::default(),
+            id_format:        IdFormat::default(),
+            hr_passphrase:    None,
+            schema:           Schema::default(),
+            locations:        Vec::new(),
+            cost_centers:     Vec::new(),
+            pending:          Vec::new(),
+            transfer_count:   0,
+            by_name:          RefCell::new(None),
         }
     }
 
     /// Add a new department and store it in memory.  This method, when supplied with strings
     /// for an alias, and full name of the department, will create the department on its own.
     pub fn add_dept(&mut self, alias: &str, dept_name: &str) -> Result<Rc<RefCell<Department>>> {
-        if self.departments.contains_key(alias) {
+        if self.alias_in_use(alias) {
             return Err(DataError::AddDept);
         }
 
@@ -58,14 +103,11 @@ impl ProgramData {
                 Department::new(dept_name, department_id)
             )
         );
+        new_department.borrow_mut().set_collation(self.collation);
 
         let dept_alias = DepartmentAlias::new(alias, Rc::clone(&new_department));
 
-        //self.dept_aliases.push(DepartmentAlias::new(alias, Rc::clone(&new_department)));
-        let insertion_index = self.dept_aliases.binary_search(&dept_alias);
-        if let Err(i) = insertion_index {
-            self.dept_aliases.insert(i, dept_alias);
-        } else {
+        if !self.dept_aliases.insert(self.collation.text_key(alias), dept_alias) {
             eprintln!("Error adding department, alias already in use");
             return Err(DataError::AddDept);
         }
@@ -79,22 +121,70 @@ impl ProgramData {
     /// fully initialised and provided as an argument.  This is due to Person being more complicated
     /// thus requiring more parameters than would be convenient to pass to a method.
     /// The personnel module provides a builder for Person to make things a little more readable.
-    /// This method takes ownership of the Person data.
-    pub fn add_person(&mut self, alias: &str, person: Person) -> Result<Rc<RefCell<Person>>> {
-        if self.personnel.contains_key(alias) {
+    /// This method takes ownership of the Person data, and assigns it a new id.  Nothing is
+    /// changed if the person can't be added.  Other people may share the same name; see
+    /// namesakes() to check for possible duplicates.
+    pub fn add_person(&mut self, alias: &str, mut person: Person) -> Result<Rc<RefCell<Person>>> {
+        if self.alias_in_use(alias) {
             return Err(DataError::AddPerson);
         }
 
+        person.set_id(self.next_employee_id());
+
+        let person_ref = self.insert_person(alias, person)?;
+
+        self.person_count += 1;
+
+        Ok(person_ref)
+    }
+
+    /// Store a person who already has an id, listing them in their current department unless they
+    /// have left the company.  Nothing is changed if this fails.
+    fn insert_person(&mut self, alias: &str, person: Person) -> Result<Rc<RefCell<Person>>> {
+        let terminated = person.is_terminated();
+
+        // Check the alias before touching any department list, which couldn't be undone
+        let key = self.collation.text_key(alias);
+        if self.person_aliases.contains(&key) {
+            return Err(DataError::AddPerson);
+        }
+
+        for (name, value) in person.custom_fields() {
+            match self.schema.get(name) {
+                Some(def) if def.name == *name => {
+                    if !value.fits(&def.kind) {
+                        return Err(DataError::Field(FieldError::InvalidValue(name.clone(), value.to_string(), def.kind.clone())));
+                    }
+                },
+                _ => return Err(DataError::Field(FieldError::UnknownField(name.clone()))),
+            };
+        }
+
+        // Two memberships of one department would fail part way through listing the person
+        person.check_memberships().map_err(PersonError::Membership)?;
+
         // Add person to a new smart pointer
         let person_ref = Rc::new(RefCell::new(person));
 
-        // Add the Rc to the alias list
-        self.person_aliases.push(
-            PersonAlias::new(
-                alias,
-                Rc::clone(&person_ref)
-            )
-        );
+        // add person to their current department first, so a failure leaves nothing half done
+        if !terminated {
+            person_ref.borrow()
+                .department()
+                .borrow_mut()
+                .add_employee(Rc::clone(&person_ref))?;
+
+            let person = person_ref.borrow();
+            for membership in person.current_memberships() {
+                membership.department().borrow_mut()
+                    .insert_member(Rc::clone(&person_ref), &person)?;
+            }
+        }
+
+        // Add the Rc to the alias list, keeping it sorted
+        let person_alias = PersonAlias::new(alias, Rc::clone(&person_ref));
+
+        let inserted = self.person_aliases.insert(key, person_alias);
+        debug_assert!(inserted, "alias key was checked before listing the person");
 
         // Add the Rc to the personnel HashMap
         self.personnel.insert(
@@ -103,49 +193,1042 @@ impl ProgramData {
         );
 
         // increment employee_count
-        self.employee_count += 1;
+        if !terminated {
+            self.employee_count += 1;
+        }
+
+        Ok(person_ref)
+    }
+
+    /// Add a department with the id it was saved under.  Used when loading saved data.
+    pub(crate) fn restore_dept(&mut self, alias: &str, dept_name: &str, id: u32) -> Result<Rc<RefCell<Department>>> {
+        let department = self.add_dept(alias, dept_name)?;
+
+        department.borrow_mut().set_id(id);
+        self.department_count = self.department_count.max(id);
+
+        Ok(department)
+    }
+
+    /// Add a person with the id they were saved under.  Used when loading saved data.
+    pub(crate) fn restore_person(&mut self, alias: &str, person: Person) -> Result<Rc<RefCell<Person>>> {
+        if self.alias_in_use(alias) {
+            return Err(DataError::AddPerson);
+        }
 
-        // add person to their initial department
-        person_ref.borrow()
-            .department()
-            .borrow_mut()
-            .add_employee(Rc::clone(&person_ref))?;
+        let id = person.id();
+        let person_ref = self.insert_person(alias, person)?;
+        self.person_count = self.person_count.max(id);
 
         Ok(person_ref)
     }
 
-    pub fn dept_list(&self) -> &Vec<DepartmentAlias> {
-        &self.dept_aliases
+    /// The employee number the next person added will get.  Numbers are never reused, even
+    /// after the person they belonged to has left.
+    pub fn next_employee_id(&self) -> u32 {
+        self.person_count + 1
     }
 
-    pub fn employee_list(&self) -> &Vec<PersonAlias> {
-        &self.person_aliases
+    /// Skip ahead so that the next person added gets at least `id`.  Numbers already in use are
+    /// never handed out again, so this can't move backward.
+    pub fn set_next_employee_id(&mut self, id: u32) {
+        self.person_count = self.person_count.max(id.saturating_sub(1));
+    }
+
+    /// How employee numbers are shown and read.
+    pub fn id_format(&self) -> &IdFormat {
+        &self.id_format
+    }
+
+    pub fn set_id_format(&mut self, format: IdFormat) {
+        self.id_format = format;
+    }
+
+    /// A person's employee number, written in the configured format.
+    pub fn employee_id(&self, person: &Person) -> String {
+        self.id_format.format(person.id())
+    }
+
+    /// Find a person, current or former, by employee number.  The number may be written in the
+    /// configured format ("EMP-000123") or as a bare number ("123").
+    pub fn lookup(&self, employee_id: &str) -> Option<&PersonAlias> {
+        let id = self.id_format.parse(employee_id)?;
+
+        self.person_aliases.iter()
+            .find(|entry| entry.pointer.borrow().id() == id)
+    }
+
+    /// Write all data to a JSON file.  See the storage module.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        Snapshot::take(self).save(path)?;
+
+        Ok(())
+    }
+
+    /// Read data previously written by save().
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<ProgramData> {
+        let data = Snapshot::load(path)?.restore()?;
+
+        Ok(data)
+    }
+
+    /// Changes whenever an alias of anyone or any department is added, renamed or removed,
+    /// including when the data is replaced by loading a file.  Equal values mean the same aliases.
+    pub fn alias_generation(&self) -> (u64, u64) {
+        (self.person_aliases.generation, self.dept_aliases.generation)
+    }
+
+    /// Every person, current or former, sorted by name.  People with the same name are kept in
+    /// alias order.  The order is worked out once and kept until someone is added, an alias
+    /// changes or the collation changes; names changed through Person::name_mut() aren't noticed.
+    pub fn employees_by_name(&self) -> Vec<&PersonAlias> {
+        let list: Vec<&PersonAlias> = self.person_aliases.iter().collect();
+        let generation = self.person_aliases.generation;
+        let mut by_name = self.by_name.borrow_mut();
+
+        let order = match &mut *by_name {
+            Some((built, order)) if *built == generation => order,
+            stale => {
+                let keys: Vec<_> = list.iter()
+                    .map(|entry| (self.collation.name_key(entry.pointer.borrow().name()), self.collation.text_key(entry.alias())))
+                    .collect();
+                let mut order: Vec<usize> = (0..list.len()).collect();
+                order.sort_by(|a, b| keys[*a].cmp(&keys[*b]));
+                &mut stale.insert((generation, order)).1
+            },
+        };
+
+        order.iter().map(|i| list[*i]).collect()
+    }
+
+    /// Current employees whose family and given names match `name`, ignoring case and accents.
+    /// Having the same name is allowed, but is worth a warning in case someone was entered twice.
+    pub fn namesakes(&self, name: &Name) -> Vec<&PersonAlias> {
+        self.person_aliases.iter()
+            .filter(|entry| {
+                let person = entry.pointer.borrow();
+                !person.is_terminated() && self.collation.same_name(person.name(), name)
+            })
+            .collect()
+    }
+
+    /// Every group of two or more current employees who share a name, as found by namesakes().
+    pub fn duplicate_names(&self) -> Vec<Vec<&PersonAlias>> {
+        let mut groups: Vec<Vec<&PersonAlias>> = Vec::new();
+
+        for entry in &self.person_aliases {
+            let person = entry.pointer.borrow();
+            if person.is_terminated() {
+                continue;
+            }
+
+            let group = groups.iter_mut()
+                .find(|g| self.collation.same_name(g[0].pointer.borrow().name(), person.name()));
+
+            match group {
+                Some(group) => group.push(entry),
+                None => groups.push(vec![entry]),
+            }
+        }
+
+        groups.retain(|g| g.len() > 1);
+        groups
+    }
+
+    /// Every department's alias list entry, in order of main alias.
+    pub fn dept_list(&self) -> impl ExactSizeIterator<Item = &DepartmentAlias> {
+        self.dept_aliases.iter()
+    }
+
+    /// Every person's alias list entry, current or former, in order of main alias.
+    pub fn employee_list(&self) -> impl ExactSizeIterator<Item = &PersonAlias> {
+        self.person_aliases.iter()
     }
 
     /// Add an existing employee to a Department.  Employee must have already been entered into
     /// ProgramData. There is no need to add a person to their initial department, this is done
-    /// automatically upon inserting the Person into ProgramData.
+    /// automatically upon inserting the Person into ProgramData.  A transfer dated after today is
+    /// scheduled with schedule_transfer() rather than made straight away.
     pub fn add_to_dept(&mut self, person_alias: &str, dept_alias: &str, date: Option<NaiveDate>) -> Result<()> {
+        let today = Local::today().naive_local();
+
+        if let Some(date) = date.filter(|d| *d > today) {
+            self.schedule_transfer(person_alias, dept_alias, date)?;
+            return Ok(());
+        }
+
+        let person = self.personnel.get(person_alias)
+            .ok_or(DataError::NoSuchPerson)?;
+
+        let department = self.departments.get(dept_alias)
+            .ok_or(DataError::NoSuchDept)?;
+
+        person.borrow_mut()
+            .transfer(Rc::clone(department), date.unwrap_or(today))?;
+
+        Ok(())
+    }
+
+    /// Hold a transfer until the given date, returning its id.  It is carried out by
+    /// apply_due_transfers() once the date arrives.  A transfer already scheduled for the same
+    /// person on the same date is replaced.
+    pub fn schedule_transfer(&mut self, person_alias: &str, dept_alias: &str, date: NaiveDate) -> Result<u32> {
+        let person = self.personnel.get(person_alias)
+            .ok_or(DataError::NoSuchPerson)?;
+
+        let department = self.departments.get(dept_alias)
+            .ok_or(DataError::NoSuchDept)?;
+
+        {
+            let person = person.borrow();
+
+            if person.is_terminated() {
+                return Err(DataError::Person(PersonError::Transfer(TransferErr::Terminated)));
+            }
+
+            if date <= person.date_of_hire() {
+                return Err(DataError::TransferDate(date));
+            }
+        }
+
+        let (person, department) = (Rc::clone(person), Rc::clone(department));
+        self.pending.retain(|t| !(Rc::ptr_eq(&t.person(), &person) && t.date() == date));
+
+        self.transfer_count += 1;
+        let id = self.transfer_count;
+        self.insert_transfer(PendingTransfer::new(id, person, department, date));
+
+        Ok(id)
+    }
+
+    /// Keep pending transfers in date order, and in the order they were entered on the same date.
+    fn insert_transfer(&mut self, transfer: PendingTransfer) {
+        let index = self.pending.iter()
+            .position(|t| (t.date(), t.id()) > (transfer.date(), transfer.id()))
+            .unwrap_or(self.pending.len());
+
+        self.pending.insert(index, transfer);
+    }
+
+    /// Add a transfer with the id it was saved under.  Used when loading saved data.
+    pub(crate) fn restore_transfer(&mut self, transfer: PendingTransfer) {
+        self.transfer_count = self.transfer_count.max(transfer.id());
+        self.insert_transfer(transfer);
+    }
+
+    /// Transfers waiting for their effective date, earliest first.
+    pub fn pending_transfers(&self) -> &Vec<PendingTransfer> {
+        &self.pending
+    }
+
+    /// Drop a pending transfer, returning it.
+    pub fn cancel_transfer(&mut self, id: u32) -> Result<PendingTransfer> {
+        match self.pending.iter().position(|t| t.id() == id) {
+            Some(i) => Ok(self.pending.remove(i)),
+            None => Err(DataError::NoSuchTransfer(id)),
+        }
+    }
+
+    /// Carry out every pending transfer dated on or before `today`, in date order, returning each
+    /// with its outcome.  Transfers that fail, e.g. because the person has moved there already,
+    /// are dropped all the same.
+    pub fn apply_due_transfers(&mut self, today: NaiveDate) -> Vec<(PendingTransfer, Result<()>)> {
+        let due = self.pending.iter().take_while(|t| t.date() <= today).count();
+
+        self.pending.drain(..due).collect::<Vec<PendingTransfer>>().into_iter()
+            .map(|t| {
+                let result = t.person().borrow_mut()
+                    .transfer(t.department(), t.date())
+                    .map_err(DataError::from);
+                (t, result)
+            })
+            .collect()
+    }
+
+    /// Give part of a person's time to a department besides their primary one, from the given date
+    /// or today.  `allocation` is a percentage; see Person::add_membership().
+    pub fn add_membership(&mut self, person_alias: &str, dept_alias: &str, allocation: u32, date: Option<NaiveDate>) -> Result<()> {
+        let person = self.personnel.get(person_alias)
+            .ok_or(DataError::NoSuchPerson)?;
+
+        let department = self.departments.get(dept_alias)
+            .ok_or(DataError::NoSuchDept)?;
+
+        let start = match date {
+            Some(d) => d,
+            None => Local::today().naive_local(),
+        };
+
+        person.borrow_mut()
+            .add_membership(Rc::clone(department), allocation, start)?;
+
+        Ok(())
+    }
+
+    /// End a person's secondary membership of a department on the given date or today.
+    pub fn end_membership(&mut self, person_alias: &str, dept_alias: &str, date: Option<NaiveDate>) -> Result<()> {
         let person = self.personnel.get(person_alias)
             .ok_or(DataError::NoSuchPerson)?;
 
         let department = self.departments.get(dept_alias)
             .ok_or(DataError::NoSuchDept)?;
 
-        let transfer_date = match date {
+        let end = match date {
+            Some(d) => d,
+            None => Local::today().naive_local(),
+        };
+
+        person.borrow_mut()
+            .end_membership(department, end)?;
+
+        Ok(())
+    }
+
+    /// Record a change of title, level or FTE for a person, effective on the given date or today.
+    pub fn change_position(&mut self, person_alias: &str, position: Position, date: Option<NaiveDate>) -> Result<()> {
+        let person = self.personnel.get(person_alias)
+            .ok_or(DataError::NoSuchPerson)?;
+
+        let effective_date = match date {
             Some(d) => d,
             None => Local::today().naive_local(),
         };
 
         person.borrow_mut()
-            .transfer(Rc::clone(department), transfer_date)?;
+            .change_position(position, effective_date)?;
+
+        Ok(())
+    }
+
+    /// Record a change of pay for a person, effective on the given date or today.
+    pub fn change_compensation(&mut self, person_alias: &str, pay: Money, basis: PayBasis, reason: &str,
+                               date: Option<NaiveDate>) -> Result<()> {
+        let person = self.personnel.get(person_alias)
+            .ok_or(DataError::NoSuchPerson)?;
+
+        let date = match date {
+            Some(d) => d,
+            None => Local::today().naive_local(),
+        };
+
+        person.borrow_mut()
+            .change_compensation(CompensationEntry { pay, basis, date, reason: String::from(reason) })?;
+
+        Ok(())
+    }
+
+    /// The custom fields people may have.
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// Declare a new custom field.
+    pub fn define_field(&mut self, name: &str, kind: FieldType) -> Result<()> {
+        self.schema.define(name, kind)?;
+
+        Ok(())
+    }
+
+    /// Remove a custom field, and its value from everyone who had one.
+    pub fn remove_field(&mut self, name: &str) -> Result<()> {
+        let def = self.schema.remove(name)?;
+
+        for entry in &self.person_aliases {
+            entry.pointer.borrow_mut().set_custom_field(&def.name, None);
+        }
+
+        Ok(())
+    }
+
+    /// Set a person's email, phone or office, or one of their custom fields, from text as typed
+    /// by a user.  Dates are read in the configured date format.  None clears the field.
+    pub fn set_field(&mut self, person_alias: &str, field: &str, value: Option<&str>) -> Result<()> {
+        let person = self.personnel.get(person_alias)
+            .ok_or(DataError::NoSuchPerson)?;
+
+        if CONTACT_FIELDS.contains(&field.to_lowercase().as_str()) {
+            person.borrow_mut().contact_mut().set(field, value)?;
+            return Ok(());
+        }
+
+        let def = self.schema.get(field)
+            .ok_or_else(|| FieldError::UnknownField(String::from(field)))?;
+
+        let value = match value {
+            Some(text) => Some(def.parse_value(text, &self.date_format)?),
+            None => None,
+        };
+
+        person.borrow_mut().set_custom_field(&def.name, value);
 
         Ok(())
     }
 
+    /// Every location, sorted by code.
+    pub fn locations(&self) -> &Vec<Location> {
+        &self.locations
+    }
+
+    /// Find a location by code, ignoring case.
+    pub fn location(&self, code: &str) -> Option<&Location> {
+        self.locations.iter().find(|l| l.code.eq_ignore_ascii_case(code))
+    }
+
+    pub fn add_location(&mut self, code: &str, name: &str) -> Result<()> {
+        if !valid_code(code) {
+            return Err(DataError::InvalidCode(String::from(code)));
+        }
+
+        if self.location(code).is_some() {
+            return Err(DataError::CodeInUse(String::from(code)));
+        }
+
+        self.locations.push(Location { code: String::from(code), name: String::from(name) });
+        self.locations.sort_by(|a, b| a.code.cmp(&b.code));
+
+        Ok(())
+    }
+
+    /// Remove a location that no department or person is assigned to.
+    pub fn remove_location(&mut self, code: &str) -> Result<()> {
+        let index = self.locations.iter().position(|l| l.code.eq_ignore_ascii_case(code))
+            .ok_or_else(|| DataError::NoSuchCode(String::from(code)))?;
+        let code = &self.locations[index].code;
+
+        let in_use = self.dept_aliases.iter().any(|d| d.pointer.borrow().location() == Some(code))
+            || self.person_aliases.iter().any(|p| p.pointer.borrow().location() == Some(code));
+        if in_use {
+            return Err(DataError::CodeAssigned(code.clone()));
+        }
+
+        self.locations.remove(index);
+
+        Ok(())
+    }
+
+    /// Assign a location to the department or person known by `alias`, or clear it with None.
+    /// A person's location overrides their department's.
+    pub fn assign_location(&mut self, alias: &str, code: Option<&str>) -> Result<()> {
+        let code = match code {
+            Some(code) => Some(self.location(code)
+                .ok_or_else(|| DataError::NoSuchCode(String::from(code)))?
+                .code.clone()),
+            None => None,
+        };
+
+        match self.resolve(alias) {
+            Some(AliasTarget::Department(department)) => department.borrow_mut().set_location(code),
+            Some(AliasTarget::Person(person)) => person.borrow_mut().set_location(code),
+            None => return Err(DataError::NoSuchAlias(String::from(alias))),
+        };
+
+        Ok(())
+    }
+
+    /// The location that applies to a person: their own if one is set, otherwise their current
+    /// department's.
+    pub fn location_of(&self, person: &Person) -> Option<&Location> {
+        self.group_code(person, &person.department().borrow(), Grouping::Location)
+            .and_then(|code| self.location(&code))
+    }
+
+    /// Every cost center, sorted by code.
+    pub fn cost_centers(&self) -> &Vec<CostCenter> {
+        &self.cost_centers
+    }
+
+    /// Find a cost center by code, ignoring case.
+    pub fn cost_center(&self, code: &str) -> Option<&CostCenter> {
+        self.cost_centers.iter().find(|c| c.code.eq_ignore_ascii_case(code))
+    }
+
+    pub fn add_cost_center(&mut self, code: &str, name: &str) -> Result<()> {
+        if !valid_code(code) {
+            return Err(DataError::InvalidCode(String::from(code)));
+        }
+
+        if self.cost_center(code).is_some() {
+            return Err(DataError::CodeInUse(String::from(code)));
+        }
+
+        self.cost_centers.push(CostCenter { code: String::from(code), name: String::from(name) });
+        self.cost_centers.sort_by(|a, b| a.code.cmp(&b.code));
+
+        Ok(())
+    }
+
+    /// Remove a cost center that no department or person is assigned to.
+    pub fn remove_cost_center(&mut self, code: &str) -> Result<()> {
+        let index = self.cost_centers.iter().position(|c| c.code.eq_ignore_ascii_case(code))
+            .ok_or_else(|| DataError::NoSuchCode(String::from(code)))?;
+        let code = &self.cost_centers[index].code;
+
+        let in_use = self.dept_aliases.iter().any(|d| d.pointer.borrow().cost_center() == Some(code))
+            || self.person_aliases.iter().any(|p| p.pointer.borrow().cost_center() == Some(code));
+        if in_use {
+            return Err(DataError::CodeAssigned(code.clone()));
+        }
+
+        self.cost_centers.remove(index);
+
+        Ok(())
+    }
+
+    /// Assign a cost center to the department or person known by `alias`, or clear it with None.
+    /// A person's cost center overrides their department's.
+    pub fn assign_cost_center(&mut self, alias: &str, code: Option<&str>) -> Result<()> {
+        let code = match code {
+            Some(code) => Some(self.cost_center(code)
+                .ok_or_else(|| DataError::NoSuchCode(String::from(code)))?
+                .code.clone()),
+            None => None,
+        };
+
+        match self.resolve(alias) {
+            Some(AliasTarget::Department(department)) => department.borrow_mut().set_cost_center(code),
+            Some(AliasTarget::Person(person)) => person.borrow_mut().set_cost_center(code),
+            None => return Err(DataError::NoSuchAlias(String::from(alias))),
+        };
+
+        Ok(())
+    }
+
+    /// The cost center that applies to a person: their own if one is set, otherwise their
+    /// current department's.
+    pub fn cost_center_of(&self, person: &Person) -> Option<&CostCenter> {
+        self.group_code(person, &person.department().borrow(), Grouping::CostCenter)
+            .and_then(|code| self.cost_center(&code))
+    }
+
+    /// The location or cost center code that applies to a person while in `department`.
+    pub fn group_code(&self, person: &Person, department: &Department, grouping: Grouping) -> Option<String> {
+        match grouping {
+            Grouping::Location => person.location().or(department.location()).cloned(),
+            Grouping::CostCenter => person.cost_center().or(department.cost_center()).cloned(),
+        }
+    }
+
+    /// The name of a location or cost center, given its code.
+    pub fn group_name(&self, code: &str, grouping: Grouping) -> Option<&String> {
+        match grouping {
+            Grouping::Location => self.location(code).map(|l| &l.name),
+            Grouping::CostCenter => self.cost_center(code).map(|c| &c.name),
+        }
+    }
+
+    /// Give a department a new name.  Its aliases are unchanged.
+    pub fn rename_dept(&mut self, dept_alias: &str, name: &str) -> Result<()> {
+        let department = self.departments.get(dept_alias)
+            .ok_or(DataError::NoSuchDept)?;
+
+        if name.trim().is_empty() {
+            return Err(DataError::InvalidName);
+        }
+
+        department.borrow_mut().set_name(name.trim());
+
+        Ok(())
+    }
+
+    /// Set or clear a department's yearly payroll budget.
+    pub fn set_budget(&mut self, dept_alias: &str, budget: Option<Money>) -> Result<()> {
+        let department = self.departments.get(dept_alias)
+            .ok_or(DataError::NoSuchDept)?;
+
+        department.borrow_mut().set_budget(budget);
+
+        Ok(())
+    }
+
+    /// Record a change of status for a person, effective on the given date or today.  Changing to
+    /// Terminated is the same as calling terminate().
+    pub fn change_status(&mut self, person_alias: &str, status: Status, date: Option<NaiveDate>) -> Result<()> {
+        if status == Status::Terminated {
+            return self.terminate(person_alias, date);
+        }
+
+        let person = self.personnel.get(person_alias)
+            .ok_or(DataError::NoSuchPerson)?;
+
+        let date = match date {
+            Some(d) => d,
+            None => Local::today().naive_local(),
+        };
+
+        person.borrow_mut()
+            .change_status(status, date)?;
+
+        Ok(())
+    }
+
+    pub fn set_employment_type(&mut self, person_alias: &str, employment_type: EmploymentType) -> Result<()> {
+        let person = self.personnel.get(person_alias)
+            .ok_or(DataError::NoSuchPerson)?;
+
+        person.borrow_mut().set_employment_type(employment_type);
+
+        Ok(())
+    }
+
+    /// Record that a person has left the company.  They are dropped from their department's list
+    /// of employees and no longer counted in employee_count, but remain in ProgramData so that
+    /// their history is available for reporting.
+    pub fn terminate(&mut self, person_alias: &str, date: Option<NaiveDate>) -> Result<()> {
+        let person = self.personnel.get(person_alias)
+            .ok_or(DataError::NoSuchPerson)?;
+
+        let termination_date = match date {
+            Some(d) => d,
+            None => Local::today().naive_local(),
+        };
+
+        person.borrow_mut()
+            .terminate(termination_date)?;
+
+        let person = Rc::clone(person);
+        self.pending.retain(|t| !Rc::ptr_eq(&t.person(), &person));
+
+        self.employee_count -= 1;
+
+        Ok(())
+    }
+
+    /// The format used to read and write dates in the user interface.
+    pub fn date_format(&self) -> &DateFormat {
+        &self.date_format
+    }
+
+    pub fn set_date_format(&mut self, format: DateFormat) {
+        self.date_format = format;
+    }
+
+    /// The rules used to put names and aliases in order, for department employee lists, the
+    /// department list and all listings.
+    pub fn collation(&self) -> Collation {
+        self.collation
+    }
+
+    /// Change the name ordering, re-sorting every department's employee list and the department
+    /// list to match.
+    pub fn set_collation(&mut self, collation: Collation) {
+        self.collation = collation;
+
+        self.dept_aliases.rekey(|entry| collation.text_key(entry.alias()));
+        self.person_aliases.rekey(|entry| collation.text_key(entry.alias()));
+
+        for entry in &self.dept_aliases {
+            entry.pointer.borrow_mut().set_collation(collation);
+        }
+    }
+
+    /// Salted hash of the passphrase needed to take the HR role in the text interface, if one
+    /// has been set.  See compensation::hash_passphrase().
+    pub fn hr_passphrase(&self) -> Option<&String> {
+        self.hr_passphrase.as_ref()
+    }
+
+    /// Set or clear the HR passphrase.  Only its hash is kept.
+    pub fn set_hr_passphrase(&mut self, passphrase: Option<&str>) {
+        self.hr_passphrase = passphrase.map(hash_passphrase);
+    }
+
+    pub(crate) fn restore_hr_passphrase(&mut self, hash: Option<String>) {
+        self.hr_passphrase = hash;
+    }
+
+    /// Whether `passphrase` matches the HR passphrase.  Nothing matches if none has been set.  A
+    /// hash kept by an earlier version, without a salt, is replaced once the passphrase matches.
+    pub fn check_hr_passphrase(&mut self, passphrase: &str) -> bool {
+        let (matches, legacy) = match &self.hr_passphrase {
+            Some(hash) => (check_passphrase(passphrase, hash), is_legacy_hash(hash)),
+            None => return false,
+        };
+
+        if matches && legacy {
+            self.set_hr_passphrase(Some(passphrase));
+        }
+
+        matches
+    }
+
+    /// Whether `alias` already names a person or department.
+    pub fn alias_in_use(&self, alias: &str) -> bool {
+        self.personnel.contains_key(alias) || self.departments.contains_key(alias)
+    }
+
+    /// Find the person or department known by `alias`, whether it is their main alias or not.
+    pub fn resolve(&self, alias: &str) -> Option<AliasTarget> {
+        if let Some(person) = self.personnel.get(alias) {
+            return Some(AliasTarget::Person(Rc::clone(person)));
+        }
+
+        self.departments.get(alias)
+            .map(|department| AliasTarget::Department(Rc::clone(department)))
+    }
+
+    /// The alias list entry for the person or department known by `alias`.
+    pub fn alias_entry(&self, alias: &str) -> Option<AliasEntry<'_>> {
+        match self.resolve(alias)? {
+            AliasTarget::Person(person) => self.person_aliases.iter()
+                .find(|entry| Rc::ptr_eq(&entry.pointer, &person))
+                .map(AliasEntry::Person),
+            AliasTarget::Department(department) => self.dept_aliases.iter()
+                .find(|entry| Rc::ptr_eq(&entry.pointer, &department))
+                .map(AliasEntry::Department),
+        }
+    }
+
+    /// Give the person or department known by `existing` another alias, such as a nickname or
+    /// email handle.
+    pub fn add_alias(&mut self, existing: &str, alias: &str) -> Result<()> {
+        check_alias(alias)?;
+
+        if self.alias_in_use(alias) {
+            return Err(DataError::AliasInUse(String::from(alias)));
+        }
+
+        match self.resolve(existing).ok_or_else(|| DataError::NoSuchAlias(String::from(existing)))? {
+            AliasTarget::Person(person) => {
+                let entry = self.person_aliases.iter_mut()
+                    .find(|entry| Rc::ptr_eq(&entry.pointer, &person))
+                    .expect("person missing from alias list");
+                entry.others.push(String::from(alias));
+                self.personnel.insert(String::from(alias), person);
+            },
+            AliasTarget::Department(department) => {
+                let entry = self.dept_aliases.iter_mut()
+                    .find(|entry| Rc::ptr_eq(&entry.pointer, &department))
+                    .expect("department missing from alias list");
+                entry.others.push(String::from(alias));
+                self.departments.insert(String::from(alias), department);
+            },
+        };
+
+        Ok(())
+    }
+
+    /// Replace one alias of a person or department with another.  Renaming the main alias keeps
+    /// it the main alias.
+    pub fn rename_alias(&mut self, old: &str, new: &str) -> Result<()> {
+        check_alias(new)?;
+
+        if self.alias_in_use(new) {
+            return Err(DataError::AliasInUse(String::from(new)));
+        }
+
+        let collation = self.collation;
+        let rename = |main: &mut String, others: &mut Vec<String>| {
+            if main == old {
+                *main = String::from(new);
+            } else if let Some(other) = others.iter_mut().find(|a| *a == old) {
+                *other = String::from(new);
+            }
+        };
+
+        match self.resolve(old).ok_or_else(|| DataError::NoSuchAlias(String::from(old)))? {
+            AliasTarget::Person(person) => {
+                if let Some(entry) = self.person_aliases.iter_mut().find(|entry| Rc::ptr_eq(&entry.pointer, &person)) {
+                    rename(&mut entry.alias, &mut entry.others);
+                }
+                self.person_aliases.rekey(|entry| collation.text_key(entry.alias()));
+                self.personnel.remove(old);
+                self.personnel.insert(String::from(new), person);
+            },
+            AliasTarget::Department(department) => {
+                if let Some(entry) = self.dept_aliases.iter_mut().find(|entry| Rc::ptr_eq(&entry.pointer, &department)) {
+                    rename(&mut entry.alias, &mut entry.others);
+                }
+                self.dept_aliases.rekey(|entry| collation.text_key(entry.alias()));
+                self.departments.remove(old);
+                self.departments.insert(String::from(new), department);
+            },
+        };
+
+        Ok(())
+    }
+
+    /// Remove one of the other aliases of a person or department.  A main alias can only be
+    /// renamed, not removed.
+    pub fn remove_alias(&mut self, alias: &str) -> Result<()> {
+        let is_main = match self.alias_entry(alias) {
+            Some(entry) => entry.alias() == alias,
+            None => return Err(DataError::NoSuchAlias(String::from(alias))),
+        };
+
+        if is_main {
+            return Err(DataError::MainAlias(String::from(alias)));
+        }
+
+        if let Some(person) = self.personnel.remove(alias) {
+            if let Some(entry) = self.person_aliases.iter_mut().find(|entry| Rc::ptr_eq(&entry.pointer, &person)) {
+                entry.others.retain(|a| a != alias);
+            }
+        } else if let Some(department) = self.departments.remove(alias) {
+            if let Some(entry) = self.dept_aliases.iter_mut().find(|entry| Rc::ptr_eq(&entry.pointer, &department)) {
+                entry.others.retain(|a| a != alias);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Every department, keyed by each of its aliases.
     pub fn departments(&self) -> &HashMap<String, Rc<RefCell<Department>>> {
         &self.departments
     }
+
+    /// Every person, current or former, keyed by each of their aliases.
+    pub fn personnel(&self) -> &HashMap<String, Rc<RefCell<Person>>> {
+        &self.personnel
+    }
+
+    /// Find the main alias of a department.
+    pub fn dept_alias_of(&self, department: &Rc<RefCell<Department>>) -> Option<&String> {
+        self.dept_aliases.iter()
+            .find(|entry| Rc::ptr_eq(entry.borrow_pointer(), department))
+            .map(|entry| entry.alias())
+    }
+
+    /// Parse and run a query written in the roster query language, e.g.
+    /// "people where dept = eng and hired < 2018-01-01 order by last".  See the query module for
+    /// the full syntax.  Dates in the query are read using the configured date format.
+    pub fn query(&self, text: &str) -> Result<QueryResult> {
+        let query = Query::parse(text, &self.date_format, &self.schema)?;
+
+        Ok(query.run(self))
+    }
+
+    /// Compute headcount, FTE, hires, transfers in and out, terminations and turnover for every
+    /// department, for each month or quarter overlapping the given date range.  If
+    /// `exclude_leave` is set, people on leave are left out of headcount and FTE.
+    pub fn headcount_report(&self, period: Period, from: NaiveDate, to: NaiveDate, exclude_leave: bool) -> HeadcountReport {
+        HeadcountReport::build(self, period, from, to, exclude_leave)
+    }
+
+    /// Group everyone employed on the given date by department and job title, optionally for a
+    /// single department.
+    pub fn position_report(&self, as_of: NaiveDate, department: Option<&str>) -> PositionReport {
+        PositionReport::build(self, as_of, department)
+    }
+
+    /// Compute the yearly cost of everyone's pay on the given date for each department, along
+    /// with each department's budget.
+    pub fn payroll_report(&self, as_of: NaiveDate) -> PayrollReport {
+        PayrollReport::build(self, as_of)
+    }
+
+    /// Group everyone employed on the given date by location or cost center.
+    pub fn group_report(&self, grouping: Grouping, as_of: NaiveDate) -> GroupReport {
+        GroupReport::build(self, grouping, as_of)
+    }
+
+    /// Compute company and department tenure for everyone employed on the given date.  The
+    /// report also provides seniority rankings, upcoming anniversaries and a tenure histogram.
+    pub fn tenure_report(&self, as_of: NaiveDate) -> TenureReport {
+        TenureReport::build(self, as_of)
+    }
+
+    /// Search people and departments for a partial or misspelled term.  Matching is
+    /// case-insensitive and checks person aliases and each part of their Name, as well as
+    /// department aliases and full names.  Each person or department appears at most once, under
+    /// its best matching field, and results are ranked from best to worst match.
+    pub fn search(&self, text: &str) -> Vec<SearchResult> {
+        let mut searcher = Searcher::new(text, self.collation);
+
+        for entry in &self.person_aliases {
+            searcher.person(entry.alias(), &entry.others, &entry.pointer);
+        }
+
+        for entry in &self.dept_aliases {
+            searcher.department(entry.alias(), &entry.others, entry.borrow_pointer());
+        }
+
+        searcher.finish()
+    }
+
+    /// Check that everything held in more than one place agrees: each current employee is listed
+    /// once, in their own department, and the lists are sorted; each person's department matches
+    /// their latest department history entry; every alias leads to a listed person or department;
+    /// and the counts match the people and departments held.  Returns every problem found, or an
+    /// empty list if there are none.
+    pub fn verify(&self) -> Vec<Violation> {
+        let mut violations = Vec::new();
+
+        let person_entries: HashMap<*const RefCell<Person>, &PersonAlias> = self.person_aliases.iter()
+            .map(|entry| (Rc::as_ptr(&entry.pointer), entry))
+            .collect();
+        let dept_entries: HashMap<*const RefCell<Department>, &DepartmentAlias> = self.dept_aliases.iter()
+            .map(|entry| (Rc::as_ptr(&entry.pointer), entry))
+            .collect();
+
+        let mut aliases: Vec<&String> = self.personnel.keys().chain(self.departments.keys()).collect();
+        aliases.sort();
+        for alias in aliases {
+            let live = match (self.personnel.get(alias), self.departments.get(alias)) {
+                (Some(person), None) => person_entries.get(&Rc::as_ptr(person))
+                    .is_some_and(|entry| entry.aliases().contains(&alias)),
+                (None, Some(department)) => dept_entries.get(&Rc::as_ptr(department))
+                    .is_some_and(|entry| entry.aliases().contains(&alias)),
+                _ => false,
+            };
+
+            if !live {
+                violations.push(Violation::DeadAlias { alias: alias.clone() });
+            }
+        }
+
+        for entry in &self.person_aliases {
+            for alias in entry.aliases() {
+                if !self.personnel.get(alias).is_some_and(|p| Rc::ptr_eq(p, &entry.pointer)) {
+                    violations.push(Violation::BrokenAlias { alias: alias.clone() });
+                }
+            }
+        }
+
+        for entry in &self.dept_aliases {
+            for alias in entry.aliases() {
+                if !self.departments.get(alias).is_some_and(|d| Rc::ptr_eq(d, &entry.pointer)) {
+                    violations.push(Violation::BrokenAlias { alias: alias.clone() });
+                }
+            }
+        }
+
+        let dept_name = |department: &Rc<RefCell<Department>>| dept_entries.get(&Rc::as_ptr(department))
+            .map_or_else(|| format!("#{}", department.borrow().id()), |entry| entry.alias.clone());
+
+        for entry in &self.person_aliases {
+            let person = entry.pointer.borrow();
+            let history = person.department_history();
+
+            if history.windows(2).any(|pair| pair[0].date() > pair[1].date()) {
+                violations.push(Violation::HistoryUnsorted { person: entry.alias.clone() });
+            }
+
+            if let Some(latest) = history.iter().max_by_key(|e| e.date()) {
+                if !Rc::ptr_eq(&latest.department(), &person.department()) {
+                    violations.push(Violation::HistoryMismatch {
+                        person: entry.alias.clone(),
+                        current: dept_name(&person.department()),
+                        history: dept_name(&latest.department()),
+                    });
+                }
+            }
+
+            if let Some(left) = person.termination_date() {
+                if left < person.date_of_hire() {
+                    violations.push(Violation::LeftBeforeHire { person: entry.alias.clone() });
+                } else if history.iter().any(|e| e.date() > left) {
+                    violations.push(Violation::LeftBeforeTransfer { person: entry.alias.clone() });
+                }
+            }
+        }
+
+        let departments: Vec<(String, Rc<RefCell<Department>>)> = self.dept_aliases.iter()
+            .map(|entry| (entry.alias.clone(), entry.clone_pointer()))
+            .collect();
+        let people: Vec<(String, Rc<RefCell<Person>>)> = self.person_aliases.iter()
+            .map(|entry| (entry.alias.clone(), entry.pointer()))
+            .collect();
+        check_lists(&departments, &people, &mut violations);
+
+        let employed = self.person_aliases.iter()
+            .filter(|entry| !entry.pointer.borrow().is_terminated())
+            .count() as u32;
+        if employed != self.employee_count {
+            violations.push(Violation::EmployeeCount { recorded: self.employee_count, actual: employed });
+        }
+
+        let highest = self.dept_aliases.iter().map(|entry| entry.pointer.borrow().id()).max().unwrap_or(0);
+        if highest > self.department_count {
+            violations.push(Violation::DepartmentCount { recorded: self.department_count, highest });
+        }
+
+        let highest = self.person_aliases.iter().map(|entry| entry.pointer.borrow().id()).max().unwrap_or(0);
+        if highest > self.person_count {
+            violations.push(Violation::PersonCount { recorded: self.person_count, highest });
+        }
+
+        violations
+    }
+
+    /// Fix the problems found by verify().  The alias lists, each person's department history and
+    /// memberships, and the ids in use are taken as correct, and everything else is rebuilt from
+    /// them.  Returns the problems found before repairing.
+    pub fn repair(&mut self) -> Vec<Violation> {
+        let violations = self.verify();
+        if violations.is_empty() {
+            return violations;
+        }
+
+        self.personnel.clear();
+        for entry in &self.person_aliases {
+            for alias in entry.aliases() {
+                self.personnel.entry(alias.clone()).or_insert_with(|| Rc::clone(&entry.pointer));
+            }
+        }
+
+        self.departments.clear();
+        for entry in &self.dept_aliases {
+            for alias in entry.aliases() {
+                if !self.personnel.contains_key(alias) {
+                    self.departments.entry(alias.clone()).or_insert_with(|| Rc::clone(&entry.pointer));
+                }
+            }
+        }
+
+        // Aliases that lost out to another person or department above can't be kept.
+        for entry in self.person_aliases.iter_mut() {
+            let (personnel, pointer) = (&self.personnel, &entry.pointer);
+            entry.others.retain(|a| personnel.get(a).is_some_and(|p| Rc::ptr_eq(p, pointer)));
+        }
+        for entry in self.dept_aliases.iter_mut() {
+            let (departments, pointer) = (&self.departments, &entry.pointer);
+            entry.others.retain(|a| departments.get(a).is_some_and(|d| Rc::ptr_eq(d, pointer)));
+        }
+
+        for entry in &self.dept_aliases {
+            entry.pointer.borrow_mut().clear_lists();
+        }
+
+        for entry in &self.person_aliases {
+            let mut person = entry.pointer.borrow_mut();
+            person.department_history_mut().sort_by_key(|e| e.date());
+            person.reset_department();
+        }
+
+        self.employee_count = 0;
+        for entry in &self.person_aliases {
+            let person = entry.pointer.borrow();
+            if person.is_terminated() {
+                continue;
+            }
+
+            // Departments not in the alias lists can't be cleared above, so may still list them.
+            let department = person.department();
+            let mut department = department.borrow_mut();
+            if department.find_employee(&person).is_none() {
+                let _ = department.insert_employee(Rc::clone(&entry.pointer), &person);
+            }
+            drop(department);
+
+            for membership in person.current_memberships() {
+                let _ = membership.department().borrow_mut().insert_member(Rc::clone(&entry.pointer), &person);
+            }
+
+            self.employee_count += 1;
+        }
+
+        for entry in &self.dept_aliases {
+            let id = entry.pointer.borrow().id();
+            self.department_count = self.department_count.max(id);
+        }
+        for entry in &self.person_aliases {
+            let id = entry.pointer.borrow().id();
+            self.person_count = self.person_count.max(id);
+        }
+
+        violations
+    }
 }
 
 #[derive(Debug)]
@@ -156,6 +1239,43 @@ pub enum DataError {
     NoSuchPerson,
     Person(PersonError),
     Department(DeptErr),
+    Query(QueryError),
+    Storage(StorageError),
+    Field(FieldError),
+    /// A location or cost center with this code already exists.
+    CodeInUse(String),
+    NoSuchCode(String),
+    /// The location or cost center is still assigned to a department or person.
+    CodeAssigned(String),
+    InvalidCode(String),
+    AliasInUse(String),
+    NoSuchAlias(String),
+    /// Main aliases can be renamed but not removed.
+    MainAlias(String),
+    InvalidAlias(String),
+    /// Department names can't be blank.
+    InvalidName,
+    NoSuchTransfer(u32),
+    /// Transfers can't be scheduled on or before the date of hire.
+    TransferDate(NaiveDate),
+}
+
+impl From<StorageError> for DataError {
+    fn from(error: StorageError) -> DataError {
+        DataError::Storage(error)
+    }
+}
+
+impl From<FieldError> for DataError {
+    fn from(error: FieldError) -> DataError {
+        DataError::Field(error)
+    }
+}
+
+impl From<QueryError> for DataError {
+    fn from(error: QueryError) -> DataError {
+        DataError::Query(error)
+    }
 }
 
 impl From<DeptErr> for DataError {
@@ -179,20 +1299,143 @@ impl fmt::Display for DataError {
             AddPerson => write!(f, "Could not add Person, alias in use"),
             NoSuchDept => write!(f, "Could not find department matching query"),
             NoSuchPerson => write!(f, "Could not find person matching query"),
-            Person(e) => write!(f, "Error on transfer: {}", e),
+            Person(e) => write!(f, "{}", e),
             Department(e) => write!(f, "Error on add_person: {}", e),
+            Query(e) => write!(f, "Query failed: {}", e),
+            Storage(e) => write!(f, "{}", e),
+            Field(e) => write!(f, "{}", e),
+            CodeInUse(c) => write!(f, "Code \"{}\" is already in use", c),
+            NoSuchCode(c) => write!(f, "No location or cost center has code \"{}\"", c),
+            CodeAssigned(c) => write!(f, "\"{}\" is still assigned to a department or employee", c),
+            InvalidCode(c) => write!(f, "\"{}\" is not a valid code; codes must be a single word", c),
+            AliasInUse(a) => write!(f, "Alias \"{}\" is already in use", a),
+            NoSuchAlias(a) => write!(f, "No person or department is known as \"{}\"", a),
+            MainAlias(a) => write!(f, "\"{}\" is a main alias and can only be renamed", a),
+            InvalidAlias(a) => write!(f, "\"{}\" is not a valid alias; aliases must be a single word", a),
+            InvalidName => write!(f, "Department name can't be blank"),
+            NoSuchTransfer(id) => write!(f, "No pending transfer #{}", id),
+            TransferDate(date) => write!(f, "Transfer on {} would be on or before the date of hire", date),
         }
     }
 }
 
 impl Error for DataError { }
 
+/// Aliases are typed as single words in the text interface, so they can't be empty or contain
+/// spaces.
+pub(crate) fn check_alias(alias: &str) -> Result<()> {
+    if alias.is_empty() || alias.contains(char::is_whitespace) {
+        return Err(DataError::InvalidAlias(String::from(alias)));
+    }
+
+    Ok(())
+}
+
+/// The person or department an alias refers to.
+#[derive(Debug, Clone)]
+pub enum AliasTarget {
+    Person(Rc<RefCell<Person>>),
+    Department(Rc<RefCell<Department>>),
+}
+
+/// The alias list entry of a person or department, as returned by ProgramData::alias_entry().
 #[derive(Debug)]
+pub enum AliasEntry<'a> {
+    Person(&'a PersonAlias),
+    Department(&'a DepartmentAlias),
+}
+
+impl<'a> AliasEntry<'a> {
+    /// The main alias.
+    pub fn alias(&self) -> &'a String {
+        match self {
+            AliasEntry::Person(entry) => entry.alias(),
+            AliasEntry::Department(entry) => entry.alias(),
+        }
+    }
+
+    /// Every alias, the main alias first.
+    pub fn aliases(&self) -> Vec<&'a String> {
+        match self {
+            AliasEntry::Person(entry) => entry.aliases(),
+            AliasEntry::Department(entry) => entry.aliases(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct DepartmentAlias {
     alias: String,
+    others: Vec<String>,
     pointer: Rc<RefCell<Department>>,
 }
 
+/// Source of AliasIndex generations.  Numbers are never reused, even by a different ProgramData,
+/// so a generation identifies one state of one index.
+static GENERATIONS: AtomicU64 = AtomicU64::new(0);
+
+fn next_generation() -> u64 {
+    GENERATIONS.fetch_add(1, atomic::Ordering::Relaxed) + 1
+}
+
+/// The alias list entries of every person or every department, ordered by main alias under the
+/// current collation.
+#[derive(Debug)]
+struct AliasIndex<T> {
+    entries: BTreeMap<TextKey, T>,
+    /// Changes whenever the entries do, so that anything worked out from them can tell it's stale.
+    generation: u64,
+}
+
+impl<T> AliasIndex<T> {
+    fn new() -> Self {
+        AliasIndex {
+            entries: BTreeMap::new(),
+            generation: next_generation(),
+        }
+    }
+
+    /// Add an entry under the key of its main alias.  Returns false if the key is taken.
+    fn insert(&mut self, key: TextKey, entry: T) -> bool {
+        if self.entries.contains_key(&key) {
+            return false;
+        }
+
+        self.entries.insert(key, entry);
+        self.generation = next_generation();
+        true
+    }
+
+    fn contains(&self, key: &TextKey) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    fn iter(&self) -> btree_map::Values<'_, TextKey, T> {
+        self.entries.values()
+    }
+
+    fn iter_mut(&mut self) -> btree_map::ValuesMut<'_, TextKey, T> {
+        self.generation = next_generation();
+        self.entries.values_mut()
+    }
+
+    /// File every entry under a new key, after a change of collation or of a main alias.
+    fn rekey<F: Fn(&T) -> TextKey>(&mut self, key: F) {
+        let entries = std::mem::take(&mut self.entries);
+        self.entries = entries.into_values().map(|entry| (key(&entry), entry)).collect();
+        self.generation = next_generation();
+    }
+}
+
+impl<'a, T> IntoIterator for &'a AliasIndex<T> {
+    type Item = &'a T;
+    type IntoIter = btree_map::Values<'a, TextKey, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.values()
+    }
+}
+
 impl Eq for DepartmentAlias { }
 
 impl PartialEq for DepartmentAlias {
@@ -217,7 +1460,7 @@ impl PartialOrd for DepartmentAlias {
 
 impl Ord for DepartmentAlias {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.alias.cmp(&other.alias)
+        Collation::default().compare_text(&self.alias, &other.alias)
     }
 }
 
@@ -225,6 +1468,7 @@ impl DepartmentAlias {
     pub fn new(alias: &str, pointer: Rc<RefCell<Department>>) -> Self {
         DepartmentAlias {
             alias: String::from(alias),
+            others: Vec::new(),
             pointer,
         }
     }
@@ -237,9 +1481,20 @@ impl DepartmentAlias {
         &self.pointer
     }
 
+    /// The main alias.
     pub fn alias(&self) -> &String {
         &self.alias
     }
+
+    /// Aliases other than the main one, in the order they were added.
+    pub fn other_aliases(&self) -> &Vec<String> {
+        &self.others
+    }
+
+    /// Every alias, the main alias first.
+    pub fn aliases(&self) -> Vec<&String> {
+        std::iter::once(&self.alias).chain(&self.others).collect()
+    }
 }
 
 impl fmt::Display for DepartmentAlias {
@@ -248,8 +1503,10 @@ impl fmt::Display for DepartmentAlias {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct PersonAlias {
     alias: String,
+    others: Vec<String>,
     pointer: Rc<RefCell<Person>>,
 }
 
@@ -257,14 +1514,26 @@ impl PersonAlias {
     pub fn new(alias: &str, pointer: Rc<RefCell<Person>>) -> Self {
         PersonAlias {
             alias: String::from(alias),
+            others: Vec::new(),
             pointer,
         }
     }
 
+    /// The main alias.
     pub fn alias(&self) -> &String {
         &self.alias
     }
 
+    /// Aliases other than the main one, in the order they were added.
+    pub fn other_aliases(&self) -> &Vec<String> {
+        &self.others
+    }
+
+    /// Every alias, the main alias first.
+    pub fn aliases(&self) -> Vec<&String> {
+        std::iter::once(&self.alias).chain(&self.others).collect()
+    }
+
     pub fn pointer(&self) -> Rc<RefCell<Person>> {
         Rc::clone(&self.pointer)
     }
@@ -0,0 +1,182 @@
+use std::fmt::{self, Write};
+use std::error::Error;
+use std::str::FromStr;
+
+use chrono::naive::NaiveDate;
+use chrono::prelude::*;
+use chrono::Duration;
+
+/// How dates are written and read.  ISO 8601 dates (YYYY-MM-DD) and relative dates such as
+/// "today" or "+2w" are always accepted as input, whatever the format.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum DateFormat {
+    /// YYYY-MM-DD
+    Iso,
+    /// MM/DD/YYYY
+    #[default]
+    Us,
+    /// DD/MM/YYYY
+    Eu,
+    /// Any chrono strftime pattern, e.g. "%d.%m.%Y".
+    Custom(String),
+}
+
+impl DateFormat {
+    /// A custom format, if the pattern can represent and read back a full date.
+    pub fn custom(pattern: &str) -> Result<Self, DateError> {
+        let sample = NaiveDate::from_ymd(2001, 2, 3);
+
+        // Unknown specifiers, and ones a date alone can't fill in such as %H, fail to format.
+        let mut written = String::new();
+        if write!(written, "{}", sample.format(pattern)).is_err() {
+            return Err(DateError::BadPattern(String::from(pattern)));
+        }
+
+        match NaiveDate::parse_from_str(&written, pattern) {
+            Ok(date) if date == sample => Ok(DateFormat::Custom(String::from(pattern))),
+            _ => Err(DateError::BadPattern(String::from(pattern))),
+        }
+    }
+
+    /// The chrono strftime pattern for this format.
+    pub fn pattern(&self) -> &str {
+        match self {
+            DateFormat::Iso => "%Y-%m-%d",
+            DateFormat::Us => "%m/%d/%Y",
+            DateFormat::Eu => "%d/%m/%Y",
+            DateFormat::Custom(pattern) => pattern,
+        }
+    }
+
+    /// A human readable description of the format, for prompts.
+    pub fn hint(&self) -> String {
+        match self {
+            DateFormat::Iso => String::from("YYYY-MM-DD"),
+            DateFormat::Us => String::from("MM/DD/YYYY"),
+            DateFormat::Eu => String::from("DD/MM/YYYY"),
+            DateFormat::Custom(pattern) => format!("{}, e.g. {}", pattern, Local::today().naive_local().format(pattern)),
+        }
+    }
+
+    pub fn format(&self, date: &NaiveDate) -> String {
+        date.format(self.pattern()).to_string()
+    }
+
+    /// Parse a date relative to the current local date.  See parse_on().
+    pub fn parse(&self, text: &str) -> Result<NaiveDate, DateError> {
+        self.parse_on(text, Local::today().naive_local())
+    }
+
+    /// Parse a date written in this format, in ISO 8601 form, or relative to `today`.  Relative
+    /// dates are "today", "yesterday", "tomorrow", or an offset such as "+2w" or "-3m", counted
+    /// in days (d), weeks (w), months (m) or years (y).
+    pub fn parse_on(&self, text: &str, today: NaiveDate) -> Result<NaiveDate, DateError> {
+        let text = text.trim();
+
+        if let Some(date) = parse_relative(text, today) {
+            return Ok(date);
+        }
+
+        NaiveDate::parse_from_str(text, self.pattern())
+            .or_else(|_| NaiveDate::parse_from_str(text, DateFormat::Iso.pattern()))
+            .map_err(|_| DateError::Invalid(String::from(text)))
+    }
+}
+
+impl FromStr for DateFormat {
+    type Err = DateError;
+
+    /// Accepts "iso", "us", "eu", or a chrono pattern containing '%'.
+    fn from_str(s: &str) -> Result<Self, DateError> {
+        match s.to_lowercase().as_str() {
+            "iso" => Ok(DateFormat::Iso),
+            "us" => Ok(DateFormat::Us),
+            "eu" => Ok(DateFormat::Eu),
+            _ if s.contains('%') => DateFormat::custom(s),
+            _ => Err(DateError::BadPattern(String::from(s))),
+        }
+    }
+}
+
+impl fmt::Display for DateFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DateFormat::Iso => write!(f, "iso"),
+            DateFormat::Us => write!(f, "us"),
+            DateFormat::Eu => write!(f, "eu"),
+            DateFormat::Custom(pattern) => write!(f, "{}", pattern),
+        }
+    }
+}
+
+fn parse_relative(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    match text.to_lowercase().as_str() {
+        "today" => return Some(today),
+        "yesterday" => return Some(today.pred()),
+        "tomorrow" => return Some(today.succ()),
+        _ => (),
+    };
+
+    let sign = match text.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+
+    let unit = text.chars().last()?.to_ascii_lowercase();
+    if text.len() < 3 || !unit.is_ascii_alphabetic() {
+        return None;
+    }
+
+    // Only digits may come between the sign and the unit, so "+-5d" isn't a second sign.
+    let digits = &text[1..text.len() - 1];
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let amount = i32::from_str(digits).ok()?.checked_mul(sign)?;
+
+    match unit {
+        'd' => today.checked_add_signed(Duration::days(i64::from(amount))),
+        'w' => today.checked_add_signed(Duration::weeks(i64::from(amount))),
+        'm' => add_months(today, amount),
+        'y' => add_months(today, amount.checked_mul(12)?),
+        _ => None,
+    }
+}
+
+/// Add (or subtract) calendar months, clamping the day to the end of shorter months.  Returns
+/// None if the result is outside the range of dates that can be represented.
+pub fn add_months(date: NaiveDate, months: i32) -> Option<NaiveDate> {
+    let month0 = (date.year() * 12 + date.month0() as i32).checked_add(months)?;
+    let year = month0.div_euclid(12);
+    let month = month0.rem_euclid(12) as u32 + 1;
+
+    let mut day = date.day();
+    loop {
+        if let Some(d) = NaiveDate::from_ymd_opt(year, month, day) {
+            return Some(d);
+        }
+        // Every month has at least 28 days, so the year itself is out of range.
+        if day <= 28 {
+            return None;
+        }
+        day -= 1;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DateError {
+    Invalid(String),
+    BadPattern(String),
+}
+
+impl fmt::Display for DateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DateError::Invalid(text) => write!(f, "Could not read \"{}\" as a date", text),
+            DateError::BadPattern(pattern) => write!(f, "\"{}\" is not a usable date format", pattern),
+        }
+    }
+}
+
+impl Error for DateError {}
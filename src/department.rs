@@ -1,16 +1,46 @@
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{OnceCell, RefCell};
+use std::collections::BTreeMap;
 use std::fmt;
 
 use crate::personnel::Person;
+use crate::collation::{Collation, PersonKey};
+use crate::compensation::Money;
 
-#[derive(Debug, PartialEq, Eq)]
+/// People indexed by their sort key under the department's collation.
+type PersonIndex = BTreeMap<PersonKey, Rc<RefCell<Person>>>;
+
+#[derive(Debug)]
 pub struct Department {
     name: String,
     id: u32,
-    employees: Vec<Rc<RefCell<Person>>>,
+    employees: PersonIndex,
+    /// People whose primary department is elsewhere but who give part of their time here.
+    members: PersonIndex,
+    /// employees and members in order, built when first asked for after a change.
+    employee_view: OnceCell<Vec<Rc<RefCell<Person>>>>,
+    member_view: OnceCell<Vec<Rc<RefCell<Person>>>>,
+    collation: Collation,
+    budget: Option<Money>,
+    location: Option<String>,
+    cost_center: Option<String>,
+}
+
+impl PartialEq for Department {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.id == other.id
+            && self.employees == other.employees
+            && self.members == other.members
+            && self.collation == other.collation
+            && self.budget == other.budget
+            && self.location == other.location
+            && self.cost_center == other.cost_center
+    }
 }
 
+impl Eq for Department {}
+
 impl fmt::Display for Department {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Dept. #{}: {}, {} employees", self.id, self.name, self.employees.len())
@@ -22,7 +52,14 @@ impl Department {
         Department {
             name: String::from(name),
             id,
-            employees: Vec::new(),
+            employees: BTreeMap::new(),
+            members: BTreeMap::new(),
+            employee_view: OnceCell::new(),
+            member_view: OnceCell::new(),
+            collation: Collation::default(),
+            budget: None,
+            location: None,
+            cost_center: None,
         }
     }
 
@@ -30,43 +67,180 @@ impl Department {
         &self.name
     }
 
+    /// See ProgramData::rename_dept().
+    pub fn set_name(&mut self, name: &str) {
+        self.name = String::from(name);
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub(crate) fn set_id(&mut self, id: u32) {
+        self.id = id;
+    }
+
+    /// Yearly payroll budget, compared against actual cost in PayrollReport.
+    pub fn budget(&self) -> Option<&Money> {
+        self.budget.as_ref()
+    }
+
+    pub fn set_budget(&mut self, budget: Option<Money>) {
+        self.budget = budget;
+    }
+
+    /// Code of the Location this department is based at, if assigned.
+    pub fn location(&self) -> Option<&String> {
+        self.location.as_ref()
+    }
+
+    /// Set by ProgramData::assign_location(), which checks the code exists.
+    pub(crate) fn set_location(&mut self, code: Option<String>) {
+        self.location = code;
+    }
+
+    /// Code of the CostCenter this department is billed to, if assigned.
+    pub fn cost_center(&self) -> Option<&String> {
+        self.cost_center.as_ref()
+    }
+
+    /// Set by ProgramData::assign_cost_center(), which checks the code exists.
+    pub(crate) fn set_cost_center(&mut self, code: Option<String>) {
+        self.cost_center = code;
+    }
+
+    /// Employees currently listed in this department, sorted by name according to collation().
+    /// People with the same name are ordered by id.
+    pub fn employees(&self) -> &Vec<Rc<RefCell<Person>>> {
+        self.employee_view.get_or_init(|| self.employees.values().cloned().collect())
+    }
+
+    /// People with a current secondary membership of this department, sorted like employees().
+    /// See Person::add_membership().
+    pub fn members(&self) -> &Vec<Rc<RefCell<Person>>> {
+        self.member_view.get_or_init(|| self.members.values().cloned().collect())
+    }
+
+    pub fn collation(&self) -> Collation {
+        self.collation
+    }
+
+    /// Change how employees are ordered, re-sorting the existing list.  ProgramData keeps this in
+    /// step with its own collation; see ProgramData::set_collation().
+    pub fn set_collation(&mut self, collation: Collation) {
+        self.collation = collation;
+        self.employees = self.reindex(&self.employees);
+        self.members = self.reindex(&self.members);
+        self.changed();
+    }
+
+    fn reindex(&self, index: &PersonIndex) -> PersonIndex {
+        index.values()
+            .map(|p| (self.collation.person_key(&p.borrow()), Rc::clone(p)))
+            .collect()
+    }
+
+    /// Drop the ordered views after employees or members change.
+    fn changed(&mut self) {
+        self.employee_view.take();
+        self.member_view.take();
+    }
+
+    /// Whether every employee and member is filed under their current name.  A person renamed
+    /// through Person::name_mut() while listed is out of place until the lists are rebuilt; see
+    /// ProgramData::verify().
+    pub(crate) fn is_indexed(&self) -> (bool, bool) {
+        let current = |index: &PersonIndex| index.iter()
+            .all(|(key, p)| *key == self.collation.person_key(&p.borrow()));
+
+        (current(&self.employees), current(&self.members))
+    }
+
+    /// Find the key a person is listed under.  The person may be mutably borrowed by the caller
+    /// (as in Person::transfer()), so they are recognised by address instead of being borrowed
+    /// again.  Falls back to a scan in case they were renamed while listed.
+    fn search(&self, index: &PersonIndex, person: &Person) -> Option<PersonKey> {
+        let key = self.collation.person_key(person);
+
+        match index.get(&key) {
+            Some(p) if std::ptr::eq(p.as_ptr(), person) => Some(key),
+            _ => index.iter()
+                .find(|(_, p)| std::ptr::eq(p.as_ptr(), person))
+                .map(|(key, _)| key.clone()),
+        }
+    }
+
+    /// The shared pointer to an employee listed in this department.
+    pub(crate) fn find_employee(&self, employee: &Person) -> Option<Rc<RefCell<Person>>> {
+        self.search(&self.employees, employee)
+            .map(|key| Rc::clone(&self.employees[&key]))
+    }
+
+    /// Empty the employee and member lists, so ProgramData::repair() can rebuild them.
+    pub(crate) fn clear_lists(&mut self) {
+        self.employees.clear();
+        self.members.clear();
+        self.changed();
+    }
+
+    /// List a secondary member in sorted position.  See Person::add_membership().
+    pub(crate) fn insert_member(&mut self, member: Rc<RefCell<Person>>, key: &Person) -> Result<(), DeptErr> {
+        let key = self.collation.person_key(key);
+
+        if self.members.contains_key(&key) {
+            return Err(DeptErr::AddEmployee);
+        }
+
+        self.members.insert(key, member);
+        self.changed();
+        Ok(())
+    }
+
+    /// Drop a secondary member from the list.  See Person::end_membership().
+    pub(crate) fn remove_member(&mut self, member: &Person) -> Result<Rc<RefCell<Person>>, DeptErr> {
+        let key = self.search(&self.members, member).ok_or(DeptErr::RemoveEmployee)?;
+
+        self.changed();
+        Ok(self.members.remove(&key).expect("key was just found"))
+    }
+
     /// Remove an employee from this department's list of employees. Returns a Rc smart pointer
-    /// to the removed instance of employee on success.  Err(DeptErr) on failure.  This function
-    /// depends on the list of employees being sorted, which it should be by default.
+    /// to the removed instance of employee on success.  Err(DeptErr) on failure.
     /// NOTE: It is better to use Person::transfer() than to invoke this function directly, as
     /// transfer() does some additional data handling on Person to keep things consistent.
     /// See Person::transfer() source for details, know what you're doing if you decide to ignore
     /// this.
     pub fn remove_employee(&mut self, employee: &Person) -> Result<Rc<RefCell<Person>>, DeptErr> {
-        let index = self.employees.binary_search_by(|p| {
-            p.borrow().cmp(employee)
-        });
+        let key = self.search(&self.employees, employee).ok_or(DeptErr::RemoveEmployee)?;
 
-        match index {
-            Ok(i) => return Ok(self.employees.remove(i)),
-            Err(_) => return Err(DeptErr::RemoveEmployee),
-        };
+        self.changed();
+        Ok(self.employees.remove(&key).expect("key was just found"))
     }
 
     /// Add an employee to this departments list of employees.  Returns Ok(()) on success,
-    /// Err(DeptErr) on failure.  This method inserts the employee into a position in the list in
-    /// order to maintain sorting.
+    /// Err(DeptErr) if the same person (not merely someone with the same name) is already listed.
     /// NOTE: It is better to use Person::transfer() than to invoke this function directly, as
     /// transfer() does some additional data handling on Person to keep things consistent.
     /// See Person::transfer() source for details, know what you're doing if you decide to ignore
     /// this.
     pub fn add_employee(&mut self, employee: Rc<RefCell<Person>>) -> Result<(), DeptErr> {
-        let index = self.employees.binary_search_by(|p| {
-            p.borrow().cmp(&employee.borrow())
-        });
-
-        match index {
-            Ok(_) => return Err(DeptErr::AddEmployee),
-            Err(i) => {
-                self.employees.insert(i, Rc::clone(&employee));
-                return Ok(());
-            },
-        };
+        let key = Rc::clone(&employee);
+        let result = self.insert_employee(employee, &key.borrow());
+        result
+    }
+
+    /// Insert an employee in sorted position, using `key` for comparisons rather than borrowing
+    /// the RefCell.  Person::transfer() uses this while it holds a mutable borrow of itself.
+    pub(crate) fn insert_employee(&mut self, employee: Rc<RefCell<Person>>, key: &Person) -> Result<(), DeptErr> {
+        let key = self.collation.person_key(key);
+
+        if self.employees.contains_key(&key) {
+            return Err(DeptErr::AddEmployee);
+        }
+
+        self.employees.insert(key, employee);
+        self.changed();
+        Ok(())
     }
 }
 
@@ -0,0 +1,232 @@
+//! Differences between two versions of the same roster, such as two saved files edited by
+//! different people.  Departments and people are matched by id, so a change of alias is reported
+//! as such rather than as one entity removed and another added.
+use std::fmt;
+use std::path::Path;
+
+use chrono::naive::NaiveDate;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::data_handling::ProgramData;
+use crate::dates::DateFormat;
+use crate::personnel::{Name, NameOrder};
+use crate::storage::{SavedDepartment, SavedPerson, Snapshot, StorageError};
+
+/// One difference between an old and a new roster.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change {
+    DeptAdded { id: u32, alias: String, name: String },
+    DeptRemoved { id: u32, alias: String, name: String },
+    DeptRenamed { id: u32, alias: String, old: String, new: String },
+    DeptAliasChanged { id: u32, old: String, new: String },
+    /// A person in the new roster but not the old, with their department's alias.
+    Hired { id: u32, alias: String, name: String, department: String, date: NaiveDate },
+    /// A person in the old roster but missing from the new one.  People who leave are normally
+    /// kept and marked Terminated, so this means their record was deleted.
+    PersonRemoved { id: u32, alias: String, name: String },
+    Terminated { id: u32, alias: String, date: NaiveDate },
+    PersonAliasChanged { id: u32, old: String, new: String },
+    /// One part of a Name; `field` is one of NAME_FIELDS.  Missing parts are empty strings.
+    NameChanged { id: u32, alias: String, field: String, old: String, new: String },
+    /// An entry in a person's department history that the old roster doesn't have.
+    DeptEntryAdded { id: u32, alias: String, department: String, date: NaiveDate },
+}
+
+/// The parts of a Name compared by a diff, in the order they are reported.
+pub const NAME_FIELDS: [&str; 7] = ["last", "first", "middle", "prefix", "suffix", "preferred", "order"];
+
+/// The part of a name called `field` in NAME_FIELDS, as text.
+pub fn name_field(name: &Name, field: &str) -> String {
+    match field {
+        "last" => name.last.clone(),
+        "first" => name.first.clone(),
+        "middle" => name.middle.join(" "),
+        "prefix" => name.prefix.clone().unwrap_or_default(),
+        "suffix" => name.suffix.clone().unwrap_or_default(),
+        "preferred" => name.preferred.clone().unwrap_or_default(),
+        "order" => match name.order {
+            NameOrder::GivenFirst => String::from("given first"),
+            NameOrder::FamilyFirst => String::from("family first"),
+        },
+        _ => String::new(),
+    }
+}
+
+impl Change {
+    /// The change as a line of text, with dates in the given format.
+    pub fn describe(&self, date_format: &DateFormat) -> String {
+        let blank = |text: &String| if text.is_empty() { String::from("(none)") } else { text.clone() };
+
+        match self {
+            Change::DeptAdded { id, alias, name } => format!("+ department \"{}\" (#{}): {}", alias, id, name),
+            Change::DeptRemoved { id, alias, name } => format!("- department \"{}\" (#{}): {}", alias, id, name),
+            Change::DeptRenamed { id, alias, old, new } => format!("~ department \"{}\" (#{}) renamed: {} -> {}", alias, id, old, new),
+            Change::DeptAliasChanged { id, old, new } => format!("~ department #{} alias: \"{}\" -> \"{}\"", id, old, new),
+            Change::Hired { id, alias, name, department, date } => format!("+ hired \"{}\" (#{}): {}, in \"{}\", {}",
+                alias, id, name, department, date_format.format(date)),
+            Change::PersonRemoved { id, alias, name } => format!("- removed \"{}\" (#{}): {}", alias, id, name),
+            Change::Terminated { id, alias, date } => format!("- terminated \"{}\" (#{}), {}", alias, id, date_format.format(date)),
+            Change::PersonAliasChanged { id, old, new } => format!("~ employee #{} alias: \"{}\" -> \"{}\"", id, old, new),
+            Change::NameChanged { id, alias, field, old, new } => {
+                let label = match field.as_str() {
+                    "prefix" | "suffix" => field.clone(),
+                    "order" => String::from("name order"),
+                    _ => format!("{} name", field),
+                };
+                format!("~ \"{}\" (#{}) {}: {} -> {}", alias, id, label, blank(old), blank(new))
+            },
+            Change::DeptEntryAdded { id, alias, department, date } => format!("> \"{}\" (#{}) to \"{}\", {}",
+                alias, id, department, date_format.format(date)),
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.describe(&DateFormat::Iso))
+    }
+}
+
+/// Every difference between two rosters: department changes first, in id order, then changes
+/// to people, in id order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RosterDiff {
+    changes: Vec<Change>,
+}
+
+impl RosterDiff {
+    /// Compare two ProgramData instances.
+    pub fn between(old: &ProgramData, new: &ProgramData) -> Self {
+        RosterDiff::between_snapshots(&Snapshot::take(old), &Snapshot::take(new))
+    }
+
+    /// Compare two saved files.
+    pub fn between_files<P: AsRef<Path>, Q: AsRef<Path>>(old: P, new: Q) -> Result<Self, StorageError> {
+        Ok(RosterDiff::between_snapshots(&Snapshot::load(old)?, &Snapshot::load(new)?))
+    }
+
+    pub fn between_snapshots(old: &Snapshot, new: &Snapshot) -> Self {
+        let mut changes = Vec::new();
+
+        let mut dept_ids: Vec<u32> = old.departments.iter().chain(&new.departments).map(|d| d.id).collect();
+        dept_ids.sort_unstable();
+        dept_ids.dedup();
+
+        for id in dept_ids {
+            let before = old.departments.iter().find(|d| d.id == id);
+            let after = new.departments.iter().find(|d| d.id == id);
+
+            match (before, after) {
+                (None, Some(d)) => changes.push(Change::DeptAdded { id, alias: d.alias.clone(), name: d.name.clone() }),
+                (Some(d), None) => changes.push(Change::DeptRemoved { id, alias: d.alias.clone(), name: d.name.clone() }),
+                (Some(before), Some(after)) => diff_department(before, after, &mut changes),
+                (None, None) => (),
+            };
+        }
+
+        let mut person_ids: Vec<u32> = old.people.iter().chain(&new.people).map(|p| p.id).collect();
+        person_ids.sort_unstable();
+        person_ids.dedup();
+
+        for id in person_ids {
+            let before = old.people.iter().find(|p| p.id == id);
+            let after = new.people.iter().find(|p| p.id == id);
+
+            match (before, after) {
+                (None, Some(p)) => {
+                    let first = p.history.iter().min_by_key(|e| e.date);
+                    changes.push(Change::Hired {
+                        id,
+                        alias: p.alias.clone(),
+                        name: p.name.to_string(),
+                        department: first.map(|e| dept_alias(new, e.department)).unwrap_or_default(),
+                        date: p.hired,
+                    });
+                    if let Some(date) = p.terminated {
+                        changes.push(Change::Terminated { id, alias: p.alias.clone(), date });
+                    }
+                },
+                (Some(p), None) => changes.push(Change::PersonRemoved { id, alias: p.alias.clone(), name: p.name.to_string() }),
+                (Some(before), Some(after)) => diff_person(new, before, after, &mut changes),
+                (None, None) => (),
+            };
+        }
+
+        RosterDiff { changes }
+    }
+
+    pub fn changes(&self) -> &Vec<Change> {
+        &self.changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// One line per change.
+    pub fn to_text(&self, date_format: &DateFormat) -> String {
+        self.changes.iter()
+            .map(|c| format!("{}\n", c.describe(date_format)))
+            .collect()
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "changes": self.changes,
+        })
+    }
+}
+
+fn dept_alias(snapshot: &Snapshot, id: u32) -> String {
+    snapshot.departments.iter()
+        .find(|d| d.id == id)
+        .map_or_else(|| format!("#{}", id), |d| d.alias.clone())
+}
+
+fn diff_department(before: &SavedDepartment, after: &SavedDepartment, changes: &mut Vec<Change>) {
+    let id = after.id;
+
+    if before.alias != after.alias {
+        changes.push(Change::DeptAliasChanged { id, old: before.alias.clone(), new: after.alias.clone() });
+    }
+
+    if before.name != after.name {
+        changes.push(Change::DeptRenamed { id, alias: after.alias.clone(), old: before.name.clone(), new: after.name.clone() });
+    }
+}
+
+fn diff_person(new: &Snapshot, before: &SavedPerson, after: &SavedPerson, changes: &mut Vec<Change>) {
+    let id = after.id;
+    let alias = &after.alias;
+
+    if before.alias != after.alias {
+        changes.push(Change::PersonAliasChanged { id, old: before.alias.clone(), new: after.alias.clone() });
+    }
+
+    for field in NAME_FIELDS.iter() {
+        let (old, new) = (name_field(&before.name, field), name_field(&after.name, field));
+
+        if old != new {
+            changes.push(Change::NameChanged { id, alias: alias.clone(), field: String::from(*field), old, new });
+        }
+    }
+
+    for entry in &after.history {
+        if !before.history.contains(entry) {
+            changes.push(Change::DeptEntryAdded {
+                id,
+                alias: alias.clone(),
+                department: dept_alias(new, entry.department),
+                date: entry.date,
+            });
+        }
+    }
+
+    if let Some(date) = after.terminated {
+        if before.terminated != Some(date) {
+            changes.push(Change::Terminated { id, alias: alias.clone(), date });
+        }
+    }
+}
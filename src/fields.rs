@@ -0,0 +1,323 @@
+//! Contact details and user-defined fields on Person.
+//!
+//! Custom fields are declared once in a Schema held by ProgramData, which fixes each field's
+//! type.  Values are checked against the schema when they are set, so a Person only ever holds
+//! values of the declared type.
+use std::fmt;
+use std::error::Error;
+use std::str::FromStr;
+
+use chrono::naive::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::dates::DateFormat;
+
+/// Names of the built-in contact fields.  Custom fields may not reuse them.
+pub const CONTACT_FIELDS: [&str; 3] = ["email", "phone", "office"];
+
+/// How to reach a person.  Every part is optional; email and phone are checked for format when
+/// set.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Contact {
+    email: Option<String>,
+    phone: Option<String>,
+    office: Option<String>,
+}
+
+impl Contact {
+    pub fn email(&self) -> Option<&String> {
+        self.email.as_ref()
+    }
+
+    pub fn phone(&self) -> Option<&String> {
+        self.phone.as_ref()
+    }
+
+    /// Office, building or desk location, in whatever form the organization uses.
+    pub fn office(&self) -> Option<&String> {
+        self.office.as_ref()
+    }
+
+    pub fn set_email(&mut self, email: Option<&str>) -> Result<(), FieldError> {
+        if let Some(email) = email {
+            if !valid_email(email) {
+                return Err(FieldError::InvalidEmail(String::from(email)));
+            }
+        }
+
+        self.email = email.map(String::from);
+        Ok(())
+    }
+
+    pub fn set_phone(&mut self, phone: Option<&str>) -> Result<(), FieldError> {
+        if let Some(phone) = phone {
+            if !valid_phone(phone) {
+                return Err(FieldError::InvalidPhone(String::from(phone)));
+            }
+        }
+
+        self.phone = phone.map(String::from);
+        Ok(())
+    }
+
+    pub fn set_office(&mut self, office: Option<&str>) {
+        self.office = office.map(String::from);
+    }
+
+    /// Set one of the CONTACT_FIELDS by name.  None clears it.
+    pub fn set(&mut self, field: &str, value: Option<&str>) -> Result<(), FieldError> {
+        match field.to_lowercase().as_str() {
+            "email" => self.set_email(value),
+            "phone" => self.set_phone(value),
+            "office" => {
+                self.set_office(value);
+                Ok(())
+            },
+            _ => Err(FieldError::UnknownField(String::from(field))),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.email.is_none() && self.phone.is_none() && self.office.is_none()
+    }
+}
+
+/// A plausible email address: one '@', a non-empty local part, and a domain containing a dot
+/// that neither starts nor ends the domain.  No whitespace.
+pub fn valid_email(email: &str) -> bool {
+    let mut parts = email.split('@');
+
+    let (local, domain) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(local), Some(domain), None) => (local, domain),
+        _ => return false,
+    };
+
+    !local.is_empty()
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !domain.contains("..")
+        && !email.contains(char::is_whitespace)
+}
+
+/// A plausible phone number: digits with optional spaces, dashes, dots, parentheses, a leading
+/// '+' and an "x" extension, containing between 7 and 15 digits before any extension.
+pub fn valid_phone(phone: &str) -> bool {
+    let lower = phone.to_lowercase();
+    let number = match lower.find('x') {
+        Some(i) => {
+            let extension = lower[i + 1..].trim();
+            if extension.is_empty() || !extension.chars().all(|c| c.is_ascii_digit()) {
+                return false;
+            }
+            &lower[..i]
+        },
+        None => lower.as_str(),
+    };
+
+    let number = number.trim();
+    let number = number.strip_prefix('+').unwrap_or(number);
+
+    let digits = number.chars().filter(|c| c.is_ascii_digit()).count();
+
+    number.chars().all(|c| c.is_ascii_digit() || " -.()".contains(c)) && (7..=15).contains(&digits)
+}
+
+/// The type of a custom field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FieldType {
+    Text,
+    Date,
+    Number,
+    /// One of a fixed list of choices.
+    Enum(Vec<String>),
+}
+
+impl FromStr for FieldType {
+    type Err = ();
+
+    /// Accepts "text", "date", "number", or "enum" followed by a comma separated list of choices,
+    /// e.g. "enum S,M,L,XL".
+    fn from_str(s: &str) -> Result<FieldType, ()> {
+        let s = s.trim();
+        let (kind, rest) = match s.find(char::is_whitespace) {
+            Some(i) => (&s[..i], s[i..].trim()),
+            None => (s, ""),
+        };
+
+        match (kind.to_lowercase().as_str(), rest.is_empty()) {
+            ("text" | "string", true) => Ok(FieldType::Text),
+            ("date", true) => Ok(FieldType::Date),
+            ("number", true) => Ok(FieldType::Number),
+            ("enum", false) => {
+                let choices: Vec<String> = rest.split(',')
+                    .map(|c| String::from(c.trim()))
+                    .filter(|c| !c.is_empty())
+                    .collect();
+
+                if choices.is_empty() {
+                    Err(())
+                } else {
+                    Ok(FieldType::Enum(choices))
+                }
+            },
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FieldType::Text => write!(f, "text"),
+            FieldType::Date => write!(f, "date"),
+            FieldType::Number => write!(f, "number"),
+            FieldType::Enum(choices) => write!(f, "enum {}", choices.join(",")),
+        }
+    }
+}
+
+/// The value of a custom field on one person.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FieldValue {
+    Text(String),
+    Date(NaiveDate),
+    Number(f64),
+    /// One of the choices of an Enum field.
+    Choice(String),
+}
+
+impl FieldValue {
+    /// Whether this value may be stored in a field of the given type.
+    pub fn fits(&self, kind: &FieldType) -> bool {
+        match (self, kind) {
+            (FieldValue::Text(_), FieldType::Text) => true,
+            (FieldValue::Date(_), FieldType::Date) => true,
+            (FieldValue::Number(n), FieldType::Number) => n.is_finite(),
+            (FieldValue::Choice(c), FieldType::Enum(choices)) => choices.contains(c),
+            _ => false,
+        }
+    }
+
+    /// Write the value for display, with dates in the given format.
+    pub fn format(&self, date_format: &DateFormat) -> String {
+        match self {
+            FieldValue::Date(d) => date_format.format(d),
+            other => other.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FieldValue::Text(s) | FieldValue::Choice(s) => write!(f, "{}", s),
+            FieldValue::Date(d) => write!(f, "{}", d.format("%Y-%m-%d")),
+            FieldValue::Number(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+/// One custom field declared in a Schema.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldDef {
+    pub name: String,
+    pub kind: FieldType,
+}
+
+impl FieldDef {
+    /// Read a value for this field from text, as typed by a user.  Dates are read in the given
+    /// format, and enum choices are matched ignoring case.
+    pub fn parse_value(&self, text: &str, date_format: &DateFormat) -> Result<FieldValue, FieldError> {
+        let text = text.trim();
+        let invalid = || FieldError::InvalidValue(self.name.clone(), String::from(text), self.kind.clone());
+
+        match &self.kind {
+            FieldType::Text => Ok(FieldValue::Text(String::from(text))),
+            FieldType::Date => date_format.parse(text)
+                .map(FieldValue::Date)
+                .map_err(|_| invalid()),
+            FieldType::Number => f64::from_str(text).ok()
+                .filter(|n| n.is_finite())
+                .map(FieldValue::Number)
+                .ok_or_else(invalid),
+            FieldType::Enum(choices) => choices.iter()
+                .find(|c| c.eq_ignore_ascii_case(text))
+                .map(|c| FieldValue::Choice(c.clone()))
+                .ok_or_else(invalid),
+        }
+    }
+}
+
+impl fmt::Display for FieldDef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.kind)
+    }
+}
+
+/// The custom fields defined for a ProgramData, in the order they were defined.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Schema {
+    fields: Vec<FieldDef>,
+}
+
+impl Schema {
+    pub fn fields(&self) -> &Vec<FieldDef> {
+        &self.fields
+    }
+
+    /// Look up a field by name, ignoring case.
+    pub fn get(&self, name: &str) -> Option<&FieldDef> {
+        self.fields.iter().find(|f| f.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Add a field.  Names are single words, unique ignoring case, and may not be one of the
+    /// built-in contact fields.
+    pub fn define(&mut self, name: &str, kind: FieldType) -> Result<(), FieldError> {
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(FieldError::InvalidName(String::from(name)));
+        }
+
+        if self.get(name).is_some() || CONTACT_FIELDS.contains(&name.to_lowercase().as_str()) {
+            return Err(FieldError::FieldExists(String::from(name)));
+        }
+
+        self.fields.push(FieldDef { name: String::from(name), kind });
+        Ok(())
+    }
+
+    /// Remove a field, returning its definition.  See ProgramData::remove_field(), which also
+    /// clears the field's values.
+    pub fn remove(&mut self, name: &str) -> Result<FieldDef, FieldError> {
+        match self.fields.iter().position(|f| f.name.eq_ignore_ascii_case(name)) {
+            Some(i) => Ok(self.fields.remove(i)),
+            None => Err(FieldError::UnknownField(String::from(name))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldError {
+    UnknownField(String),
+    FieldExists(String),
+    InvalidName(String),
+    /// Field name, rejected text, and the field's type.
+    InvalidValue(String, String, FieldType),
+    InvalidEmail(String),
+    InvalidPhone(String),
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FieldError::UnknownField(name) => write!(f, "No field named \"{}\"", name),
+            FieldError::FieldExists(name) => write!(f, "A field named \"{}\" already exists", name),
+            FieldError::InvalidName(name) => write!(f, "\"{}\" is not a valid field name; names must be a single word", name),
+            FieldError::InvalidValue(name, value, kind) => write!(f, "\"{}\" is not a valid value for {} ({})", value, name, kind),
+            FieldError::InvalidEmail(email) => write!(f, "\"{}\" is not a valid email address", email),
+            FieldError::InvalidPhone(phone) => write!(f, "\"{}\" is not a valid phone number", phone),
+        }
+    }
+}
+
+impl Error for FieldError {}
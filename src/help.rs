@@ -0,0 +1,314 @@
+//! Help pages for the commands of the text interface.  Each command describes itself once, as a
+//! HelpPage, and the same pages are shown by HELP and exported as a Markdown document or a man
+//! page.
+use std::fmt;
+use std::str::FromStr;
+
+/// A subject that commands are grouped under in HELP TOPICS and in exported documentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Topic {
+    General,
+    People,
+    Departments,
+    Finding,
+    Reports,
+    Files,
+    Settings,
+}
+
+impl Topic {
+    /// Every topic, in the order they are listed.
+    pub const ALL: [Topic; 7] = [Topic::General, Topic::People, Topic::Departments, Topic::Finding,
+                                 Topic::Reports, Topic::Files, Topic::Settings];
+
+    /// The word used for the topic in HELP [TOPIC].
+    pub fn keyword(self) -> &'static str {
+        match self {
+            Topic::General => "general",
+            Topic::People => "people",
+            Topic::Departments => "departments",
+            Topic::Finding => "finding",
+            Topic::Reports => "reports",
+            Topic::Files => "files",
+            Topic::Settings => "settings",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Topic::General => "Getting help and leaving the program.",
+            Topic::People => "Hiring, moving and keeping records on employees.",
+            Topic::Departments => "Organizing departments, their budgets, locations and cost centers.",
+            Topic::Finding => "Listing, searching for and naming people and departments.",
+            Topic::Reports => "Headcount, tenure, position, payroll and location reports.",
+            Topic::Files => "Saving, loading, comparing, merging and checking data.",
+            Topic::Settings => "Display settings, custom fields and the role of the session.",
+        }
+    }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Topic::General => write!(f, "General"),
+            Topic::People => write!(f, "People"),
+            Topic::Departments => write!(f, "Departments"),
+            Topic::Finding => write!(f, "Finding things"),
+            Topic::Reports => write!(f, "Reports"),
+            Topic::Files => write!(f, "Files"),
+            Topic::Settings => write!(f, "Settings"),
+        }
+    }
+}
+
+impl FromStr for Topic {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Topic::ALL.iter()
+            .find(|topic| topic.keyword().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or(())
+    }
+}
+
+/// How help for every command is written out by HELP EXPORT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocFormat {
+    Markdown,
+    Man,
+}
+
+impl FromStr for DocFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "markdown" | "md" => Ok(DocFormat::Markdown),
+            "man" => Ok(DocFormat::Man),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The help for one command: how it is written, what it does, what its arguments mean, examples
+/// of its use and other commands to look at.  Built up a part at a time, starting from new().
+#[derive(Debug, Clone)]
+pub struct HelpPage {
+    topic: Topic,
+    usage: Vec<String>,
+    description: String,
+    arguments: Vec<(String, String)>,
+    examples: Vec<String>,
+    related: Vec<String>,
+}
+
+impl HelpPage {
+    pub fn new(topic: Topic) -> Self {
+        HelpPage {
+            topic,
+            usage: Vec::new(),
+            description: String::new(),
+            arguments: Vec::new(),
+            examples: Vec::new(),
+            related: Vec::new(),
+        }
+    }
+
+    /// Add one form of the command to the synopsis.
+    pub fn usage(mut self, usage: &str) -> Self {
+        self.usage.push(String::from(usage));
+        self
+    }
+
+    /// What the command does, in paragraphs separated by blank lines.  Lines are shown as they
+    /// are written.
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = String::from(description);
+        self
+    }
+
+    pub fn argument(mut self, name: &str, meaning: &str) -> Self {
+        self.arguments.push((String::from(name), String::from(meaning)));
+        self
+    }
+
+    pub fn example(mut self, example: &str) -> Self {
+        self.examples.push(String::from(example));
+        self
+    }
+
+    /// Other commands to see, by keyword.
+    pub fn related(mut self, keywords: &[&str]) -> Self {
+        self.related.extend(keywords.iter().map(|k| String::from(*k)));
+        self
+    }
+
+    pub fn topic(&self) -> Topic {
+        self.topic
+    }
+
+    /// The page without any mention of the given commands among those to see, for when they
+    /// aren't available.
+    pub fn without_related(&self, keywords: &[&str]) -> HelpPage {
+        let mut page = self.clone();
+        page.related.retain(|k| !keywords.contains(&k.as_str()));
+        page
+    }
+
+    /// The page as shown by HELP [COMMAND].
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+
+        for usage in &self.usage {
+            text.push_str(usage);
+            text.push('\n');
+        }
+
+        if !self.description.is_empty() {
+            text.push('\n');
+            text.push_str(&self.description);
+            text.push('\n');
+        }
+
+        if !self.arguments.is_empty() {
+            let width = self.arguments.iter().map(|(name, _)| name.chars().count()).max().unwrap_or(0);
+
+            text.push_str("\nArguments:\n");
+            for (name, meaning) in &self.arguments {
+                text.push_str(&format!("    {:width$}  {}\n", name, meaning, width = width));
+            }
+        }
+
+        for (i, example) in self.examples.iter().enumerate() {
+            text.push_str(if i == 0 { "\nEx:  " } else { "     " });
+            text.push_str(example);
+            text.push('\n');
+        }
+
+        if !self.related.is_empty() {
+            let related: Vec<String> = self.related.iter().map(|k| k.to_uppercase()).collect();
+            text.push_str(&format!("\nSee also: {}\n", related.join(", ")));
+        }
+
+        text
+    }
+
+    /// The page as a section of a Markdown document.  Related commands link to their sections.
+    pub fn markdown(&self, keyword: &str, summary: &str) -> String {
+        let mut text = format!("### {}\n\n{}\n\n```\n{}\n```\n", keyword, summary, self.usage.join("\n"));
+
+        for paragraph in self.description.split("\n\n").filter(|p| !p.trim().is_empty()) {
+            text.push('\n');
+            text.push_str(paragraph);
+            text.push('\n');
+        }
+
+        if !self.arguments.is_empty() {
+            text.push_str("\n**Arguments**\n\n");
+            for (name, meaning) in &self.arguments {
+                text.push_str(&format!("- `{}`: {}\n", name, meaning));
+            }
+        }
+
+        if !self.examples.is_empty() {
+            text.push_str(&format!("\n**Examples**\n\n```\n{}\n```\n", self.examples.join("\n")));
+        }
+
+        if !self.related.is_empty() {
+            let related: Vec<String> = self.related.iter().map(|k| format!("[{}](#{})", k, k)).collect();
+            text.push_str(&format!("\nSee also: {}\n", related.join(", ")));
+        }
+
+        text
+    }
+
+    /// The page as a subsection of a man page, in roff.
+    pub fn man(&self, keyword: &str, summary: &str) -> String {
+        let mut text = format!(".SS {}\n{}\n.PP\n.nf\n", keyword, roff(summary));
+
+        for usage in &self.usage {
+            text.push_str(&format!(".B {}\n", roff(usage)));
+        }
+        text.push_str(".fi\n");
+
+        for paragraph in self.description.split("\n\n").filter(|p| !p.trim().is_empty()) {
+            text.push_str(&format!(".PP\n{}\n", roff(paragraph)));
+        }
+
+        for (name, meaning) in &self.arguments {
+            text.push_str(&format!(".TP\n.B {}\n{}\n", roff(name), roff(meaning)));
+        }
+
+        if !self.examples.is_empty() {
+            text.push_str(".PP\nExamples:\n.RS\n.nf\n");
+            for example in &self.examples {
+                text.push_str(&roff(example));
+                text.push('\n');
+            }
+            text.push_str(".fi\n.RE\n");
+        }
+
+        if !self.related.is_empty() {
+            text.push_str(&format!(".PP\nSee also: {}.\n", roff(&self.related.join(", "))));
+        }
+
+        text
+    }
+}
+
+/// Help for every command as a Markdown document, grouped by topic.  Each command is given as
+/// (keyword, summary, page).
+pub fn markdown(commands: &[(&str, &str, &HelpPage)]) -> String {
+    let mut text = String::from("# depart_mental commands\n");
+
+    for topic in Topic::ALL.iter() {
+        let pages: Vec<_> = commands.iter().filter(|(_, _, page)| page.topic == *topic).collect();
+        if pages.is_empty() {
+            continue;
+        }
+
+        text.push_str(&format!("\n## {}\n\n{}\n", topic, topic.description()));
+        for (keyword, summary, page) in pages {
+            text.push('\n');
+            text.push_str(&page.markdown(keyword, summary));
+        }
+    }
+
+    text
+}
+
+/// Help for every command as a man page for section 1, grouped by topic.
+pub fn man(commands: &[(&str, &str, &HelpPage)]) -> String {
+    let mut text = format!(".TH DEPART_MENTAL 1 \"\" \"depart_mental {}\"\n\
+        .SH NAME\n\
+        depart_mental \\- keep track of departments and the people in them\n\
+        .SH SYNOPSIS\n\
+        .B depart_mental\n\
+        .SH DESCRIPTION\n\
+        Commands are read one per line from standard input.  At a terminal, Tab completes\n\
+        commands and aliases, and Ctrl-R searches earlier commands.\n", env!("CARGO_PKG_VERSION"));
+
+    for topic in Topic::ALL.iter() {
+        let pages: Vec<_> = commands.iter().filter(|(_, _, page)| page.topic == *topic).collect();
+        if pages.is_empty() {
+            continue;
+        }
+
+        text.push_str(&format!(".SH {}\n{}\n", roff(&topic.to_string().to_uppercase()), roff(topic.description())));
+        for (keyword, summary, page) in pages {
+            text.push_str(&page.man(&keyword.to_uppercase(), summary));
+        }
+    }
+
+    text
+}
+
+/// Escape text for roff: backslashes, and lines that would otherwise be read as requests.
+fn roff(text: &str) -> String {
+    text.replace('\\', "\\e")
+        .lines()
+        .map(|line| if line.starts_with('.') || line.starts_with('\'') { format!("\\&{}", line) } else { String::from(line) })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
@@ -0,0 +1,84 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// How employee numbers are written, e.g. "EMP-000123".  The number itself never changes once a
+/// person has been added; the format only affects how it's shown and read.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct IdFormat {
+    prefix: String,
+    width: usize,
+}
+
+impl IdFormat {
+    /// A format writing `prefix` followed by the number, zero-padded to at least `width` digits.
+    pub fn new(prefix: &str, width: usize) -> Self {
+        IdFormat {
+            prefix: String::from(prefix),
+            width,
+        }
+    }
+
+    pub fn prefix(&self) -> &String {
+        &self.prefix
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn format(&self, id: u32) -> String {
+        format!("{}{:0width$}", self.prefix, id, width = self.width)
+    }
+
+    /// Read an employee number written in this format, or as a bare number.  The prefix is
+    /// matched ignoring case.
+    pub fn parse(&self, text: &str) -> Option<u32> {
+        let text = text.trim();
+        let prefix_len = self.prefix.len();
+
+        let digits = match text.get(..prefix_len) {
+            Some(start) if prefix_len > 0 && start.eq_ignore_ascii_case(&self.prefix) => &text[prefix_len..],
+            _ => text,
+        };
+
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+
+        u32::from_str(digits).ok().filter(|id| *id > 0)
+    }
+}
+
+impl FromStr for IdFormat {
+    type Err = ();
+
+    /// Accepts "plain" for bare numbers, or a pattern where a run of '#' stands for the
+    /// zero-padded number, e.g. "EMP-######".
+    fn from_str(s: &str) -> Result<IdFormat, ()> {
+        if s.eq_ignore_ascii_case("plain") {
+            return Ok(IdFormat::default());
+        }
+
+        let prefix = s.trim_end_matches('#');
+        let width = s.len() - prefix.len();
+
+        if width == 0 || prefix.contains('#') || prefix.contains(char::is_whitespace) {
+            return Err(());
+        }
+
+        // A single '#' means no padding.
+        let width = if width == 1 { 0 } else { width };
+
+        Ok(IdFormat::new(prefix, width))
+    }
+}
+
+impl fmt::Display for IdFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.prefix.is_empty() && self.width == 0 {
+            return write!(f, "plain");
+        }
+
+        write!(f, "{}{}", self.prefix, "#".repeat(self.width.max(1)))
+    }
+}
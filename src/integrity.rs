@@ -0,0 +1,162 @@
+//! Consistency checks on ProgramData.  Much of the data is held twice, such as each person's
+//! current department and the department's own list of employees, and the rest of the program
+//! relies on the copies agreeing.  See ProgramData::verify() and ProgramData::repair().
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::department::Department;
+use crate::personnel::Person;
+
+/// One way in which ProgramData is inconsistent.  People and departments are named by their
+/// main alias, or by id if they have none.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// A current employee missing from their department's list of employees.
+    NotListed { person: String, department: String },
+    /// A person listed more than once in a department's employees.
+    ListedTwice { person: String, department: String },
+    /// A person listed in the employees of a department other than their current one, or listed
+    /// after leaving.
+    ListedWrongly { person: String, department: String },
+    /// A person with a current membership of a department missing from its members.
+    MemberNotListed { person: String, department: String },
+    /// A person listed in a department's members more than once, or without a current
+    /// membership of it.
+    MemberListedWrongly { person: String, department: String },
+    /// A department's employees are filed out of order, such as under a name they no longer
+    /// have, which breaks searching the list.
+    Unsorted { department: String },
+    MembersUnsorted { department: String },
+    /// A person's department history is out of date order.
+    HistoryUnsorted { person: String },
+    /// A person's current department isn't the one in their latest department history entry.
+    HistoryMismatch { person: String, current: String, history: String },
+    /// A person whose termination is dated before their date of hire.
+    LeftBeforeHire { person: String },
+    /// A person whose termination is dated before an entry in their department history.
+    LeftBeforeTransfer { person: String },
+    /// An alias that leads to a person or department not in the alias lists.
+    DeadAlias { alias: String },
+    /// An alias in the alias lists that doesn't lead back to its person or department.
+    BrokenAlias { alias: String },
+    /// The count of current employees doesn't match the number of people who haven't left.
+    EmployeeCount { recorded: u32, actual: u32 },
+    /// The last department id handed out is lower than an id in use.
+    DepartmentCount { recorded: u32, highest: u32 },
+    /// The last employee number handed out is lower than a number in use.
+    PersonCount { recorded: u32, highest: u32 },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::NotListed { person, department } =>
+                write!(f, "\"{}\" is missing from the employees of \"{}\"", person, department),
+            Violation::ListedTwice { person, department } =>
+                write!(f, "\"{}\" is listed more than once in the employees of \"{}\"", person, department),
+            Violation::ListedWrongly { person, department } =>
+                write!(f, "\"{}\" is listed in the employees of \"{}\" but doesn't work there", person, department),
+            Violation::MemberNotListed { person, department } =>
+                write!(f, "\"{}\" is missing from the members of \"{}\"", person, department),
+            Violation::MemberListedWrongly { person, department } =>
+                write!(f, "\"{}\" is wrongly listed in the members of \"{}\"", person, department),
+            Violation::Unsorted { department } => write!(f, "The employees of \"{}\" are out of order", department),
+            Violation::MembersUnsorted { department } => write!(f, "The members of \"{}\" are out of order", department),
+            Violation::HistoryUnsorted { person } => write!(f, "The department history of \"{}\" is out of order", person),
+            Violation::HistoryMismatch { person, current, history } =>
+                write!(f, "\"{}\" is in \"{}\", but their department history ends in \"{}\"", person, current, history),
+            Violation::LeftBeforeHire { person } => write!(f, "\"{}\" left before they were hired", person),
+            Violation::LeftBeforeTransfer { person } =>
+                write!(f, "\"{}\" left before a change of department in their history", person),
+            Violation::DeadAlias { alias } => write!(f, "Alias \"{}\" leads to a person or department that isn't listed", alias),
+            Violation::BrokenAlias { alias } => write!(f, "Alias \"{}\" doesn't lead to the person or department it belongs to", alias),
+            Violation::EmployeeCount { recorded, actual } =>
+                write!(f, "Employee count is {}, but {} people are employed", recorded, actual),
+            Violation::DepartmentCount { recorded, highest } =>
+                write!(f, "Last department id given out is {}, but #{} is in use", recorded, highest),
+            Violation::PersonCount { recorded, highest } =>
+                write!(f, "Last employee number given out is {}, but #{} is in use", recorded, highest),
+        }
+    }
+}
+
+/// Check every department's employee and member lists against the people in the roster.  Each
+/// department and person is given with the name to report them by.
+pub(crate) fn check_lists(departments: &[(String, Rc<RefCell<Department>>)], people: &[(String, Rc<RefCell<Person>>)],
+                          violations: &mut Vec<Violation>) {
+    let names: HashMap<*const RefCell<Person>, &String> = people.iter()
+        .map(|(name, person)| (Rc::as_ptr(person), name))
+        .collect();
+    let mut employees = HashSet::new();
+    let mut members = HashSet::new();
+
+    for (dept_name, department) in departments {
+        let dept = department.borrow();
+        let (employees_indexed, members_indexed) = dept.is_indexed();
+
+        if !employees_indexed {
+            violations.push(Violation::Unsorted { department: dept_name.clone() });
+        }
+
+        if !members_indexed {
+            violations.push(Violation::MembersUnsorted { department: dept_name.clone() });
+        }
+
+        for listed in dept.employees() {
+            let works_here = {
+                let p = listed.borrow();
+                !p.is_terminated() && Rc::ptr_eq(&p.department(), department)
+            };
+            let (person, department) = (name_of(&names, listed), Rc::as_ptr(department));
+
+            if !employees.insert((department, Rc::as_ptr(listed))) {
+                violations.push(Violation::ListedTwice { person, department: dept_name.clone() });
+            } else if !works_here || !names.contains_key(&Rc::as_ptr(listed)) {
+                violations.push(Violation::ListedWrongly { person, department: dept_name.clone() });
+            }
+        }
+
+        for listed in dept.members() {
+            let member_here = {
+                let p = listed.borrow();
+                !p.is_terminated() && p.current_memberships().iter().any(|m| Rc::ptr_eq(&m.department(), department))
+            };
+
+            if !members.insert((Rc::as_ptr(department), Rc::as_ptr(listed))) || !member_here
+                || !names.contains_key(&Rc::as_ptr(listed)) {
+                violations.push(Violation::MemberListedWrongly { person: name_of(&names, listed), department: dept_name.clone() });
+            }
+        }
+    }
+
+    let dept_name = |department: &Rc<RefCell<Department>>| departments.iter()
+        .find(|(_, d)| Rc::ptr_eq(d, department))
+        .map_or_else(|| format!("#{}", department.borrow().id()), |(name, _)| name.clone());
+
+    for (name, pointer) in people {
+        let person = pointer.borrow();
+        if person.is_terminated() {
+            continue;
+        }
+
+        let department = person.department();
+        if !employees.contains(&(Rc::as_ptr(&department), Rc::as_ptr(pointer))) {
+            violations.push(Violation::NotListed { person: name.clone(), department: dept_name(&department) });
+        }
+
+        for membership in person.current_memberships() {
+            let department = membership.department();
+            if !members.contains(&(Rc::as_ptr(&department), Rc::as_ptr(pointer))) {
+                violations.push(Violation::MemberNotListed { person: name.clone(), department: dept_name(&department) });
+            }
+        }
+    }
+}
+
+/// The name a listed person is reported by, or their id if they aren't in the roster.
+fn name_of(names: &HashMap<*const RefCell<Person>, &String>, person: &Rc<RefCell<Person>>) -> String {
+    names.get(&Rc::as_ptr(person))
+        .map_or_else(|| format!("#{}", person.borrow().id()), |name| String::clone(name))
+}
@@ -3,4 +3,22 @@ extern crate chrono;
 pub mod personnel;
 pub mod department;
 pub mod textinterface;
-pub mod data_handling;
\ No newline at end of file
+pub mod data_handling;
+pub mod search;
+pub mod query;
+pub mod reports;
+pub mod tenure;
+pub mod dates;
+pub mod collation;
+pub mod ids;
+pub mod storage;
+pub mod compensation;
+pub mod fields;
+pub mod organization;
+pub mod scheduling;
+pub mod reorg;
+pub mod diff;
+pub mod merge;
+pub mod integrity;
+pub mod lineedit;
+pub mod help;
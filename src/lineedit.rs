@@ -0,0 +1,189 @@
+//! Reading input from the terminal with line editing.  Commands are kept in a history file between
+//! sessions, can be searched with Ctrl-R, and Tab completes command keywords and the aliases of
+//! people and departments.  When input isn't a terminal, such as commands piped in from a file,
+//! lines are read as they are and nothing is recorded.
+use std::io::{self, prelude::*, IsTerminal};
+use std::path::PathBuf;
+
+use rustyline::completion::Completer;
+use rustyline::config::{CompletionType, Config};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::FileHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+/// History file, kept in the home directory.
+pub const HISTORY_FILE: &str = ".depart_mental_history";
+
+/// Number of commands kept in the history file.
+const HISTORY_SIZE: usize = 1000;
+
+pub struct LineEditor {
+    /// None when input isn't a terminal.
+    editor: Option<Editor<Completions, FileHistory>>,
+    history: Option<PathBuf>,
+}
+
+impl LineEditor {
+    /// Set up editing if input is a terminal, loading any history from earlier sessions.
+    pub fn new() -> Self {
+        if !io::stdin().is_terminal() {
+            return LineEditor { editor: None, history: None };
+        }
+
+        let config = Config::builder()
+            .max_history_size(HISTORY_SIZE)
+            .and_then(|config| config.history_ignore_dups(true))
+            .map(|config| config.history_ignore_space(true)
+                .auto_add_history(false)
+                .completion_type(CompletionType::List)
+                .build());
+
+        let mut editor = match config.and_then(Editor::with_config) {
+            Ok(editor) => editor,
+            Err(e) => {
+                eprintln!("Line editing unavailable: {}", e);
+                return LineEditor { editor: None, history: None };
+            },
+        };
+        editor.set_helper(Some(Completions::default()));
+
+        let history = std::env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE));
+        if let Some(path) = &history {
+            // A missing file just means there's no history yet.
+            if path.exists() {
+                if let Err(e) = editor.load_history(path) {
+                    eprintln!("Error reading command history from {}: {}", path.display(), e);
+                }
+            }
+        }
+
+        LineEditor { editor: Some(editor), history }
+    }
+
+    /// Whether input is being edited at a terminal, and so whether completions are used.
+    pub fn is_interactive(&self) -> bool {
+        self.editor.is_some()
+    }
+
+    /// Replace the words offered by Tab completion: command keywords, and the aliases of people
+    /// and departments.
+    pub fn set_completions(&mut self, keywords: Vec<String>, aliases: Vec<String>) {
+        if let Some(completions) = self.editor.as_mut().and_then(|editor| editor.helper_mut()) {
+            completions.keywords = keywords;
+            completions.keywords.sort();
+            completions.keywords.dedup();
+            completions.aliases = aliases;
+        }
+    }
+
+    /// Read a command, completing the first word as a keyword.  The command is added to the
+    /// history.  Returns None at the end of input.
+    pub fn read_command(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        let line = self.read(prompt, true)?;
+
+        if let (Some(editor), Some(line)) = (self.editor.as_mut(), &line) {
+            if !line.trim().is_empty() && editor.add_history_entry(line.as_str()).map_err(to_io)? {
+                if let Some(path) = &self.history {
+                    if let Err(e) = editor.append_history(path) {
+                        eprintln!("Error saving command history to {}: {}", path.display(), e);
+                    }
+                }
+            }
+        }
+
+        Ok(line)
+    }
+
+    /// Read an answer to a prompt, completing aliases.  Returns None at the end of input.
+    pub fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        self.read(prompt, false)
+    }
+
+    fn read(&mut self, prompt: &str, command: bool) -> io::Result<Option<String>> {
+        let editor = match self.editor.as_mut() {
+            Some(editor) => editor,
+            None => {
+                let mut stdout = io::stdout();
+                stdout.write_all(prompt.as_bytes())?;
+                stdout.flush()?;
+
+                let mut line = String::new();
+                return match io::stdin().read_line(&mut line)? {
+                    0 => Ok(None),
+                    _ => Ok(Some(line)),
+                };
+            },
+        };
+
+        if let Some(completions) = editor.helper_mut() {
+            completions.command = command;
+        }
+
+        match editor.readline(prompt) {
+            Ok(line) => Ok(Some(line)),
+            // Ctrl-C abandons the line, as in a shell.
+            Err(ReadlineError::Interrupted) => Ok(Some(String::new())),
+            Err(ReadlineError::Eof) => Ok(None),
+            Err(e) => Err(to_io(e)),
+        }
+    }
+}
+
+impl Default for LineEditor {
+    fn default() -> Self {
+        LineEditor::new()
+    }
+}
+
+fn to_io(error: ReadlineError) -> io::Error {
+    match error {
+        ReadlineError::Io(e) => e,
+        e => io::Error::other(e),
+    }
+}
+
+/// Words offered by Tab.  In a command the first word, and the word after HELP, is completed from
+/// the keywords, ignoring case; any other word is completed from the aliases.
+#[derive(Default)]
+struct Completions {
+    keywords: Vec<String>,
+    aliases: Vec<String>,
+    /// Whether the line being read is a command, rather than an answer to a prompt.
+    command: bool,
+}
+
+impl Completer for Completions {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos].char_indices().rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let word = &line[start..pos];
+        let before: Vec<&str> = line[..start].split_whitespace().collect();
+
+        let keyword = self.command && (before.is_empty() || (before.len() == 1 && before[0].eq_ignore_ascii_case("help")));
+
+        let candidates = if keyword {
+            let word = word.to_lowercase();
+            self.keywords.iter().filter(|k| k.starts_with(&word)).cloned().collect()
+        } else {
+            self.aliases.iter().filter(|a| a.starts_with(word)).cloned().collect()
+        };
+
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for Completions {
+    type Hint = String;
+}
+
+impl Highlighter for Completions { }
+
+impl Validator for Completions { }
+
+impl Helper for Completions { }
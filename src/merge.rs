@@ -0,0 +1,520 @@
+//! Three-way merge of two rosters edited separately from a common ancestor, such as two copies of
+//! a saved file handed to different people.  Each side's changes are worked out against the base
+//! version: changes made on only one side are taken as they are, and changes made to the same
+//! record on both sides are left as conflicts to be resolved one way or the other.
+//!
+//! Records are matched by id (or by code or name, for locations, cost centers and fields) as in
+//! RosterDiff.  Departments, people and pending transfers added on both sides can end up with the
+//! same id; those added by "theirs" are given new ids before merging.
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::compensation::{CompensationEntry, Money};
+use crate::data_handling::ProgramData;
+use crate::integrity::Violation;
+use crate::personnel::Name;
+use crate::storage::{Snapshot, StorageError, FORMAT_VERSION};
+
+/// One of the three versions taking part in a merge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Base,
+    Ours,
+    Theirs,
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Side::Base => write!(f, "base"),
+            Side::Ours => write!(f, "ours"),
+            Side::Theirs => write!(f, "theirs"),
+        }
+    }
+}
+
+/// A record changed differently on both sides, such as a person transferred to two different
+/// departments.  Until resolved, the merged roster keeps our version of the conflicting fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    section: &'static str,
+    key: Value,
+    label: String,
+    /// The fields changed on both sides, or empty if the whole record was added or removed.
+    fields: Vec<String>,
+    versions: [Option<Value>; 3],
+    shown: [String; 3],
+    resolution: Option<Side>,
+}
+
+impl Conflict {
+    /// What the conflict is about, e.g. `"jsmith" (#4)` or `department "eng" (#2)`.
+    pub fn label(&self) -> &String {
+        &self.label
+    }
+
+    /// Names of the fields changed on both sides.  Empty if the record was removed on one side
+    /// and changed on the other, or added on both sides with different contents.
+    pub fn fields(&self) -> &Vec<String> {
+        &self.fields
+    }
+
+    /// The record as it is on the given side, as saved JSON.  None if it doesn't exist there.
+    pub fn version(&self, side: Side) -> Option<&Value> {
+        self.versions[index(side)].as_ref()
+    }
+
+    /// The conflicting fields on the given side, as text.
+    pub fn describe(&self, side: Side) -> &String {
+        &self.shown[index(side)]
+    }
+
+    pub fn resolution(&self) -> Option<Side> {
+        self.resolution
+    }
+
+    /// Whether any of the conflicting fields is pay, a budget or the HR passphrase.
+    pub fn involves_pay(&self) -> bool {
+        self.fields.iter().any(|f| PAY_FIELDS.contains(&f.as_str()))
+    }
+}
+
+/// Fields holding pay, budgets or the HR passphrase, which are only shown in the HR role.
+const PAY_FIELDS: [&str; 3] = ["compensation", "budget", "hr_passphrase"];
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.fields.is_empty() {
+            write!(f, "{}", self.label)
+        } else {
+            write!(f, "{}: {}", self.label, self.fields.join(", "))
+        }
+    }
+}
+
+fn index(side: Side) -> usize {
+    match side {
+        Side::Base => 0,
+        Side::Ours => 1,
+        Side::Theirs => 2,
+    }
+}
+
+/// The outcome of merging two rosters: every change that could be made automatically, and the
+/// conflicts left to resolve before the result can be used.
+#[derive(Debug, Clone)]
+pub struct RosterMerge {
+    merged: Value,
+    conflicts: Vec<Conflict>,
+    renumbered: Vec<String>,
+}
+
+/// The collections in a Snapshot merged record by record, with the field that identifies a record.
+const SECTIONS: [(&str, &str); 6] = [
+    ("fields", "name"),
+    ("locations", "code"),
+    ("cost_centers", "code"),
+    ("departments", "id"),
+    ("people", "id"),
+    ("pending", "id"),
+];
+
+impl RosterMerge {
+    /// Merge `ours` and `theirs`, both edited from `base`.
+    pub fn new(base: &Snapshot, ours: &Snapshot, theirs: &Snapshot) -> Self {
+        let mut theirs = theirs.clone();
+        let renumbered = renumber(base, ours, &mut theirs);
+
+        let sides = [to_value(base), to_value(ours), to_value(&theirs)];
+        let mut merged = sides[1].clone();
+        let mut conflicts = Vec::new();
+
+        let settings: Vec<Option<&Value>> = sides.iter().map(|s| s.get("settings")).collect();
+        let (settings, conflict) = merge_record(settings[0], settings[1], settings[2]);
+        merged["settings"] = settings.unwrap_or(Value::Null);
+        if let Some(fields) = conflict {
+            conflicts.push(new_conflict("settings", Value::Null, String::from("settings"), fields,
+                sides.iter().map(|s| s.get("settings").cloned()).collect(), &sides));
+        }
+
+        for (section, key) in SECTIONS.iter() {
+            let lists: Vec<Vec<Value>> = sides.iter()
+                .map(|s| s.get(*section).and_then(Value::as_array).cloned().unwrap_or_default())
+                .collect();
+            let find = |list: &Vec<Value>, id: &Value| list.iter().find(|r| r.get(*key) == Some(id)).cloned();
+
+            // Our order first, then records only the other versions have.
+            let mut keys: Vec<Value> = Vec::new();
+            for record in lists[1].iter().chain(&lists[2]).chain(&lists[0]) {
+                if let Some(id) = record.get(*key) {
+                    if !keys.contains(id) {
+                        keys.push(id.clone());
+                    }
+                }
+            }
+
+            let mut records = Vec::new();
+            for id in keys {
+                let versions: Vec<Option<Value>> = lists.iter().map(|list| find(list, &id)).collect();
+                let (record, conflict) = merge_record(versions[0].as_ref(), versions[1].as_ref(), versions[2].as_ref());
+
+                if let Some(fields) = conflict {
+                    let label = label(section, &id, &versions);
+                    conflicts.push(new_conflict(section, id, label, fields, versions, &sides));
+                }
+
+                if let Some(record) = record {
+                    records.push(record);
+                }
+            }
+
+            merged[*section] = Value::Array(records);
+        }
+
+        let next_id = |s: &Value| s.get("next_employee_id").and_then(Value::as_u64).unwrap_or(1);
+        let highest = merged["people"].as_array().into_iter().flatten()
+            .filter_map(|p| p.get("id").and_then(Value::as_u64))
+            .max()
+            .unwrap_or(0);
+        merged["next_employee_id"] = Value::from(next_id(&sides[1]).max(next_id(&sides[2])).max(highest + 1));
+        merged["version"] = Value::from(FORMAT_VERSION);
+
+        RosterMerge { merged, conflicts, renumbered }
+    }
+
+    /// Merge two saved files, both edited from the file at `base`.
+    pub fn files<B: AsRef<Path>, O: AsRef<Path>, T: AsRef<Path>>(base: B, ours: O, theirs: T)
+                                                               -> Result<Self, StorageError> {
+        Ok(RosterMerge::new(&Snapshot::load(base)?, &Snapshot::load(ours)?, &Snapshot::load(theirs)?))
+    }
+
+    /// Conflicts in the order they were found: settings, then fields, locations, cost centers,
+    /// departments, people and pending transfers.
+    pub fn conflicts(&self) -> &Vec<Conflict> {
+        &self.conflicts
+    }
+
+    /// Number of conflicts not yet resolved.
+    pub fn unresolved(&self) -> usize {
+        self.conflicts.iter().filter(|c| c.resolution.is_none()).count()
+    }
+
+    /// Records added on both sides under the same id, and the new ids given to theirs.
+    pub fn renumbered(&self) -> &Vec<String> {
+        &self.renumbered
+    }
+
+    /// Settle a conflict by taking the conflicting fields from one side.  Changes made to other
+    /// fields of the same record are kept either way.
+    pub fn resolve(&mut self, conflict: usize, side: Side) -> Result<(), MergeError> {
+        let conflict = self.conflicts.get_mut(conflict)
+            .ok_or(MergeError::NoSuchConflict(conflict))?;
+        conflict.resolution = Some(side);
+
+        Ok(())
+    }
+
+    /// The merged roster.  Fails while conflicts are unresolved.
+    pub fn snapshot(&self) -> Result<Snapshot, MergeError> {
+        let unresolved = self.unresolved();
+        if unresolved > 0 {
+            return Err(MergeError::Unresolved(unresolved));
+        }
+
+        let mut merged = self.merged.clone();
+        for conflict in &self.conflicts {
+            let side = conflict.resolution.unwrap_or(Side::Ours);
+            apply(&mut merged, conflict, conflict.version(side));
+        }
+
+        serde_json::from_value(merged).map_err(|e| MergeError::Invalid(StorageError::Format(e)))
+    }
+
+    /// Build a ProgramData from the merged roster.  Fails while conflicts are unresolved, or if
+    /// the two sets of changes don't fit together: two people hired under the same alias, or
+    /// anything ProgramData::verify() finds, such as a transfer made on one side dated after a
+    /// termination made on the other.
+    pub fn restore(&self) -> Result<ProgramData, MergeError> {
+        let data = self.snapshot()?.restore().map_err(MergeError::Invalid)?;
+
+        let violations = data.verify();
+        if !violations.is_empty() {
+            return Err(MergeError::Inconsistent(violations));
+        }
+
+        Ok(data)
+    }
+}
+
+fn to_value(snapshot: &Snapshot) -> Value {
+    serde_json::to_value(snapshot).expect("Snapshot is always representable as JSON")
+}
+
+/// Three-way merge of one record.  Returns the merged record, and the fields changed on both
+/// sides if there is a conflict (empty when the conflict is about the whole record).  Conflicting
+/// fields take our value.
+fn merge_record(base: Option<&Value>, ours: Option<&Value>, theirs: Option<&Value>) -> (Option<Value>, Option<Vec<String>>) {
+    if ours == theirs || theirs == base {
+        return (ours.cloned(), None);
+    }
+    if ours == base {
+        return (theirs.cloned(), None);
+    }
+
+    let (base, ours, theirs) = match (base.and_then(Value::as_object), ours.and_then(Value::as_object),
+                                      theirs.and_then(Value::as_object)) {
+        (Some(base), Some(ours), Some(theirs)) => (base, ours, theirs),
+        _ => return (ours.cloned(), Some(Vec::new())),
+    };
+
+    let mut merged = ours.clone();
+    let mut fields = Vec::new();
+
+    let keys: BTreeSet<&String> = ours.keys().chain(theirs.keys()).collect();
+    for field in keys {
+        let (b, o, t) = (base.get(field), ours.get(field), theirs.get(field));
+
+        if o == t || t == b {
+            continue;
+        }
+        if o == b {
+            match t {
+                Some(t) => merged.insert(field.clone(), t.clone()),
+                None => merged.remove(field),
+            };
+        } else {
+            fields.push(field.clone());
+        }
+    }
+
+    let conflict = if fields.is_empty() { None } else { Some(fields) };
+    (Some(Value::Object(merged)), conflict)
+}
+
+/// Set the record a conflict is about to the chosen version of its conflicting fields.
+fn apply(merged: &mut Value, conflict: &Conflict, chosen: Option<&Value>) {
+    let (section, key) = match SECTIONS.iter().find(|(s, _)| *s == conflict.section) {
+        Some((section, key)) => (*section, *key),
+        None => {
+            if let (Some(chosen), Some(settings)) = (chosen, merged.get_mut(conflict.section)) {
+                set_fields(settings, &conflict.fields, chosen);
+            }
+            return;
+        },
+    };
+
+    let list = match merged.get_mut(section).and_then(Value::as_array_mut) {
+        Some(list) => list,
+        None => return,
+    };
+    let position = list.iter().position(|r| r.get(key) == Some(&conflict.key));
+
+    match (position, chosen) {
+        (Some(i), Some(chosen)) if conflict.fields.is_empty() => list[i] = chosen.clone(),
+        (Some(i), Some(chosen)) => set_fields(&mut list[i], &conflict.fields, chosen),
+        (Some(i), None) => {
+            list.remove(i);
+        },
+        (None, Some(chosen)) => list.push(chosen.clone()),
+        (None, None) => (),
+    };
+}
+
+fn set_fields(record: &mut Value, fields: &[String], chosen: &Value) {
+    if let Some(record) = record.as_object_mut() {
+        for field in fields {
+            match chosen.get(field) {
+                Some(value) => record.insert(field.clone(), value.clone()),
+                None => record.remove(field),
+            };
+        }
+    }
+}
+
+fn new_conflict(section: &'static str, key: Value, label: String, fields: Vec<String>,
+                versions: Vec<Option<Value>>, sides: &[Value; 3]) -> Conflict {
+    let mut shown = [String::new(), String::new(), String::new()];
+
+    for (i, version) in versions.iter().enumerate() {
+        shown[i] = match version {
+            None => String::from("(not present)"),
+            Some(record) if fields.is_empty() => show_record(record),
+            Some(record) => fields.iter()
+                .map(|f| format!("{}: {}", f, show_field(f, record.get(f).unwrap_or(&Value::Null), &sides[i])))
+                .collect::<Vec<String>>()
+                .join("; "),
+        };
+    }
+
+    let mut versions = versions.into_iter();
+    let mut next = || versions.next().flatten();
+    Conflict {
+        section,
+        key,
+        label,
+        fields,
+        versions: [next(), next(), next()],
+        shown,
+        resolution: None,
+    }
+}
+
+fn label(section: &str, key: &Value, versions: &[Option<Value>]) -> String {
+    let alias = versions.iter().rev().flatten()
+        .find_map(|r| r.get("alias").and_then(Value::as_str).map(String::from))
+        .unwrap_or_default();
+    let key = key.as_str().map(String::from).unwrap_or_else(|| key.to_string());
+
+    match section {
+        "departments" => format!("department \"{}\" (#{})", alias, key),
+        "people" => format!("\"{}\" (#{})", alias, key),
+        "pending" => format!("pending transfer #{}", key),
+        "locations" => format!("location {}", key),
+        "cost_centers" => format!("cost center {}", key),
+        _ => format!("field {}", key),
+    }
+}
+
+fn show_record(record: &Value) -> String {
+    match (record.get("alias").and_then(Value::as_str), record.get("name")) {
+        (Some(alias), Some(name)) => format!("\"{}\": {}", alias, show_field("name", name, &Value::Null)),
+        _ => record.to_string(),
+    }
+}
+
+/// A field as text, naming departments by the alias they have in `side`.
+fn show_field(field: &str, value: &Value, side: &Value) -> String {
+    let dept = |entry: &Value| {
+        let id = entry.get("department").and_then(Value::as_u64).unwrap_or(0);
+        side.get("departments").and_then(Value::as_array).into_iter().flatten()
+            .find(|d| d.get("id").and_then(Value::as_u64) == Some(id))
+            .and_then(|d| d.get("alias")).and_then(Value::as_str)
+            .map_or_else(|| format!("#{}", id), String::from)
+    };
+    let text = |value: Option<&Value>| value.and_then(Value::as_str).unwrap_or("").to_string();
+    let entries = |value: &Value, show: &dyn Fn(&Value) -> String| value.as_array().into_iter().flatten()
+        .map(show)
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    match (field, value) {
+        (_, Value::Null) => String::from("(none)"),
+        (_, Value::String(s)) => s.clone(),
+        ("name", name) => serde_json::from_value::<Name>(name.clone())
+            .map_or_else(|_| name.to_string(), |n| n.to_string()),
+        // Only the HR role is shown pay conflicts, and even then the passphrase hash isn't shown.
+        ("hr_passphrase", _) => String::from("(set)"),
+        ("budget", budget) => serde_json::from_value::<Money>(budget.clone())
+            .map_or_else(|_| budget.to_string(), |b| b.to_string()),
+        ("compensation", pay) => entries(pay, &|e| serde_json::from_value::<CompensationEntry>(e.clone())
+            .map_or_else(|_| e.to_string(), |e| format!("{} from {}", e, e.date))),
+        ("history", history) => entries(history, &|e| format!("{} from {}", dept(e), text(e.get("date")))),
+        ("memberships", memberships) => entries(memberships, &|m| {
+            let end = m.get("end").and_then(Value::as_str).map(|d| format!(" to {}", d)).unwrap_or_default();
+            format!("{} {}% from {}{}", dept(m), m.get("allocation").unwrap_or(&Value::Null), text(m.get("start")), end)
+        }),
+        (_, value) => value.to_string(),
+    }
+}
+
+/// Give departments, people and pending transfers added on both sides under the same id a new
+/// id in `theirs`, and update references to them.  Records added identically on both sides are
+/// left alone.  Returns a description of each change.
+fn renumber(base: &Snapshot, ours: &Snapshot, theirs: &mut Snapshot) -> Vec<String> {
+    let mut notes = Vec::new();
+
+    let fresh = |ids: Vec<u32>| ids.into_iter().max().unwrap_or(0) + 1;
+
+    let all_depts = base.departments.iter().chain(&ours.departments).chain(&theirs.departments)
+        .map(|d| d.id).collect();
+    let mut next = fresh(all_depts);
+    let mut departments: HashMap<u32, u32> = HashMap::new();
+    for dept in theirs.departments.iter_mut() {
+        let clash = ours.departments.iter().find(|d| d.id == dept.id);
+        if base.departments.iter().all(|d| d.id != dept.id) && clash.is_some_and(|d| d != dept) {
+            notes.push(format!("department \"{}\" (#{}) is now #{}", dept.alias, dept.id, next));
+            departments.insert(dept.id, next);
+            dept.id = next;
+            next += 1;
+        }
+    }
+
+    for person in theirs.people.iter_mut() {
+        for entry in person.history.iter_mut() {
+            entry.department = *departments.get(&entry.department).unwrap_or(&entry.department);
+        }
+        for membership in person.memberships.iter_mut() {
+            membership.department = *departments.get(&membership.department).unwrap_or(&membership.department);
+        }
+    }
+
+    let all_people = base.people.iter().chain(&ours.people).chain(&theirs.people)
+        .map(|p| p.id).collect();
+    let mut next = fresh(all_people);
+    let mut people: HashMap<u32, u32> = HashMap::new();
+    for person in theirs.people.iter_mut() {
+        let clash = ours.people.iter().find(|p| p.id == person.id);
+        if base.people.iter().all(|p| p.id != person.id) && clash.is_some_and(|p| p != person) {
+            notes.push(format!("\"{}\" (#{}) is now #{}", person.alias, person.id, next));
+            people.insert(person.id, next);
+            person.id = next;
+            next += 1;
+        }
+    }
+
+    for transfer in theirs.pending.iter_mut() {
+        transfer.department = *departments.get(&transfer.department).unwrap_or(&transfer.department);
+        transfer.person = *people.get(&transfer.person).unwrap_or(&transfer.person);
+    }
+
+    let all_pending = base.pending.iter().chain(&ours.pending).chain(&theirs.pending)
+        .map(|t| t.id).collect();
+    let mut next = fresh(all_pending);
+    for transfer in theirs.pending.iter_mut() {
+        let clash = ours.pending.iter().find(|t| t.id == transfer.id);
+        if base.pending.iter().all(|t| t.id != transfer.id) && clash.is_some_and(|t| t != transfer) {
+            notes.push(format!("pending transfer #{} is now #{}", transfer.id, next));
+            transfer.id = next;
+            next += 1;
+        }
+    }
+
+    notes
+}
+
+#[derive(Debug)]
+pub enum MergeError {
+    NoSuchConflict(usize),
+    /// The merge can't be used until every conflict is resolved.
+    Unresolved(usize),
+    /// The merged roster doesn't hold together.
+    Invalid(StorageError),
+    /// The merged roster can be read, but changes from the two sides contradict each other.
+    Inconsistent(Vec<Violation>),
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MergeError::NoSuchConflict(n) => write!(f, "There is no conflict number {}", n + 1),
+            MergeError::Unresolved(n) => write!(f, "{} conflict(s) left to resolve", n),
+            MergeError::Invalid(e) => write!(f, "Merged roster can't be used: {}", e),
+            MergeError::Inconsistent(violations) => {
+                let problems: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+                write!(f, "Merged roster can't be used: {}", problems.join("; "))
+            },
+        }
+    }
+}
+
+impl Error for MergeError {}
+
+impl From<StorageError> for MergeError {
+    fn from(error: StorageError) -> Self {
+        MergeError::Invalid(error)
+    }
+}
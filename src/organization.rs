@@ -0,0 +1,65 @@
+//! Locations and cost centers.  Each is identified by a short code, such as "NYC" or "CC-4100".
+//! A department may be assigned one of each, and a person may override their department's.
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// An office or site.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Location {
+    pub code: String,
+    pub name: String,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.name)
+    }
+}
+
+/// An account that payroll is billed to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CostCenter {
+    pub code: String,
+    pub name: String,
+}
+
+impl fmt::Display for CostCenter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.name)
+    }
+}
+
+/// Which of the two a listing or report is grouped by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grouping {
+    Location,
+    CostCenter,
+}
+
+impl FromStr for Grouping {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Grouping, ()> {
+        match s.to_lowercase().replace(['-', '_'], "").as_str() {
+            "location" | "locations" => Ok(Grouping::Location),
+            "costcenter" | "costcenters" | "costcentre" | "costcentres" => Ok(Grouping::CostCenter),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Grouping {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Grouping::Location => write!(f, "location"),
+            Grouping::CostCenter => write!(f, "cost center"),
+        }
+    }
+}
+
+/// Codes are typed as single words in the text interface.
+pub fn valid_code(code: &str) -> bool {
+    !code.is_empty() && !code.contains(char::is_whitespace)
+}
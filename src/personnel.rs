@@ -1,44 +1,325 @@
 use std::fmt;
+use std::str::FromStr;
 use chrono::naive::NaiveDate;
 use std::cmp::Ordering;
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::error::Error;
 use std::ops::Deref;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
 
 use crate::department::Department;
+use crate::collation::Collation;
+use crate::compensation::{CompensationEntry, CompensationError};
+use crate::fields::{Contact, FieldValue};
+
+/// Whether a name is customarily written given name first ("John Smith") or family name first
+/// ("Mao Zedong").
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub enum NameOrder {
+    #[default]
+    GivenFirst,
+    FamilyFirst,
+}
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+/// A person's name.  `first` is the given name and `last` the family name, whatever order they
+/// are written in.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Name {
     pub last: String,
-    pub middle: Option<String>,
+    pub middle: Vec<String>,
     pub first: String,
+    /// Title written before the name, e.g. "Dr."
+    pub prefix: Option<String>,
+    /// Generational or other suffix, e.g. "Jr." or "III"
+    pub suffix: Option<String>,
+    /// The name the person likes to be called by, used in place of the given name for display.
+    pub preferred: Option<String>,
+    pub order: NameOrder,
+}
+
+impl Name {
+    pub fn new(first: &str, last: &str) -> Self {
+        Name {
+            last: String::from(last),
+            middle: Vec::new(),
+            first: String::from(first),
+            prefix: None,
+            suffix: None,
+            preferred: None,
+            order: NameOrder::GivenFirst,
+        }
+    }
+
+    /// The preferred name if one is set, otherwise the given name.
+    pub fn given(&self) -> &String {
+        self.preferred.as_ref().unwrap_or(&self.first)
+    }
+
+    /// The name as the person would be addressed, e.g. "Bob Smith Jr." or "Mao Zedong".
+    pub fn display_name(&self) -> String {
+        let mut parts: Vec<&str> = Vec::new();
+
+        match self.order {
+            NameOrder::GivenFirst => {
+                parts.push(self.given());
+                parts.push(&self.last);
+            },
+            NameOrder::FamilyFirst => {
+                parts.push(&self.last);
+                parts.push(self.given());
+            },
+        };
+
+        if let Some(suffix) = &self.suffix {
+            parts.push(suffix);
+        }
+
+        parts.join(" ")
+    }
+
+    /// Every part of the name in its natural written order, e.g. "Dr. Robert James Smith Jr."
+    pub fn full_name(&self) -> String {
+        let mut parts: Vec<&str> = Vec::new();
+
+        if let Some(prefix) = &self.prefix {
+            parts.push(prefix);
+        }
+
+        match self.order {
+            NameOrder::GivenFirst => {
+                parts.push(&self.first);
+                parts.extend(self.middle.iter().map(|m| m.as_str()));
+                parts.push(&self.last);
+            },
+            NameOrder::FamilyFirst => {
+                parts.push(&self.last);
+                parts.push(&self.first);
+                parts.extend(self.middle.iter().map(|m| m.as_str()));
+            },
+        };
+
+        if let Some(suffix) = &self.suffix {
+            parts.push(suffix);
+        }
+
+        parts.join(" ")
+    }
 }
 
+/// Names display in list order: "Smith, John Paul, Jr." for names written given name first, or
+/// "Mao Zedong" for names written family name first.
 impl fmt::Display for Name {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match &self.middle {
-            Some(mid) => write!(f, "{}, {} {}", self.last, self.first, mid),
-            None => write!(f, "{}, {}", self.last, self.first),
+        match self.order {
+            NameOrder::GivenFirst => write!(f, "{}, {}", self.last, self.first)?,
+            NameOrder::FamilyFirst => write!(f, "{} {}", self.last, self.first)?,
+        };
+
+        for mid in &self.middle {
+            write!(f, " {}", mid)?;
+        }
+
+        if let Some(suffix) = &self.suffix {
+            write!(f, ", {}", suffix)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// What a person does: their job title, grade, and how much of a full-time schedule they work.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Position {
+    pub title: String,
+    /// Level or pay grade, e.g. "L4" or "Senior".
+    pub level: Option<String>,
+    /// Full-time equivalent, from just above 0.0 up to 1.0 for a full-time position.
+    pub fte: f64,
+}
+
+impl Position {
+    /// A full-time position with no level.
+    pub fn new(title: &str) -> Self {
+        Position {
+            title: String::from(title),
+            level: None,
+            fte: 1.0,
+        }
+    }
+
+    /// Whether fte is within (0, 1].
+    pub fn valid_fte(fte: f64) -> bool {
+        fte > 0.0 && fte <= 1.0
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.title)?;
+
+        if let Some(level) = &self.level {
+            write!(f, " ({})", level)?;
+        }
+
+        if self.fte < 1.0 {
+            write!(f, ", {:.2} FTE", self.fte)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A position held from the given date until the next entry in the person's position history.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionEntry {
+    position: Position,
+    date: NaiveDate,
+}
+
+impl PositionEntry {
+    pub(crate) fn new(position: Position, date: NaiveDate) -> Self {
+        PositionEntry {
+            position,
+            date,
+        }
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// The date the person started in this position.
+    pub fn date(&self) -> NaiveDate {
+        self.date
+    }
+}
+
+impl fmt::Display for PositionEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}, {}", self.position, self.date)
+    }
+}
+
+/// The terms a person is engaged on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EmploymentType {
+    #[default]
+    FullTime,
+    PartTime,
+    Contractor,
+}
+
+impl EmploymentType {
+    pub const ALL: [EmploymentType; 3] = [EmploymentType::FullTime, EmploymentType::PartTime, EmploymentType::Contractor];
+}
+
+impl FromStr for EmploymentType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<EmploymentType, ()> {
+        match s.to_lowercase().replace(['-', '_'], "").as_str() {
+            "fulltime" | "full" => Ok(EmploymentType::FullTime),
+            "parttime" | "part" => Ok(EmploymentType::PartTime),
+            "contractor" | "contract" => Ok(EmploymentType::Contractor),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for EmploymentType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EmploymentType::FullTime => write!(f, "full-time"),
+            EmploymentType::PartTime => write!(f, "part-time"),
+            EmploymentType::Contractor => write!(f, "contractor"),
         }
     }
 }
 
+/// Whether a person is working.  People are active from their date of hire until their status
+/// history says otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Status {
+    Active,
+    OnLeave,
+    Suspended,
+    /// Final: nothing can change after a person has been terminated.
+    Terminated,
+}
+
+impl FromStr for Status {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Status, ()> {
+        match s.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+            "active" => Ok(Status::Active),
+            "onleave" | "leave" => Ok(Status::OnLeave),
+            "suspended" => Ok(Status::Suspended),
+            "terminated" => Ok(Status::Terminated),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Status::Active => write!(f, "active"),
+            Status::OnLeave => write!(f, "on leave"),
+            Status::Suspended => write!(f, "suspended"),
+            Status::Terminated => write!(f, "terminated"),
+        }
+    }
+}
+
+/// A status in effect from the given date until the next entry in the person's status history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusEntry {
+    pub status: Status,
+    pub date: NaiveDate,
+}
+
+impl fmt::Display for StatusEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}, {}", self.status, self.date)
+    }
+}
+
 #[derive(Debug)]
 pub struct Person {
+    id: u32,
     name: Name,
     date_of_hire: NaiveDate,
     department: Rc<RefCell<Department>>,
     dept_history: Vec<DeptEntry>,
+    position_history: Vec<PositionEntry>,
+    compensation_history: Vec<CompensationEntry>,
+    contact: Contact,
+    custom_fields: BTreeMap<String, FieldValue>,
+    employment_type: EmploymentType,
+    /// Location and cost center codes that override those of the person's department.
+    location: Option<String>,
+    cost_center: Option<String>,
+    /// Changes of status after hire, sorted by date.  A Terminated entry is always last.
+    status_history: Vec<StatusEntry>,
+    /// Shares of time given to departments other than the primary one, current and past.
+    memberships: Vec<Membership>,
 }
 
 impl PartialEq for Person {
     fn eq(&self, other: &Self) -> bool {
-        if &self.name != &other.name {
+        if self.id != other.id {
             return false;
         }
 
-        if &self.date_of_hire != &other.date_of_hire {
+        if self.name != other.name {
+            return false;
+        }
+
+        if self.date_of_hire != other.date_of_hire {
             return false;
         }
 
@@ -46,7 +327,31 @@ impl PartialEq for Person {
             return false;
         }
 
-        if &self.dept_history != &other.dept_history {
+        if self.dept_history != other.dept_history {
+            return false;
+        }
+
+        if self.position_history != other.position_history {
+            return false;
+        }
+
+        if self.compensation_history != other.compensation_history {
+            return false;
+        }
+
+        if self.contact != other.contact || self.custom_fields != other.custom_fields {
+            return false;
+        }
+
+        if self.employment_type != other.employment_type || self.status_history != other.status_history {
+            return false;
+        }
+
+        if self.location != other.location || self.cost_center != other.cost_center {
+            return false;
+        }
+
+        if self.memberships != other.memberships {
             return false;
         }
 
@@ -61,6 +366,113 @@ impl Person {
         PersonBuilder::new()
     }
 
+    /// Rebuild a person from saved data.  Their current department is taken from the latest entry
+    /// in `dept_history`, which must not be empty.
+    pub(crate) fn restore(id: u32, name: Name, date_of_hire: NaiveDate, dept_history: Vec<DeptEntry>,
+                          position_history: Vec<PositionEntry>, termination_date: Option<NaiveDate>) -> Self {
+        let department = dept_history.iter()
+            .max_by_key(|entry| entry.date)
+            .map(|entry| Rc::clone(&entry.department))
+            .expect("restored person has no department history");
+
+        Person {
+            id,
+            name,
+            date_of_hire,
+            department,
+            dept_history,
+            position_history,
+            compensation_history: Vec::new(),
+            contact: Contact::default(),
+            custom_fields: BTreeMap::new(),
+            employment_type: EmploymentType::default(),
+            location: None,
+            cost_center: None,
+            status_history: termination_date.into_iter()
+                .map(|date| StatusEntry { status: Status::Terminated, date })
+                .collect(),
+            memberships: Vec::new(),
+        }
+    }
+
+    /// Replace the secondary memberships of a restored person.  ProgramData lists them in each
+    /// department with a current membership when the person is added.
+    pub(crate) fn restore_memberships(&mut self, mut memberships: Vec<Membership>) {
+        memberships.sort_by_key(|m| m.start);
+        self.memberships = memberships;
+    }
+
+    /// Check secondary memberships that weren't made through add_membership(), such as restored
+    /// ones: each allocation is within range, memberships of the same department don't overlap,
+    /// they never total 100% or more, and none is of the current primary department.
+    pub(crate) fn check_memberships(&self) -> Result<(), MembershipErr> {
+        for membership in &self.memberships {
+            if membership.allocation == 0 || membership.allocation >= 100 {
+                return Err(MembershipErr::InvalidAllocation(membership.allocation));
+            }
+
+            if membership.end.is_none() && Rc::ptr_eq(&membership.department, &self.department) {
+                return Err(MembershipErr::PrimaryDepartment);
+            }
+
+            // Allocations only go up when a membership starts, so checking each start is enough
+            let active: Vec<&Membership> = self.memberships.iter()
+                .filter(|m| m.is_active_on(membership.start))
+                .collect();
+
+            if active.iter().filter(|m| Rc::ptr_eq(&m.department, &membership.department)).count() > 1 {
+                return Err(MembershipErr::Overlapping);
+            }
+
+            let total: u32 = active.iter().map(|m| m.allocation).sum();
+            if total >= 100 {
+                return Err(MembershipErr::OverAllocated(total));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replace the employment type and status history of a restored person.  A termination given
+    /// to restore() is kept if `status_history` doesn't record one.
+    pub(crate) fn restore_employment(&mut self, employment_type: EmploymentType, mut status_history: Vec<StatusEntry>) {
+        let terminated = self.termination_date();
+
+        if let Some(date) = terminated {
+            if !status_history.iter().any(|e| e.status == Status::Terminated) {
+                status_history.retain(|e| e.date < date);
+                status_history.push(StatusEntry { status: Status::Terminated, date });
+            }
+        }
+
+        status_history.sort_by_key(|e| (e.status == Status::Terminated, e.date));
+
+        self.employment_type = employment_type;
+        self.status_history = status_history;
+    }
+
+    /// Replace the pay history of a restored person.
+    pub(crate) fn restore_compensation(&mut self, mut history: Vec<CompensationEntry>) {
+        history.sort_by_key(|entry| entry.date);
+        self.compensation_history = history;
+    }
+
+    /// Replace the contact details and custom field values of a restored person.
+    pub(crate) fn restore_details(&mut self, contact: Contact, custom_fields: BTreeMap<String, FieldValue>) {
+        self.contact = contact;
+        self.custom_fields = custom_fields;
+    }
+
+    /// Number identifying this person, unique within ProgramData.  Assigned when the person is
+    /// added with ProgramData::add_person(); zero until then.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub(crate) fn set_id(&mut self, id: u32) {
+        self.id = id;
+    }
+
     pub fn name(&self) -> &Name {
         &self.name
     }
@@ -77,18 +489,45 @@ impl Person {
         &self.name.last
     }
 
+    /// The first middle name, if any.  See middle_names() for all of them.
     pub fn middle_name(&self) -> Option<&String> {
-        if let Some(mid) = &self.name.middle {
-            return Some(mid);
-        } else {
-            return None;
-        }
+        self.name.middle.first()
+    }
+
+    pub fn middle_names(&self) -> &Vec<String> {
+        &self.name.middle
     }
 
     pub fn date_of_hire(&self) -> NaiveDate {
         self.date_of_hire
     }
 
+    pub fn contact(&self) -> &Contact {
+        &self.contact
+    }
+
+    pub fn contact_mut(&mut self) -> &mut Contact {
+        &mut self.contact
+    }
+
+    /// Values of custom fields, keyed by field name.  Fields with no value are absent.
+    pub fn custom_fields(&self) -> &BTreeMap<String, FieldValue> {
+        &self.custom_fields
+    }
+
+    pub fn custom_field(&self, name: &str) -> Option<&FieldValue> {
+        self.custom_fields.get(name)
+    }
+
+    /// Set or clear a custom field.  The value is not checked here; ProgramData::set_field()
+    /// checks it against the schema.
+    pub(crate) fn set_custom_field(&mut self, name: &str, value: Option<FieldValue>) {
+        match value {
+            Some(value) => self.custom_fields.insert(String::from(name), value),
+            None => self.custom_fields.remove(name),
+        };
+    }
+
     pub fn department(&self) -> Rc<RefCell<Department>> {
         Rc::clone(&self.department)
     }
@@ -101,17 +540,439 @@ impl Person {
         &mut self.dept_history
     }
 
+    /// Make the department in the latest department history entry the current one again.  See
+    /// ProgramData::repair().
+    pub(crate) fn reset_department(&mut self) {
+        if let Some(latest) = self.dept_history.iter().max_by_key(|entry| entry.date) {
+            self.department = Rc::clone(&latest.department);
+        }
+    }
+
+    /// The most recent position, or None if no position has been recorded.  Like department(),
+    /// this includes a change recorded with a future date.
+    pub fn position(&self) -> Option<&Position> {
+        self.position_history.iter()
+            .max_by_key(|entry| entry.date)
+            .map(|entry| &entry.position)
+    }
+
+    /// The position held at the end of the given day, if any.
+    pub fn position_on(&self, date: NaiveDate) -> Option<&Position> {
+        self.position_history.iter()
+            .filter(|entry| entry.date <= date)
+            .max_by_key(|entry| entry.date)
+            .map(|entry| &entry.position)
+    }
+
+    /// Full-time equivalent of the current position.  People with no recorded position count as
+    /// full-time.
+    pub fn fte(&self) -> f64 {
+        self.position().map_or(1.0, |p| p.fte)
+    }
+
+    /// Full-time equivalent on the given date, as for fte().
+    pub fn fte_on(&self, date: NaiveDate) -> f64 {
+        self.position_on(date).map_or(1.0, |p| p.fte)
+    }
+
+    pub fn position_history(&self) -> &Vec<PositionEntry> {
+        &self.position_history
+    }
+
+    /// Record a promotion, change of title or change of hours, effective on the given date.  A
+    /// change already recorded for the same date is replaced.
+    pub fn change_position(&mut self, position: Position, date: NaiveDate) -> Result<(), PersonError> {
+        if self.is_terminated() {
+            return Err(PersonError::Position(PositionErr::Terminated));
+        }
+
+        if !Position::valid_fte(position.fte) {
+            return Err(PersonError::Position(PositionErr::InvalidFte(position.fte)));
+        }
+
+        if date < self.date_of_hire {
+            return Err(PersonError::Position(PositionErr::BeforeHire));
+        }
+
+        if self.position_on(date) == Some(&position) {
+            return Err(PersonError::Position(PositionErr::Unchanged));
+        }
+
+        self.position_history.retain(|entry| entry.date != date);
+        self.position_history.push(PositionEntry { position, date });
+        self.position_history.sort_by_key(|entry| entry.date);
+
+        Ok(())
+    }
+
+    /// The most recent pay entry, or None if no pay has been recorded.
+    pub fn compensation(&self) -> Option<&CompensationEntry> {
+        self.compensation_history.last()
+    }
+
+    /// The pay in effect at the end of the given day, if any.
+    pub fn compensation_on(&self, date: NaiveDate) -> Option<&CompensationEntry> {
+        self.compensation_history.iter()
+            .filter(|entry| entry.date <= date)
+            .max_by_key(|entry| entry.date)
+    }
+
+    pub fn compensation_history(&self) -> &Vec<CompensationEntry> {
+        &self.compensation_history
+    }
+
+    /// Record a change of pay.  An entry already recorded for the same date is replaced.
+    pub fn change_compensation(&mut self, entry: CompensationEntry) -> Result<(), PersonError> {
+        if self.is_terminated() {
+            return Err(PersonError::Compensation(CompensationError::Terminated));
+        }
+
+        if entry.date < self.date_of_hire {
+            return Err(PersonError::Compensation(CompensationError::BeforeHire));
+        }
+
+        self.compensation_history.retain(|e| e.date != entry.date);
+        self.compensation_history.push(entry);
+        self.compensation_history.sort_by_key(|e| e.date);
+
+        Ok(())
+    }
+
+    pub fn employment_type(&self) -> EmploymentType {
+        self.employment_type
+    }
+
+    pub fn set_employment_type(&mut self, employment_type: EmploymentType) {
+        self.employment_type = employment_type;
+    }
+
+    /// Location code set for this person in place of their department's, if any.  See
+    /// ProgramData::location_of() for the location that applies.
+    pub fn location(&self) -> Option<&String> {
+        self.location.as_ref()
+    }
+
+    /// Set by ProgramData::assign_location(), which checks the code exists.
+    pub(crate) fn set_location(&mut self, code: Option<String>) {
+        self.location = code;
+    }
+
+    /// Cost center code set for this person in place of their department's, if any.  See
+    /// ProgramData::cost_center_of() for the cost center that applies.
+    pub fn cost_center(&self) -> Option<&String> {
+        self.cost_center.as_ref()
+    }
+
+    /// Set by ProgramData::assign_cost_center(), which checks the code exists.
+    pub(crate) fn set_cost_center(&mut self, code: Option<String>) {
+        self.cost_center = code;
+    }
+
+    /// The most recently recorded status.  Like position(), this includes a change recorded with
+    /// a future date.
+    pub fn status(&self) -> Status {
+        self.status_history.last().map_or(Status::Active, |e| e.status)
+    }
+
+    /// Status at the end of the given day, or None before the date of hire.
+    pub fn status_on(&self, date: NaiveDate) -> Option<Status> {
+        if date < self.date_of_hire {
+            return None;
+        }
+
+        let status = self.status_history.iter()
+            .filter(|e| e.date <= date)
+            .max_by_key(|e| e.date)
+            .map_or(Status::Active, |e| e.status);
+
+        Some(status)
+    }
+
+    pub fn status_history(&self) -> &Vec<StatusEntry> {
+        &self.status_history
+    }
+
+    /// Record a change of status effective on the given date.  A change already recorded for the
+    /// same date is replaced.  Changing to Terminated is the same as calling terminate().
+    pub fn change_status(&mut self, status: Status, date: NaiveDate) -> Result<(), PersonError> {
+        if self.is_terminated() {
+            return Err(PersonError::Status(StatusErr::Terminated));
+        }
+
+        if status == Status::Terminated {
+            return self.terminate(date);
+        }
+
+        if date < self.date_of_hire {
+            return Err(PersonError::Status(StatusErr::BeforeHire));
+        }
+
+        if self.status_on(date) == Some(status) {
+            return Err(PersonError::Status(StatusErr::Unchanged));
+        }
+
+        self.status_history.retain(|e| e.date != date);
+        self.status_history.push(StatusEntry { status, date });
+        self.status_history.sort_by_key(|e| e.date);
+
+        Ok(())
+    }
+
+    /// The date this person left, if they have.
+    pub fn termination_date(&self) -> Option<NaiveDate> {
+        self.status_history.iter()
+            .find(|e| e.status == Status::Terminated)
+            .map(|e| e.date)
+    }
+
+    pub fn is_terminated(&self) -> bool {
+        self.termination_date().is_some()
+    }
+
+    /// Every secondary membership, including ones that have ended, in order of start date.
+    pub fn memberships(&self) -> &Vec<Membership> {
+        &self.memberships
+    }
+
+    /// Secondary memberships that have not been ended.  Like department(), this includes a
+    /// membership recorded with a future date.
+    pub fn current_memberships(&self) -> Vec<&Membership> {
+        self.memberships.iter()
+            .filter(|m| m.end.is_none())
+            .collect()
+    }
+
+    /// Secondary memberships in effect at the end of the given day.  None while the person is not
+    /// employed.
+    pub fn memberships_on(&self, date: NaiveDate) -> Vec<&Membership> {
+        if self.department_on(date).is_none() {
+            return Vec::new();
+        }
+
+        self.memberships.iter()
+            .filter(|m| m.is_active_on(date))
+            .collect()
+    }
+
+    /// Percentage of this person's time given to a department at the end of the given day.  The
+    /// primary department has whatever the secondary memberships leave over.
+    pub fn allocation_on(&self, department: &Rc<RefCell<Department>>, date: NaiveDate) -> u32 {
+        self.allocations_on(date).into_iter()
+            .find(|(d, _)| Rc::ptr_eq(d, department))
+            .map_or(0, |(_, allocation)| allocation)
+    }
+
+    /// Percentage of this person's time given to a department, counting current memberships.
+    pub fn allocation(&self, department: &Rc<RefCell<Department>>) -> u32 {
+        let secondary: u32 = self.current_memberships().iter().map(|m| m.allocation).sum();
+
+        if Rc::ptr_eq(&self.department, department) {
+            100 - secondary
+        } else {
+            self.current_memberships().iter()
+                .find(|m| Rc::ptr_eq(&m.department, department))
+                .map_or(0, |m| m.allocation)
+        }
+    }
+
+    /// Every department the person belongs to at the end of the given day, primary first, with
+    /// the percentage of their time each is given.  Empty while the person is not employed.
+    pub fn allocations_on(&self, date: NaiveDate) -> Vec<(Rc<RefCell<Department>>, u32)> {
+        let primary = match self.department_on(date) {
+            Some(department) => department,
+            None => return Vec::new(),
+        };
+
+        let secondary = self.memberships_on(date);
+        let mut allocations = vec![(primary, 100 - secondary.iter().map(|m| m.allocation).sum::<u32>())];
+
+        allocations.extend(secondary.iter().map(|m| (Rc::clone(&m.department), m.allocation)));
+        allocations
+    }
+
+    /// Give a percentage of this person's time to a department other than their primary one,
+    /// starting on the given date.  If they are already a member, their allocation is changed
+    /// from that date.  Secondary allocations must leave something for the primary department,
+    /// so they total at most 99%.  Like transfer(), this panics if self is not listed in their
+    /// primary department.
+    pub fn add_membership(&mut self, department: Rc<RefCell<Department>>, allocation: u32, date: NaiveDate) -> Result<(), PersonError> {
+        if self.is_terminated() {
+            return Err(PersonError::Membership(MembershipErr::Terminated));
+        }
+
+        if date < self.date_of_hire {
+            return Err(PersonError::Membership(MembershipErr::BeforeHire));
+        }
+
+        if allocation == 0 || allocation >= 100 {
+            return Err(PersonError::Membership(MembershipErr::InvalidAllocation(allocation)));
+        }
+
+        if Rc::ptr_eq(&self.department, &department) {
+            return Err(PersonError::Membership(MembershipErr::PrimaryDepartment));
+        }
+
+        let existing = self.memberships.iter()
+            .position(|m| m.end.is_none() && Rc::ptr_eq(&m.department, &department));
+
+        if existing.is_some_and(|i| self.memberships[i].allocation == allocation) {
+            return Err(PersonError::Membership(MembershipErr::Unchanged));
+        }
+
+        let total: u32 = self.memberships.iter()
+            .filter(|m| !Rc::ptr_eq(&m.department, &department) && m.end.is_none_or(|end| end > date))
+            .map(|m| m.allocation)
+            .sum::<u32>() + allocation;
+
+        if total >= 100 {
+            return Err(PersonError::Membership(MembershipErr::OverAllocated(total)));
+        }
+
+        match existing {
+            Some(i) if self.memberships[i].start >= date => {
+                self.memberships.remove(i);
+            },
+            Some(i) => self.memberships[i].end = Some(date),
+            None => {
+                let self_ref = self.department.borrow().find_employee(self)
+                    .unwrap_or_else(|| panic!("Error: {}", TransferErr::NotListedInDept));
+
+                department.borrow_mut().insert_member(self_ref, self)
+                    .unwrap_or_else(|_| panic!("Error: {}", MembershipErr::AlreadyListed));
+            },
+        };
+
+        self.memberships.push(Membership { department, allocation, start: date, end: None });
+        self.memberships.sort_by_key(|m| m.start);
+
+        Ok(())
+    }
+
+    /// End this person's secondary membership of a department on the given date.  A membership
+    /// ended on or before the day it starts is removed altogether.
+    pub fn end_membership(&mut self, department: &Rc<RefCell<Department>>, date: NaiveDate) -> Result<(), PersonError> {
+        let index = self.memberships.iter()
+            .position(|m| m.end.is_none() && Rc::ptr_eq(&m.department, department))
+            .ok_or(PersonError::Membership(MembershipErr::NotMember))?;
+
+        self.close_membership(index, date);
+
+        Ok(())
+    }
+
+    /// End the membership at `index` on `date` and drop the person from the department's list
+    /// of members.
+    fn close_membership(&mut self, index: usize, date: NaiveDate) {
+        self.memberships[index].department.borrow_mut().remove_member(self)
+            .unwrap_or_else(|_| panic!("Error: {}", MembershipErr::NotListed));
+
+        if date <= self.memberships[index].start {
+            self.memberships.remove(index);
+        } else {
+            self.memberships[index].end = Some(date);
+        }
+    }
+
+    /// The date the person joined their current department, by hire or transfer.
+    pub fn department_since(&self) -> NaiveDate {
+        self.dept_history.iter()
+            .map(|entry| entry.date)
+            .max()
+            .unwrap_or(self.date_of_hire)
+    }
+
+    /// The department this person belonged to at the end of the given day, or None if they had
+    /// not been hired yet or had already left.  Department history is searched by date, so entries
+    /// recorded out of order are still handled correctly.
+    pub fn department_on(&self, date: NaiveDate) -> Option<Rc<RefCell<Department>>> {
+        if date < self.date_of_hire {
+            return None;
+        }
+
+        if let Some(terminated) = self.termination_date() {
+            if date >= terminated {
+                return None;
+            }
+        }
+
+        let mut current: Option<&DeptEntry> = None;
+        for entry in &self.dept_history {
+            if entry.date <= date && current.is_none_or(|c| entry.date >= c.date) {
+                current = Some(entry);
+            }
+        }
+
+        current.map(|entry| Rc::clone(&entry.department))
+    }
+
+    /// Record this person as having left the company on the given date.  They are removed from
+    /// their current department's employee list and any secondary memberships are ended, but
+    /// their history is kept.  A status change recorded for the same date is replaced.  Fails if
+    /// the date is before hire, before the person joined their current department, or before a
+    /// later status change.  Like transfer(), this panics if self is not listed in their current
+    /// department.
+    pub fn terminate(&mut self, date: NaiveDate) -> Result<(), PersonError> {
+        if self.is_terminated() {
+            return Err(PersonError::AlreadyTerminated);
+        }
+
+        if date < self.date_of_hire {
+            return Err(PersonError::Termination(TerminationErr::BeforeHire));
+        }
+
+        let since = self.department_since();
+        if date < since {
+            return Err(PersonError::Termination(TerminationErr::BeforeLatest(since)));
+        }
+
+        if let Some(later) = self.status_history.iter().map(|e| e.date).filter(|d| *d > date).max() {
+            return Err(PersonError::Termination(TerminationErr::BeforeStatusChange(later)));
+        }
+
+        self.department.borrow_mut().remove_employee(self)
+            .unwrap_or_else(|_| panic!("Error: {}", TransferErr::NotListedInDept));
+
+        while let Some(i) = self.memberships.iter().position(|m| m.end.is_none()) {
+            self.close_membership(i, date);
+        }
+
+        self.status_history.retain(|e| e.date != date);
+        self.status_history.push(StatusEntry { status: Status::Terminated, date });
+
+        Ok(())
+    }
+
     /// transfer an employee from their current department to another. Returns empty Ok(()) on
     /// success, or Err(personnel::PersonError) on failure.  Fails if self is not found listed in
     /// their current department, which would be indicative of an error in this API or mishandling
     /// of an employee Vec.  Can also fail if self is found listed in the department they are being
     /// transferred to.  Neither condition should happen, and will lead to database corruption.
+    /// A secondary membership of the new department ends on the transfer date, since all of the
+    /// person's remaining time belongs to their primary department.
     pub fn transfer(&mut self, department: Rc<RefCell<Department>>, date: NaiveDate) -> Result<(), PersonError> {
+        if self.is_terminated() {
+            return Err(PersonError::Transfer(TransferErr::Terminated));
+        }
+
+        if date < self.date_of_hire {
+            return Err(PersonError::Transfer(TransferErr::BeforeHire));
+        }
+
+        // Department history must stay in date order, with the latest entry the current department
+        if date < self.department_since() {
+            return Err(PersonError::Transfer(TransferErr::BeforeLatest(self.department_since())));
+        }
+
         // Naturally return Err if trying to transfer to the department self is already a member of
-        if self.department.borrow().eq(&department.borrow().deref()) { // This error is non-critical
+        if Rc::ptr_eq(&self.department, &department) { // This error is non-critical
             return Err(PersonError::Transfer(TransferErr::AlreadyInDept));
         }
 
+        // Fold any secondary membership of the new department into the transfer
+        if let Some(i) = self.memberships.iter().position(|m| m.end.is_none() && Rc::ptr_eq(&m.department, &department)) {
+            self.close_membership(i, date);
+        }
+
         // Set up an entry for self.dept_history
         let entry = DeptEntry {
             date,
@@ -119,16 +980,16 @@ impl Person {
         };
 
         // Remove self from old department, store the result
-        let result = self.department.borrow_mut().remove_employee(&self);
+        let result = self.department.borrow_mut().remove_employee(self);
 
         // Get the Rc for self from the previous result, panic! on Err
-        let self_ref = result.expect(
-            &format!("Error: {}", PersonError::Transfer(TransferErr::NotListedInDept))
-        );
+        let self_ref = result.unwrap_or_else(|_| {
+            panic!("Error: {}", PersonError::Transfer(TransferErr::NotListedInDept))
+        });
 
         // Add the Rc to the new department, panic! on Err
-        entry.department.borrow_mut().add_employee(Rc::clone(&self_ref))
-            .expect(&format!("Error: {}", PersonError::Transfer(TransferErr::AlreadyInDept)) );
+        entry.department.borrow_mut().insert_employee(Rc::clone(&self_ref), self)
+            .unwrap_or_else(|_| panic!("Error: {}", PersonError::Transfer(TransferErr::AlreadyInDept)));
 
         // Update self's department Rc
         self.department = Rc::clone(&entry.department);
@@ -144,12 +1005,24 @@ impl Person {
 #[derive(Debug)]
 pub enum PersonError {
     Transfer(TransferErr),
+    Position(PositionErr),
+    Compensation(CompensationError),
+    Status(StatusErr),
+    Membership(MembershipErr),
+    Termination(TerminationErr),
+    AlreadyTerminated,
 }
 
 impl fmt::Display for PersonError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            PersonError::Transfer(e) => write!(f, "Transfer failed: {}", e)
+            PersonError::Transfer(e) => write!(f, "Transfer failed: {}", e),
+            PersonError::Position(e) => write!(f, "Position change failed: {}", e),
+            PersonError::Compensation(e) => write!(f, "Pay change failed: {}", e),
+            PersonError::Status(e) => write!(f, "Status change failed: {}", e),
+            PersonError::Membership(e) => write!(f, "Membership change failed: {}", e),
+            PersonError::Termination(e) => write!(f, "Termination failed: {}", e),
+            PersonError::AlreadyTerminated => write!(f, "Person has already been terminated"),
         }
     }
 }
@@ -166,6 +1039,10 @@ impl From<TransferErr> for PersonError {
 pub enum TransferErr {
     NotListedInDept,
     AlreadyInDept,
+    Terminated,
+    BeforeHire,
+    /// Transfers can't be dated before the person joined their current department.
+    BeforeLatest(NaiveDate),
 }
 
 impl fmt::Display for TransferErr {
@@ -173,12 +1050,111 @@ impl fmt::Display for TransferErr {
         match self {
             TransferErr::NotListedInDept => write!(f, "Person not listed in department"),
             TransferErr::AlreadyInDept => write!(f, "Invalid transfer to same Dept"),
+            TransferErr::Terminated => write!(f, "Person is no longer employed"),
+            TransferErr::BeforeHire => write!(f, "Transfer is dated before the date of hire"),
+            TransferErr::BeforeLatest(date) => write!(f, "Transfer is dated before the person joined their current department on {}", date),
         }
     }
 }
 
 impl Error for TransferErr {}
 
+#[derive(Debug)]
+pub enum PositionErr {
+    Terminated,
+    InvalidFte(f64),
+    BeforeHire,
+    Unchanged,
+}
+
+impl fmt::Display for PositionErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PositionErr::Terminated => write!(f, "Person is no longer employed"),
+            PositionErr::InvalidFte(fte) => write!(f, "FTE must be more than 0 and at most 1, not {}", fte),
+            PositionErr::BeforeHire => write!(f, "Position change is dated before the date of hire"),
+            PositionErr::Unchanged => write!(f, "Person already holds that position"),
+        }
+    }
+}
+
+impl Error for PositionErr {}
+
+#[derive(Debug)]
+pub enum TerminationErr {
+    BeforeHire,
+    /// Terminations can't be dated before the person joined their current department.
+    BeforeLatest(NaiveDate),
+    /// Terminations can't be dated before a status change already recorded.
+    BeforeStatusChange(NaiveDate),
+}
+
+impl fmt::Display for TerminationErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TerminationErr::BeforeHire => write!(f, "Termination is dated before the date of hire"),
+            TerminationErr::BeforeLatest(date) => write!(f, "Termination is dated before the person joined their current department on {}", date),
+            TerminationErr::BeforeStatusChange(date) => write!(f, "Termination is dated before a status change recorded for {}", date),
+        }
+    }
+}
+
+impl Error for TerminationErr {}
+
+#[derive(Debug)]
+pub enum StatusErr {
+    Terminated,
+    BeforeHire,
+    Unchanged,
+}
+
+impl fmt::Display for StatusErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StatusErr::Terminated => write!(f, "Person is no longer employed"),
+            StatusErr::BeforeHire => write!(f, "Status change is dated before the date of hire"),
+            StatusErr::Unchanged => write!(f, "Person already has that status"),
+        }
+    }
+}
+
+impl Error for StatusErr {}
+
+#[derive(Debug)]
+pub enum MembershipErr {
+    Terminated,
+    BeforeHire,
+    InvalidAllocation(u32),
+    /// Secondary allocations would total this much, leaving nothing for the primary department.
+    OverAllocated(u32),
+    PrimaryDepartment,
+    NotMember,
+    Unchanged,
+    NotListed,
+    AlreadyListed,
+    /// Two memberships of the same department are in effect at once.
+    Overlapping,
+}
+
+impl fmt::Display for MembershipErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MembershipErr::Terminated => write!(f, "Person is no longer employed"),
+            MembershipErr::BeforeHire => write!(f, "Membership is dated before the date of hire"),
+            MembershipErr::InvalidAllocation(a) => write!(f, "Allocation must be between 1% and 99%, not {}%", a),
+            MembershipErr::OverAllocated(total) => write!(f, "Secondary allocations would total {}%, leaving nothing for the primary department", total),
+            MembershipErr::PrimaryDepartment => write!(f, "That is the person's primary department"),
+            MembershipErr::NotMember => write!(f, "Person is not a secondary member of that department"),
+            MembershipErr::Unchanged => write!(f, "Person already has that allocation"),
+            MembershipErr::NotListed => write!(f, "Person not listed as a member of department"),
+            MembershipErr::AlreadyListed => write!(f, "Person already listed as a member of department"),
+            MembershipErr::Overlapping => write!(f, "Memberships of the same department overlap"),
+        }
+    }
+}
+
+impl Error for MembershipErr {}
+
 impl fmt::Display for Person {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}, DOH: {}, {}", self.name, self.date_of_hire, self.department.borrow().name())
@@ -191,37 +1167,112 @@ pub struct DeptEntry {
     date: NaiveDate,
 }
 
+impl DeptEntry {
+    pub(crate) fn new(department: Rc<RefCell<Department>>, date: NaiveDate) -> Self {
+        DeptEntry {
+            department,
+            date,
+        }
+    }
+
+    pub fn department(&self) -> Rc<RefCell<Department>> {
+        Rc::clone(&self.department)
+    }
+
+    /// The date the person joined this department.
+    pub fn date(&self) -> NaiveDate {
+        self.date
+    }
+}
+
 impl fmt::Display for DeptEntry {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}, {}", self.department.borrow().name(), self.date.format("%m/%d/%Y"))
+        write!(f, "{}, {}", self.department.borrow().name(), self.date)
+    }
+}
+
+/// A share of a person's time given to a department other than their primary one, from `start`
+/// until `end`, if it has ended.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Membership {
+    department: Rc<RefCell<Department>>,
+    /// Percentage of the person's time, from 1 to 99.
+    allocation: u32,
+    start: NaiveDate,
+    end: Option<NaiveDate>,
+}
+
+impl Membership {
+    pub(crate) fn new(department: Rc<RefCell<Department>>, allocation: u32, start: NaiveDate, end: Option<NaiveDate>) -> Self {
+        Membership {
+            department,
+            allocation,
+            start,
+            end,
+        }
+    }
+
+    pub fn department(&self) -> Rc<RefCell<Department>> {
+        Rc::clone(&self.department)
+    }
+
+    pub fn allocation(&self) -> u32 {
+        self.allocation
+    }
+
+    pub fn start(&self) -> NaiveDate {
+        self.start
+    }
+
+    /// The date the membership ended, if it has.
+    pub fn end(&self) -> Option<NaiveDate> {
+        self.end
+    }
+
+    /// Whether the membership was in effect at the end of the given day.
+    pub fn is_active_on(&self, date: NaiveDate) -> bool {
+        self.start <= date && self.end.is_none_or(|end| date < end)
+    }
+}
+
+impl fmt::Display for Membership {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}, {}%, {}", self.department.borrow().name(), self.allocation, self.start)?;
+
+        if let Some(end) = self.end {
+            write!(f, " to {}", end)?;
+        }
+
+        Ok(())
     }
 }
 
 impl PartialOrd for Person {
     fn partial_cmp(&self, other: &Person) -> Option<Ordering> {
-        Some(self.cmp(&other))
+        Some(self.cmp(other))
     }
 }
 
+/// People are ordered by name, then by id, so that two different people with the same name still
+/// have a fixed order.
 impl Ord for Person {
     fn cmp(&self, other: &Person) -> Ordering {
         self.name.cmp(&other.name)
+            .then_with(|| self.id.cmp(&other.id))
     }
 }
 
 impl PartialOrd for Name {
     fn partial_cmp(&self, other: &Name) -> Option<Ordering> {
-        Some(self.cmp(&other))
+        Some(self.cmp(other))
     }
 }
 
+/// Names are ordered by the default Collation: family name, given name, then every other part of
+/// the name, so that only identical names compare equal.
 impl Ord for Name {
     fn cmp(&self, other: &Name) -> Ordering {
-        if self.last != other.last {
-            self.last.cmp(&other.last)
-        } else {
-            self.first.cmp(&other.first)
-        }
+        Collation::default().compare_names(self, other)
     }
 }
 
@@ -229,9 +1280,17 @@ impl Ord for Name {
 pub struct PersonBuilder {
     name_first: Option<String>,
     name_last: Option<String>,
-    name_mid: Option<String>,
+    name_mid: Vec<String>,
+    name_prefix: Option<String>,
+    name_suffix: Option<String>,
+    name_preferred: Option<String>,
+    name_order: NameOrder,
     doh: Option<NaiveDate>,
     dept: Option<Rc<RefCell<Department>>>,
+    position: Option<Position>,
+    contact: Contact,
+    custom_fields: BTreeMap<String, FieldValue>,
+    employment_type: EmploymentType,
 }
 
 impl PersonBuilder {
@@ -239,9 +1298,17 @@ impl PersonBuilder {
         PersonBuilder {
             name_first: None,
             name_last: None,
-            name_mid: None,
+            name_mid: Vec::new(),
+            name_prefix: None,
+            name_suffix: None,
+            name_preferred: None,
+            name_order: NameOrder::GivenFirst,
             doh: None,
             dept: None,
+            position: None,
+            contact: Contact::default(),
+            custom_fields: BTreeMap::new(),
+            employment_type: EmploymentType::default(),
         }
     }
 
@@ -250,8 +1317,29 @@ impl PersonBuilder {
         self
     }
 
+    /// Add a middle name.  May be called more than once for people with several middle names.
     pub fn middle_name(&mut self, middle_name: &str) -> &mut Self {
-        self.name_mid = Some(String::from(middle_name));
+        self.name_mid.push(String::from(middle_name));
+        self
+    }
+
+    pub fn prefix(&mut self, prefix: &str) -> &mut Self {
+        self.name_prefix = Some(String::from(prefix));
+        self
+    }
+
+    pub fn suffix(&mut self, suffix: &str) -> &mut Self {
+        self.name_suffix = Some(String::from(suffix));
+        self
+    }
+
+    pub fn preferred_name(&mut self, preferred_name: &str) -> &mut Self {
+        self.name_preferred = Some(String::from(preferred_name));
+        self
+    }
+
+    pub fn name_order(&mut self, order: NameOrder) -> &mut Self {
+        self.name_order = order;
         self
     }
 
@@ -265,22 +1353,55 @@ impl PersonBuilder {
         self
     }
 
+    /// The position the person is hired into.  Optional.
+    pub fn position(&mut self, position: Position) -> &mut Self {
+        self.position = Some(position);
+        self
+    }
+
     pub fn department(&mut self, department: Rc<RefCell<Department>>) -> &mut Self {
         self.dept = Some(department);
         self
     }
 
+    /// Defaults to full-time.
+    pub fn employment_type(&mut self, employment_type: EmploymentType) -> &mut Self {
+        self.employment_type = employment_type;
+        self
+    }
+
+    pub fn contact(&mut self, contact: Contact) -> &mut Self {
+        self.contact = contact;
+        self
+    }
+
+    /// Give a custom field a value.  ProgramData::add_person() rejects values that don't match
+    /// its schema.
+    pub fn custom_field(&mut self, name: &str, value: FieldValue) -> &mut Self {
+        self.custom_fields.insert(String::from(name), value);
+        self
+    }
+
     /// Construct an instance of Person from the given values.  Returns Ok(Person) on success, or
     /// Err(Self) on failure.  Function consumes self.
+    #[allow(clippy::result_large_err)]
     pub fn build(self) -> Result<Person, Self> {
         if self.name_first.is_none() || self.name_last.is_none() || self.doh.is_none() || self.dept.is_none() {
             return Err(self);
         }
 
+        if self.position.as_ref().is_some_and(|p| !Position::valid_fte(p.fte)) {
+            return Err(self);
+        }
+
         let name = Name {
             last: self.name_last.unwrap(),
             middle: self.name_mid,
             first: self.name_first.unwrap(),
+            prefix: self.name_prefix,
+            suffix: self.name_suffix,
+            preferred: self.name_preferred,
+            order: self.name_order,
         };
 
         let department_ref = self.dept.unwrap();
@@ -291,11 +1412,25 @@ impl PersonBuilder {
             department: Rc::clone(&department_ref),
         };
 
+        let position_history = self.position.into_iter()
+            .map(|position| PositionEntry { position, date: doh })
+            .collect();
+
         Ok(Person {
+            id: 0,
             name,
             date_of_hire: doh,
             department: Rc::clone(&department_ref),
             dept_history: vec![dept_entry],
+            position_history,
+            compensation_history: Vec::new(),
+            contact: self.contact,
+            custom_fields: self.custom_fields,
+            employment_type: self.employment_type,
+            location: None,
+            cost_center: None,
+            status_history: Vec::new(),
+            memberships: Vec::new(),
         })
     }
 }
\ No newline at end of file
@@ -0,0 +1,862 @@
+//! A small query language for ad-hoc filtering of ProgramData.  A query names what to list,
+//! followed by optional clauses in any order:
+//!
+//! ```text
+//! people where dept = eng and hired < 2018-01-01 order by last, first desc limit 10 select alias, name
+//! departments where headcount > 5 order by name
+//! ```
+//!
+//! Comparisons are `=`, `!=`, `<`, `<=`, `>`, `>=` and `~` (contains).  Text comparisons ignore
+//! case and accents, and sorting follows the ProgramData collation.  Conditions may be combined
+//! with `and`, `or`, `not` and parentheses.  Dates may be written in ISO 8601 form, in the
+//! configured date format, or relative to today (`-2y`).  Custom fields defined in the ProgramData
+//! schema can be used like any other person field.
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::fmt;
+use std::error::Error;
+use std::str::FromStr;
+
+use chrono::naive::NaiveDate;
+
+use crate::data_handling::{DepartmentAlias, PersonAlias, ProgramData};
+use crate::dates::DateFormat;
+use crate::collation::Collation;
+use crate::department::Department;
+use crate::fields::{FieldType, FieldValue, Schema};
+use crate::organization::Grouping;
+
+/// A field that can be filtered on, sorted by, or selected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Field {
+    Alias,
+    /// Every alias of a person or department, the main alias first.
+    Aliases,
+    /// A person's employee number, in the configured format.
+    EmployeeId,
+    First,
+    Middle,
+    Last,
+    Prefix,
+    Suffix,
+    Preferred,
+    /// The name as the person is addressed, e.g. "Bob Smith Jr."
+    Display,
+    Name,
+    Hired,
+    Dept,
+    DeptName,
+    /// Date the person joined their current department.
+    Since,
+    /// Number of transfers since hire.
+    Transfers,
+    /// Every department alias the person has belonged to.
+    History,
+    /// Current job title.
+    Title,
+    /// Current level or grade.
+    Level,
+    /// Current full-time equivalent of a person, or total for a department.
+    Fte,
+    Email,
+    Phone,
+    Office,
+    /// Employment type: full-time, part-time or contractor.
+    Type,
+    /// Current status: active, on leave, suspended or terminated.
+    Status,
+    /// Location code of a department, or the one that applies to a person.
+    Location,
+    /// Cost center code of a department, or the one that applies to a person.
+    CostCenter,
+    /// A custom field defined in the ProgramData schema.
+    Custom(String),
+    Id,
+    Headcount,
+}
+
+impl Field {
+    fn parse(word: &str, source: Source, schema: &Schema) -> Option<Field> {
+        use self::Field::*;
+
+        let field = match (source, word.to_lowercase().as_str()) {
+            (_, "alias") => Alias,
+            (_, "aliases") => Aliases,
+            (_, "name") => Name,
+            (Source::People, "id") => EmployeeId,
+            (Source::People, "first") => First,
+            (Source::People, "middle") => Middle,
+            (Source::People, "last") => Last,
+            (Source::People, "prefix") => Prefix,
+            (Source::People, "suffix") => Suffix,
+            (Source::People, "preferred") => Preferred,
+            (Source::People, "display") => Display,
+            (Source::People, "hired") => Hired,
+            (Source::People, "dept") => Dept,
+            (Source::People, "dept_name") => DeptName,
+            (Source::People, "since") => Since,
+            (Source::People, "transfers") => Transfers,
+            (Source::People, "history") => History,
+            (Source::People, "title") => Title,
+            (Source::People, "level") => Level,
+            (_, "fte") => Fte,
+            (Source::People, "email") => Email,
+            (Source::People, "phone") => Phone,
+            (Source::People, "office") => Office,
+            (Source::People, "type") => Type,
+            (Source::People, "status") => Status,
+            (_, "location") => Location,
+            (_, "cost_center") | (_, "costcenter") => CostCenter,
+            (Source::Departments, "id") => Id,
+            (Source::Departments, "headcount") => Headcount,
+            (Source::People, _) => Custom(schema.get(word)?.name.clone()),
+            _ => return None,
+        };
+
+        Some(field)
+    }
+
+    fn default_projection(source: Source) -> Vec<Field> {
+        match source {
+            Source::People => vec![Field::Alias, Field::Name, Field::Hired, Field::Dept],
+            Source::Departments => vec![Field::Alias, Field::Name, Field::Headcount],
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::Field::*;
+
+        let name = match self {
+            Alias => "alias",
+            Aliases => "aliases",
+            EmployeeId => "id",
+            First => "first",
+            Middle => "middle",
+            Last => "last",
+            Prefix => "prefix",
+            Suffix => "suffix",
+            Preferred => "preferred",
+            Display => "display",
+            Name => "name",
+            Hired => "hired",
+            Dept => "dept",
+            DeptName => "dept_name",
+            Since => "since",
+            Transfers => "transfers",
+            History => "history",
+            Title => "title",
+            Level => "level",
+            Fte => "fte",
+            Email => "email",
+            Phone => "phone",
+            Office => "office",
+            Type => "type",
+            Status => "status",
+            Location => "location",
+            CostCenter => "cost_center",
+            Custom(name) => name,
+            Id => "id",
+            Headcount => "headcount",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// A typed value produced for one field of one row.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Text(String),
+    Date(NaiveDate),
+    Number(i64),
+    Decimal(f64),
+    List(Vec<String>),
+}
+
+impl Value {
+    /// Order two values for sorting.  Text is compared by the given collation, and Null sorts
+    /// last.
+    pub fn compare(&self, other: &Value, collation: &Collation) -> Ordering {
+        use self::Value::*;
+
+        match (self, other) {
+            (Null, Null) => Ordering::Equal,
+            (Null, _) => Ordering::Greater,
+            (_, Null) => Ordering::Less,
+            (Text(a), Text(b)) => collation.compare_text(a, b),
+            (Date(a), Date(b)) => a.cmp(b),
+            (Number(a), Number(b)) => a.cmp(b),
+            (Decimal(a), Decimal(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+            (Number(a), Decimal(b)) => (*a as f64).partial_cmp(b).unwrap_or(Ordering::Equal),
+            (Decimal(a), Number(b)) => a.partial_cmp(&(*b as f64)).unwrap_or(Ordering::Equal),
+            (List(a), List(b)) => a.len().cmp(&b.len()),
+            (a, b) => a.to_string().cmp(&b.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Null => Ok(()),
+            Value::Text(s) => write!(f, "{}", s),
+            Value::Date(d) => write!(f, "{}", d.format("%Y-%m-%d")),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Decimal(n) => write!(f, "{:.2}", n),
+            Value::List(l) => write!(f, "{}", l.join(" ")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    People,
+    Departments,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+impl fmt::Display for CompareOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompareOp::Eq => write!(f, "="),
+            CompareOp::Ne => write!(f, "!="),
+            CompareOp::Lt => write!(f, "<"),
+            CompareOp::Le => write!(f, "<="),
+            CompareOp::Gt => write!(f, ">"),
+            CompareOp::Ge => write!(f, ">="),
+            CompareOp::Contains => write!(f, "~"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Compare(Field, CompareOp, Value),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortKey {
+    pub field: Field,
+    pub descending: bool,
+}
+
+/// A parsed query, ready to be run against ProgramData.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub source: Source,
+    pub filter: Option<Condition>,
+    pub order: Vec<SortKey>,
+    pub limit: Option<usize>,
+    pub projection: Vec<Field>,
+}
+
+/// One row of query output.  Values line up with QueryResult::columns().
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    values: Vec<Value>,
+}
+
+impl Row {
+    pub fn values(&self) -> &Vec<Value> {
+        &self.values
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Value> {
+        self.values.get(index)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryResult {
+    columns: Vec<Field>,
+    rows: Vec<Row>,
+}
+
+impl QueryResult {
+    pub fn columns(&self) -> &Vec<Field> {
+        &self.columns
+    }
+
+    pub fn rows(&self) -> &Vec<Row> {
+        &self.rows
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Op(CompareOp),
+    LParen,
+    RParen,
+    Comma,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Word(w) => write!(f, "{}", w),
+            Token::Quoted(w) => write!(f, "\"{}\"", w),
+            Token::Op(op) => write!(f, "{}", op),
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+            Token::Comma => write!(f, ","),
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => { chars.next(); },
+            '(' => { chars.next(); tokens.push(Token::LParen); },
+            ')' => { chars.next(); tokens.push(Token::RParen); },
+            ',' => { chars.next(); tokens.push(Token::Comma); },
+            '~' => { chars.next(); tokens.push(Token::Op(CompareOp::Contains)); },
+            '=' => { chars.next(); tokens.push(Token::Op(CompareOp::Eq)); },
+            '!' | '<' | '>' => {
+                chars.next();
+                let equals = chars.peek() == Some(&'=');
+                if equals {
+                    chars.next();
+                }
+
+                let op = match (c, equals) {
+                    ('!', true) => CompareOp::Ne,
+                    ('<', false) => CompareOp::Lt,
+                    ('<', true) => CompareOp::Le,
+                    ('>', false) => CompareOp::Gt,
+                    ('>', true) => CompareOp::Ge,
+                    _ => return Err(QueryError::Syntax(String::from("expected '!='"))),
+                };
+                tokens.push(Token::Op(op));
+            },
+            '"' | '\'' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some(q) if q == c => break,
+                        Some(other) => text.push(other),
+                        None => return Err(QueryError::Syntax(String::from("unterminated string"))),
+                    }
+                }
+                tokens.push(Token::Quoted(text));
+            },
+            _ => {
+                let mut word = String::new();
+                while let Some(&w) = chars.peek() {
+                    if w.is_whitespace() || "()=!<>~,\"'".contains(w) {
+                        break;
+                    }
+                    word.push(w);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            },
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    source: Source,
+    date_format: DateFormat,
+    schema: Schema,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    /// Consume the next token if it is the given keyword, ignoring case.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword) => {
+                self.position += 1;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), QueryError> {
+        if self.keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.unexpected(keyword))
+        }
+    }
+
+    fn unexpected(&self, wanted: &str) -> QueryError {
+        match self.peek() {
+            Some(token) => QueryError::Syntax(format!("expected {}, found '{}'", wanted, token)),
+            None => QueryError::Syntax(format!("expected {}, found end of query", wanted)),
+        }
+    }
+
+    fn field(&mut self) -> Result<Field, QueryError> {
+        match self.next() {
+            Some(Token::Word(w)) => Field::parse(&w, self.source, &self.schema)
+                .ok_or(QueryError::UnknownField(w)),
+            _ => {
+                self.position -= 1;
+                Err(self.unexpected("field name"))
+            },
+        }
+    }
+
+    fn condition(&mut self) -> Result<Condition, QueryError> {
+        let mut left = self.conjunction()?;
+
+        while self.keyword("or") {
+            let right = self.conjunction()?;
+            left = Condition::Or(Box::new(left), Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn conjunction(&mut self) -> Result<Condition, QueryError> {
+        let mut left = self.unary()?;
+
+        while self.keyword("and") {
+            let right = self.unary()?;
+            left = Condition::And(Box::new(left), Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Condition, QueryError> {
+        if self.keyword("not") {
+            return Ok(Condition::Not(Box::new(self.unary()?)));
+        }
+
+        if self.peek() == Some(&Token::LParen) {
+            self.position += 1;
+            let inner = self.condition()?;
+            if self.next() != Some(Token::RParen) {
+                self.position -= 1;
+                return Err(self.unexpected("')'"));
+            }
+            return Ok(inner);
+        }
+
+        let field = self.field()?;
+
+        let op = match self.next() {
+            Some(Token::Op(op)) => op,
+            _ => {
+                self.position -= 1;
+                return Err(self.unexpected("comparison operator"));
+            },
+        };
+
+        match self.next() {
+            // Literals are converted to the field's type here, so that a typo in a date is
+            // reported rather than silently matching nothing.
+            Some(Token::Word(w)) | Some(Token::Quoted(w)) => {
+                let literal = literal_value(&field, &w, &self.date_format, &self.schema)?;
+                Ok(Condition::Compare(field, op, literal))
+            },
+            _ => {
+                self.position -= 1;
+                Err(self.unexpected("value"))
+            },
+        }
+    }
+
+    fn order(&mut self) -> Result<Vec<SortKey>, QueryError> {
+        let mut keys = Vec::new();
+
+        loop {
+            let field = self.field()?;
+            let descending = if self.keyword("desc") {
+                true
+            } else {
+                self.keyword("asc");
+                false
+            };
+            keys.push(SortKey { field, descending });
+
+            if self.peek() != Some(&Token::Comma) {
+                return Ok(keys);
+            }
+            self.position += 1;
+        }
+    }
+
+    fn projection(&mut self) -> Result<Vec<Field>, QueryError> {
+        let mut fields = vec![self.field()?];
+
+        while self.peek() == Some(&Token::Comma) {
+            self.position += 1;
+            fields.push(self.field()?);
+        }
+
+        Ok(fields)
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    /// Parse a query, reading dates in ISO 8601 form.  Only built-in fields are recognized.
+    fn from_str(text: &str) -> Result<Query, QueryError> {
+        Query::parse(text, &DateFormat::Iso, &Schema::default())
+    }
+}
+
+impl Query {
+    /// Parse a query, reading dates in the given format as well as ISO 8601 and relative forms
+    /// such as "-1y".  People's custom fields are looked up in `schema`.
+    pub fn parse(text: &str, date_format: &DateFormat, schema: &Schema) -> Result<Query, QueryError> {
+        let tokens = tokenize(text)?;
+
+        let source = match tokens.first() {
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case("people") => Source::People,
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case("employees") => Source::People,
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case("departments") => Source::Departments,
+            _ => return Err(QueryError::Syntax(String::from("query must start with PEOPLE or DEPARTMENTS"))),
+        };
+
+        let mut parser = Parser {
+            tokens,
+            position: 1,
+            source,
+            date_format: date_format.clone(),
+            schema: schema.clone(),
+        };
+        let mut query = Query {
+            source,
+            filter: None,
+            order: Vec::new(),
+            limit: None,
+            projection: Vec::new(),
+        };
+
+        while parser.peek().is_some() {
+            if parser.keyword("where") {
+                if query.filter.is_some() {
+                    return Err(QueryError::Syntax(String::from("WHERE given more than once")));
+                }
+                query.filter = Some(parser.condition()?);
+            } else if parser.keyword("order") {
+                if !query.order.is_empty() {
+                    return Err(QueryError::Syntax(String::from("ORDER BY given more than once")));
+                }
+                parser.expect_keyword("by")?;
+                query.order = parser.order()?;
+            } else if parser.keyword("limit") {
+                if query.limit.is_some() {
+                    return Err(QueryError::Syntax(String::from("LIMIT given more than once")));
+                }
+                query.limit = match parser.next() {
+                    Some(Token::Word(w)) => Some(usize::from_str(&w)
+                        .map_err(|_| QueryError::Syntax(format!("LIMIT expects a number, found '{}'", w)))?),
+                    _ => {
+                        parser.position -= 1;
+                        return Err(parser.unexpected("number"));
+                    },
+                };
+            } else if parser.keyword("select") {
+                if !query.projection.is_empty() {
+                    return Err(QueryError::Syntax(String::from("SELECT given more than once")));
+                }
+                query.projection = parser.projection()?;
+            } else {
+                return Err(parser.unexpected("WHERE, ORDER BY, LIMIT or SELECT"));
+            }
+        }
+
+        if query.projection.is_empty() {
+            query.projection = Field::default_projection(source);
+        }
+
+        Ok(query)
+    }
+}
+
+fn literal_value(field: &Field, literal: &str, date_format: &DateFormat, schema: &Schema) -> Result<Value, QueryError> {
+    let invalid = || QueryError::InvalidValue(field.clone(), String::from(literal));
+
+    match field {
+        Field::Custom(name) => match schema.get(name).map(|def| &def.kind) {
+            Some(FieldType::Date) => date_format.parse(literal)
+                .map(Value::Date)
+                .map_err(|_| invalid()),
+            Some(FieldType::Number) => f64::from_str(literal)
+                .map(Value::Decimal)
+                .map_err(|_| invalid()),
+            _ => Ok(Value::Text(String::from(literal))),
+        },
+        Field::Hired | Field::Since => date_format.parse(literal)
+            .map(Value::Date)
+            .map_err(|_| invalid()),
+        Field::Transfers | Field::Id | Field::Headcount => i64::from_str(literal)
+            .map(Value::Number)
+            .map_err(|_| invalid()),
+        Field::Fte => f64::from_str(literal)
+            .map(Value::Decimal)
+            .map_err(|_| invalid()),
+        _ => Ok(Value::Text(String::from(literal))),
+    }
+}
+
+/// Test a value against a literal.  Text is matched ignoring case and accents.
+fn compare(value: &Value, op: CompareOp, literal: &Value, collation: &Collation) -> bool {
+    if let Value::List(items) = value {
+        // A list field matches if any of its members matches, and "!=" if none of them do.
+        let item_op = if op == CompareOp::Ne { CompareOp::Eq } else { op };
+        let any = items.iter().any(|item| compare(&Value::Text(item.clone()), item_op, literal, collation));
+
+        return if op == CompareOp::Ne { !any } else { any };
+    }
+
+    if let Value::Null = value {
+        return op == CompareOp::Ne;
+    }
+
+    if op == CompareOp::Contains {
+        return collation.fold(&value.to_string()).contains(&collation.fold(&literal.to_string()));
+    }
+
+    let ordering = match (value, literal) {
+        (Value::Text(a), Value::Text(b)) => collation.compare_primary(a, b),
+        _ => value.compare(literal, collation),
+    };
+
+    match op {
+        CompareOp::Eq => ordering == Ordering::Equal,
+        CompareOp::Ne => ordering != Ordering::Equal,
+        CompareOp::Lt => ordering == Ordering::Less,
+        CompareOp::Le => ordering != Ordering::Greater,
+        CompareOp::Gt => ordering == Ordering::Greater,
+        CompareOp::Ge => ordering != Ordering::Less,
+        CompareOp::Contains => unreachable!(),
+    }
+}
+
+fn matches(condition: &Condition, record: &dyn Fn(&Field) -> Value, collation: &Collation) -> bool {
+    match condition {
+        Condition::Compare(field, op, literal) => compare(&record(field), *op, literal, collation),
+        Condition::And(a, b) => matches(a, record, collation) && matches(b, record, collation),
+        Condition::Or(a, b) => matches(a, record, collation) || matches(b, record, collation),
+        Condition::Not(a) => !matches(a, record, collation),
+    }
+}
+
+impl Query {
+    /// Evaluate this query over the given data and return the matching rows.
+    pub fn run(&self, data: &ProgramData) -> QueryResult {
+        let collation = data.collation();
+
+        // Every field that's needed for filtering, sorting or output is fetched for each row.
+        let mut wanted: Vec<Field> = self.projection.clone();
+        for key in &self.order {
+            wanted.push(key.field.clone());
+        }
+
+        let mut records: Vec<Vec<(Field, Value)>> = Vec::new();
+
+        let mut consider = |record: &dyn Fn(&Field) -> Value| {
+            if let Some(filter) = &self.filter {
+                if !matches(filter, record, &collation) {
+                    return;
+                }
+            }
+            records.push(wanted.iter().map(|f| (f.clone(), record(f))).collect());
+        };
+
+        match self.source {
+            Source::People => {
+                for entry in data.employee_list() {
+                    consider(&|field| person_field(data, entry, field));
+                }
+            },
+            Source::Departments => {
+                for entry in data.dept_list() {
+                    consider(&|field| department_field(entry, field));
+                }
+            },
+        }
+
+        let lookup = |record: &Vec<(Field, Value)>, field: &Field| -> Value {
+            record.iter()
+                .find(|(f, _)| f == field)
+                .map(|(_, v)| v.clone())
+                .unwrap_or(Value::Null)
+        };
+
+        records.sort_by(|a, b| {
+            for key in &self.order {
+                let ordering = lookup(a, &key.field).compare(&lookup(b, &key.field), &collation);
+                let ordering = if key.descending { ordering.reverse() } else { ordering };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            Ordering::Equal
+        });
+
+        let limit = self.limit.unwrap_or(records.len());
+
+        let rows = records.iter()
+            .take(limit)
+            .map(|record| Row {
+                values: self.projection.iter().map(|f| lookup(record, f)).collect(),
+            })
+            .collect();
+
+        QueryResult {
+            columns: self.projection.clone(),
+            rows,
+        }
+    }
+}
+
+fn person_field(data: &ProgramData, entry: &PersonAlias, field: &Field) -> Value {
+    let person = entry.pointer();
+    let person = person.borrow();
+    let department = person.department();
+    let dept_alias = |dept: &Rc<RefCell<Department>>| -> String {
+        data.dept_alias_of(dept).cloned().unwrap_or_default()
+    };
+
+    match field {
+        Field::Alias => Value::Text(entry.alias().clone()),
+        Field::Aliases => Value::List(entry.aliases().into_iter().cloned().collect()),
+        Field::EmployeeId => Value::Text(data.employee_id(&person)),
+        Field::First => Value::Text(person.first_name().clone()),
+        Field::Middle if person.middle_names().is_empty() => Value::Null,
+        Field::Middle => Value::Text(person.middle_names().join(" ")),
+        Field::Last => Value::Text(person.last_name().clone()),
+        Field::Prefix => optional_text(&person.name().prefix),
+        Field::Suffix => optional_text(&person.name().suffix),
+        Field::Preferred => optional_text(&person.name().preferred),
+        Field::Display => Value::Text(person.name().display_name()),
+        Field::Name => Value::Text(person.name().to_string()),
+        Field::Hired => Value::Date(person.date_of_hire()),
+        Field::Dept => Value::Text(dept_alias(&department)),
+        Field::DeptName => Value::Text(department.borrow().name().clone()),
+        Field::Since => match person.department_history().last() {
+            Some(entry) => Value::Date(entry.date()),
+            None => Value::Null,
+        },
+        Field::Transfers => Value::Number(person.department_history().len().saturating_sub(1) as i64),
+        Field::History => {
+            let mut aliases: Vec<String> = Vec::new();
+            for entry in person.department_history() {
+                let department = entry.department();
+                let alias = dept_alias(&department);
+                if !aliases.contains(&alias) {
+                    aliases.push(alias);
+                }
+            }
+            Value::List(aliases)
+        },
+        Field::Title => match person.position() {
+            Some(position) => Value::Text(position.title.clone()),
+            None => Value::Null,
+        },
+        Field::Level => match person.position() {
+            Some(position) => optional_text(&position.level),
+            None => Value::Null,
+        },
+        Field::Fte => Value::Decimal(person.fte()),
+        Field::Email => optional_text(&person.contact().email().cloned()),
+        Field::Phone => optional_text(&person.contact().phone().cloned()),
+        Field::Office => optional_text(&person.contact().office().cloned()),
+        Field::Type => Value::Text(person.employment_type().to_string()),
+        Field::Status => Value::Text(person.status().to_string()),
+        Field::Location => optional_text(&data.group_code(&person, &department.borrow(), Grouping::Location)),
+        Field::CostCenter => optional_text(&data.group_code(&person, &department.borrow(), Grouping::CostCenter)),
+        Field::Custom(name) => match person.custom_field(name) {
+            Some(FieldValue::Text(text)) | Some(FieldValue::Choice(text)) => Value::Text(text.clone()),
+            Some(FieldValue::Date(date)) => Value::Date(*date),
+            Some(FieldValue::Number(n)) => Value::Decimal(*n),
+            None => Value::Null,
+        },
+        Field::Id | Field::Headcount => Value::Null,
+    }
+}
+
+fn optional_text(text: &Option<String>) -> Value {
+    match text {
+        Some(text) => Value::Text(text.clone()),
+        None => Value::Null,
+    }
+}
+
+fn department_field(entry: &DepartmentAlias, field: &Field) -> Value {
+    let pointer = entry.borrow_pointer();
+    let department = pointer.borrow();
+
+    match field {
+        Field::Alias => Value::Text(entry.alias().clone()),
+        Field::Aliases => Value::List(entry.aliases().into_iter().cloned().collect()),
+        Field::Name => Value::Text(department.name().clone()),
+        Field::Id => Value::Number(i64::from(department.id())),
+        Field::Headcount => Value::Number(department.employees().len() as i64),
+        Field::Fte => Value::Decimal(department.employees().iter()
+            .chain(department.members())
+            .map(|p| {
+                let p = p.borrow();
+                p.fte() * f64::from(p.allocation(pointer)) / 100.0
+            })
+            .sum()),
+        Field::Location => optional_text(&department.location().cloned()),
+        Field::CostCenter => optional_text(&department.cost_center().cloned()),
+        _ => Value::Null,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    Syntax(String),
+    UnknownField(String),
+    InvalidValue(Field, String),
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QueryError::Syntax(e) => write!(f, "Syntax error: {}", e),
+            QueryError::UnknownField(field) => write!(f, "Unknown field \"{}\"", field),
+            QueryError::InvalidValue(field, value) => write!(f, "Invalid value \"{}\" for {}", value, field),
+        }
+    }
+}
+
+impl Error for QueryError {}
@@ -0,0 +1,519 @@
+//! Reorganization plans.  A plan is a list of department creations, renames, merges and
+//! transfers, built up one step at a time.  It can be previewed as a before and after comparison
+//! of department rosters and checked as a whole, then applied all at once with a single effective
+//! date.  Nothing is changed until the plan is applied, and if any step fails, nothing is changed
+//! at all.
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::fmt;
+use std::error::Error;
+
+use chrono::naive::NaiveDate;
+
+use crate::data_handling::{AliasEntry, DataError, ProgramData, check_alias};
+use crate::personnel::{Name, Person};
+use crate::storage::Snapshot;
+
+/// One change in a ReorgPlan.  Departments and people are named by any of their aliases.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// Create a department with the given alias and name.
+    Create { alias: String, name: String },
+    /// Give a department a new name.
+    Rename { department: String, name: String },
+    /// Make `department` a person's primary department.
+    Transfer { person: String, department: String },
+    /// Move everyone in `from` into `into`, including secondary members.  `from` is kept, empty,
+    /// since history still refers to it.
+    Merge { from: String, into: String },
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Step::Create { alias, name } => write!(f, "create \"{}\": {}", alias, name),
+            Step::Rename { department, name } => write!(f, "rename \"{}\" to {}", department, name),
+            Step::Transfer { person, department } => write!(f, "transfer \"{}\" to \"{}\"", person, department),
+            Step::Merge { from, into } => write!(f, "merge \"{}\" into \"{}\"", from, into),
+        }
+    }
+}
+
+/// A department's primary employees at one point in a plan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Roster {
+    /// Main alias of the department.
+    pub alias: String,
+    pub name: String,
+    /// Main alias and name of each employee, sorted by name.
+    pub people: Vec<(String, Name)>,
+}
+
+/// How one department's roster differs after a plan is applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RosterChange {
+    pub alias: String,
+    /// The name before the plan, or None for a department the plan creates.
+    pub old_name: Option<String>,
+    pub new_name: String,
+    /// People joining, with the alias of the department they come from.
+    pub joined: Vec<(String, Name, String)>,
+    /// People leaving, with the alias of the department they go to.
+    pub left: Vec<(String, Name, String)>,
+    pub headcount_before: usize,
+    pub headcount_after: usize,
+}
+
+/// Department rosters before and after a plan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReorgPreview {
+    before: Vec<Roster>,
+    after: Vec<Roster>,
+}
+
+impl ReorgPreview {
+    /// Every department before the plan, in alias order.
+    pub fn before(&self) -> &Vec<Roster> {
+        &self.before
+    }
+
+    /// Every department after the plan, including new ones, in alias order.
+    pub fn after(&self) -> &Vec<Roster> {
+        &self.after
+    }
+
+    /// Departments that are created, renamed, or gain or lose people.
+    pub fn changes(&self) -> Vec<RosterChange> {
+        let dept_of = |rosters: &Vec<Roster>, person: &str| rosters.iter()
+            .find(|r| r.people.iter().any(|(alias, _)| alias == person))
+            .map(|r| r.alias.clone())
+            .unwrap_or_default();
+
+        let mut changes = Vec::new();
+
+        for after in &self.after {
+            let before = self.before.iter().find(|r| r.alias == after.alias);
+            let before_people: &[(String, Name)] = before.map_or(&[], |r| &r.people);
+
+            let joined: Vec<(String, Name, String)> = after.people.iter()
+                .filter(|(alias, _)| !before_people.iter().any(|(a, _)| a == alias))
+                .map(|(alias, name)| (alias.clone(), name.clone(), dept_of(&self.before, alias)))
+                .collect();
+            let left: Vec<(String, Name, String)> = before_people.iter()
+                .filter(|(alias, _)| !after.people.iter().any(|(a, _)| a == alias))
+                .map(|(alias, name)| (alias.clone(), name.clone(), dept_of(&self.after, alias)))
+                .collect();
+
+            let old_name = before.map(|r| r.name.clone());
+
+            if old_name.as_ref() == Some(&after.name) && joined.is_empty() && left.is_empty() {
+                continue;
+            }
+
+            changes.push(RosterChange {
+                alias: after.alias.clone(),
+                old_name,
+                new_name: after.name.clone(),
+                joined,
+                left,
+                headcount_before: before_people.len(),
+                headcount_after: after.people.len(),
+            });
+        }
+
+        changes
+    }
+}
+
+/// The state of the organization partway through a plan, used to check and preview it without
+/// touching ProgramData.
+struct Model {
+    rosters: Vec<Roster>,
+    /// Main aliases of departments emptied by a merge.
+    merged: Vec<String>,
+    /// Main aliases of people moved by a Transfer step.
+    moved: Vec<String>,
+    /// Current secondary memberships, as the main aliases of the person and department and the
+    /// allocation.
+    members: Vec<(String, String, u32)>,
+}
+
+impl Model {
+    fn new(data: &ProgramData) -> Self {
+        let alias_of = |person: &Rc<RefCell<Person>>| data.employee_list()
+            .find(|e| Rc::ptr_eq(&e.pointer(), person))
+            .map(|e| e.alias().clone())
+            .unwrap_or_default();
+        let mut members = Vec::new();
+
+        let rosters = data.dept_list()
+            .map(|entry| {
+                let department = entry.borrow_pointer().borrow();
+                let people = department.employees().iter()
+                    .map(|person| (alias_of(person), person.borrow().name().clone()))
+                    .collect();
+
+                for person in department.members() {
+                    let allocation = person.borrow().allocation(entry.borrow_pointer());
+                    members.push((alias_of(person), entry.alias().clone(), allocation));
+                }
+
+                Roster { alias: entry.alias().clone(), name: department.name().clone(), people }
+            })
+            .collect();
+
+        Model { rosters, merged: Vec::new(), moved: Vec::new(), members }
+    }
+
+    /// The main alias of a department, whether it exists already or is created by the plan.
+    fn department(&self, data: &ProgramData, alias: &str) -> Option<String> {
+        match data.alias_entry(alias) {
+            Some(AliasEntry::Department(entry)) => Some(entry.alias().clone()),
+            Some(AliasEntry::Person(_)) => None,
+            None => self.rosters.iter().find(|r| r.alias == alias).map(|r| r.alias.clone()),
+        }
+    }
+
+    fn roster(&mut self, alias: &str) -> &mut Roster {
+        self.rosters.iter_mut()
+            .find(|r| r.alias == alias)
+            .expect("roster for known department")
+    }
+
+    /// Move a person between rosters.
+    fn shift(&mut self, person: &str, from: &str, to: &str) {
+        let roster = self.roster(from);
+        let entry = match roster.people.iter().position(|(alias, _)| alias == person) {
+            Some(i) => roster.people.remove(i),
+            None => return,
+        };
+
+        self.roster(to).people.push(entry);
+    }
+
+    /// Check one step and, if it is sound, carry it out on the model.
+    fn step(&mut self, data: &ProgramData, step: &Step, date: Option<NaiveDate>) -> Result<(), ReorgError> {
+        let known = |model: &Model, alias: &str| model.department(data, alias)
+            .ok_or_else(|| ReorgError::NoSuchDept(String::from(alias)));
+
+        match step {
+            Step::Create { alias, name } => {
+                check_alias(alias).map_err(|_| ReorgError::InvalidAlias(alias.clone()))?;
+
+                if data.alias_in_use(alias) || self.rosters.iter().any(|r| r.alias == *alias) {
+                    return Err(ReorgError::AliasInUse(alias.clone()));
+                }
+                if name.trim().is_empty() {
+                    return Err(ReorgError::BlankName);
+                }
+
+                self.rosters.push(Roster { alias: alias.clone(), name: String::from(name.trim()), people: Vec::new() });
+                self.rosters.sort_by(|a, b| data.collation().compare_text(&a.alias, &b.alias));
+            },
+            Step::Rename { department, name } => {
+                let department = known(self, department)?;
+
+                if name.trim().is_empty() {
+                    return Err(ReorgError::BlankName);
+                }
+
+                self.roster(&department).name = String::from(name.trim());
+            },
+            Step::Transfer { person, department } => {
+                let entry = match data.alias_entry(person) {
+                    Some(AliasEntry::Person(entry)) => entry,
+                    _ => return Err(ReorgError::NoSuchPerson(person.clone())),
+                };
+                let alias = entry.alias().clone();
+                let department = known(self, department)?;
+
+                let current = self.rosters.iter()
+                    .find(|r| r.people.iter().any(|(a, _)| *a == alias))
+                    .map(|r| r.alias.clone())
+                    .ok_or_else(|| ReorgError::Terminated(alias.clone()))?;
+
+                if self.merged.contains(&department) {
+                    return Err(ReorgError::MergedAway(department));
+                }
+                if current == department {
+                    return Err(ReorgError::AlreadyInDept(alias, department));
+                }
+                if self.moved.contains(&alias) {
+                    return Err(ReorgError::MovedTwice(alias));
+                }
+                if date.is_some_and(|d| d < entry.pointer().borrow().date_of_hire()) {
+                    return Err(ReorgError::BeforeHire(alias));
+                }
+                if date.is_some_and(|d| d < entry.pointer().borrow().department_since()) {
+                    return Err(ReorgError::BeforeTransfer(alias));
+                }
+
+                // A membership of the new department is folded into the transfer
+                self.members.retain(|(p, d, _)| *p != alias || *d != department);
+                self.shift(&alias, &current, &department);
+                self.moved.push(alias);
+            },
+            Step::Merge { from, into } => {
+                let from = known(self, from)?;
+                let into = known(self, into)?;
+
+                if from == into {
+                    return Err(ReorgError::SameDept(from));
+                }
+                if self.merged.contains(&from) {
+                    return Err(ReorgError::MergedAway(from));
+                }
+                if self.merged.contains(&into) {
+                    return Err(ReorgError::MergedAway(into));
+                }
+
+                let people: Vec<String> = self.roster(&from).people.iter().map(|(a, _)| a.clone()).collect();
+
+                if let Some(date) = date {
+                    for alias in &people {
+                        let dates = data.personnel().get(alias).map(|p| (p.borrow().date_of_hire(), p.borrow().department_since()));
+                        if dates.is_some_and(|(hired, _)| date < hired) {
+                            return Err(ReorgError::BeforeHire(alias.clone()));
+                        }
+                        if dates.is_some_and(|(_, since)| date < since) {
+                            return Err(ReorgError::BeforeTransfer(alias.clone()));
+                        }
+                    }
+                }
+
+                for alias in &people {
+                    self.members.retain(|(p, d, _)| p != alias || *d != into);
+                    self.shift(alias, &from, &into);
+                }
+
+                // Secondary members of `from` have their share added to any they have in `into`
+                let members: Vec<(String, u32)> = self.members.iter()
+                    .filter(|(_, d, _)| *d == from)
+                    .map(|(p, _, allocation)| (p.clone(), *allocation))
+                    .collect();
+                self.members.retain(|(_, d, _)| *d != from);
+
+                for (alias, allocation) in members {
+                    if date.is_some_and(|date| data.personnel().get(&alias).is_some_and(|p| date < p.borrow().date_of_hire())) {
+                        return Err(ReorgError::BeforeHire(alias));
+                    }
+                    if self.roster(&into).people.iter().any(|(a, _)| *a == alias) {
+                        continue;
+                    }
+
+                    let total = allocation + self.members.iter()
+                        .filter(|(p, _, _)| *p == alias)
+                        .map(|(_, _, allocation)| allocation)
+                        .sum::<u32>();
+                    if total >= 100 {
+                        return Err(ReorgError::OverAllocated(alias, total));
+                    }
+
+                    match self.members.iter_mut().find(|(p, d, _)| *p == alias && *d == into) {
+                        Some(membership) => membership.2 += allocation,
+                        None => self.members.push((alias, into.clone(), allocation)),
+                    }
+                }
+                self.merged.push(from);
+            },
+        };
+
+        Ok(())
+    }
+
+    /// Rosters with their people in name order.
+    fn finish(mut self, data: &ProgramData) -> Vec<Roster> {
+        let collation = data.collation();
+
+        for roster in &mut self.rosters {
+            roster.people.sort_by(|a, b| collation.compare_names(&a.1, &b.1)
+                .then_with(|| collation.compare_text(&a.0, &b.0)));
+        }
+
+        self.rosters
+    }
+}
+
+/// A set of changes to apply together.  See the module documentation.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ReorgPlan {
+    steps: Vec<Step>,
+}
+
+impl ReorgPlan {
+    pub fn new() -> Self {
+        ReorgPlan::default()
+    }
+
+    /// The steps in the order they will be applied.
+    pub fn steps(&self) -> &Vec<Step> {
+        &self.steps
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Add a step to the end of the plan.  Steps aren't checked until validate() or apply().
+    pub fn add(&mut self, step: Step) {
+        self.steps.push(step);
+    }
+
+    /// Take out the step at `index`, counting from zero.
+    pub fn remove(&mut self, index: usize) -> Option<Step> {
+        if index < self.steps.len() {
+            Some(self.steps.remove(index))
+        } else {
+            None
+        }
+    }
+
+    /// Check every step against `data`, taking account of the steps before it, as if the plan
+    /// were applied on `date`.  Returns each problem with the number of its step, counting from
+    /// one.  A step with a problem is skipped, so later steps are checked as if it were left out.
+    pub fn validate(&self, data: &ProgramData, date: NaiveDate) -> Vec<(usize, ReorgError)> {
+        let mut model = Model::new(data);
+
+        self.steps.iter().enumerate()
+            .filter_map(|(i, step)| model.step(data, step, Some(date)).err().map(|e| (i + 1, e)))
+            .collect()
+    }
+
+    /// Department rosters before and after the plan.  Steps with problems are left out; see
+    /// validate().
+    pub fn preview(&self, data: &ProgramData) -> ReorgPreview {
+        let before = Model::new(data).finish(data);
+
+        let mut model = Model::new(data);
+        for step in &self.steps {
+            let _ = model.step(data, step, None);
+        }
+
+        ReorgPreview { before, after: model.finish(data) }
+    }
+
+    /// Apply every step with the given effective date.  The plan is checked first, and nothing is
+    /// changed if it has any problems.  If a step fails anyway, the changes already made are
+    /// rolled back.
+    pub fn apply(&self, data: &mut ProgramData, date: NaiveDate) -> Result<(), ReorgError> {
+        if self.steps.is_empty() {
+            return Err(ReorgError::EmptyPlan);
+        }
+
+        let problems = self.validate(data, date);
+        if !problems.is_empty() {
+            return Err(ReorgError::Invalid(problems));
+        }
+
+        let snapshot = Snapshot::take(data);
+
+        if let Err(e) = self.apply_steps(data, date) {
+            *data = snapshot.restore().map_err(|e| ReorgError::Failed(DataError::Storage(e)))?;
+            return Err(ReorgError::Failed(e));
+        }
+
+        Ok(())
+    }
+
+    fn apply_steps(&self, data: &mut ProgramData, date: NaiveDate) -> Result<(), DataError> {
+        for step in &self.steps {
+            match step {
+                Step::Create { alias, name } => {
+                    data.add_dept(alias, name.trim())?;
+                },
+                Step::Rename { department, name } => data.rename_dept(department, name)?,
+                Step::Transfer { person, department } => {
+                    let person = data.personnel().get(person).cloned().ok_or(DataError::NoSuchPerson)?;
+                    let department = data.departments().get(department).cloned().ok_or(DataError::NoSuchDept)?;
+
+                    person.borrow_mut().transfer(department, date)?;
+                },
+                Step::Merge { from, into } => {
+                    let from = data.departments().get(from).cloned().ok_or(DataError::NoSuchDept)?;
+                    let into = data.departments().get(into).cloned().ok_or(DataError::NoSuchDept)?;
+
+                    let employees = from.borrow().employees().clone();
+                    for person in employees {
+                        person.borrow_mut().transfer(Rc::clone(&into), date)?;
+                    }
+
+                    // Secondary members keep their share of time, now given to `into`
+                    let members = from.borrow().members().clone();
+                    for person in members {
+                        let mut person = person.borrow_mut();
+                        let allocation = person.allocation(&from);
+
+                        person.end_membership(&from, date)?;
+                        if !Rc::ptr_eq(&person.department(), &into) {
+                            let allocation = allocation + person.allocation(&into);
+                            person.add_membership(Rc::clone(&into), allocation, date)?;
+                        }
+                    }
+                },
+            };
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum ReorgError {
+    NoSuchDept(String),
+    NoSuchPerson(String),
+    AliasInUse(String),
+    InvalidAlias(String),
+    BlankName,
+    /// The person has left, so has no department to move from.
+    Terminated(String),
+    /// Person and department aliases.
+    AlreadyInDept(String, String),
+    MovedTwice(String),
+    SameDept(String),
+    /// The department was emptied by an earlier merge in the plan.
+    MergedAway(String),
+    BeforeHire(String),
+    /// The plan's date is before the person joined their current department.
+    BeforeTransfer(String),
+    /// A merge would give the person this much secondary allocation, leaving nothing for their
+    /// primary department.
+    OverAllocated(String, u32),
+    EmptyPlan,
+    /// Problems found by validate(), with their step numbers.
+    Invalid(Vec<(usize, ReorgError)>),
+    /// A step failed while the plan was being applied, and the changes were rolled back.
+    Failed(DataError),
+}
+
+impl fmt::Display for ReorgError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::ReorgError::*;
+
+        match self {
+            NoSuchDept(d) => write!(f, "No department known as \"{}\"", d),
+            NoSuchPerson(p) => write!(f, "No employee known as \"{}\"", p),
+            AliasInUse(a) => write!(f, "Alias \"{}\" is already in use", a),
+            InvalidAlias(a) => write!(f, "\"{}\" is not a valid alias; aliases must be a single word", a),
+            BlankName => write!(f, "Department name can't be blank"),
+            Terminated(p) => write!(f, "\"{}\" is no longer employed", p),
+            AlreadyInDept(p, d) => write!(f, "\"{}\" is already in \"{}\"", p, d),
+            MovedTwice(p) => write!(f, "\"{}\" is transferred more than once", p),
+            SameDept(d) => write!(f, "Can't merge \"{}\" into itself", d),
+            MergedAway(d) => write!(f, "\"{}\" has been merged into another department", d),
+            BeforeHire(p) => write!(f, "The plan's date is before \"{}\" was hired", p),
+            BeforeTransfer(p) => write!(f, "The plan's date is before \"{}\" joined their current department", p),
+            OverAllocated(p, total) => write!(f, "\"{}\" would have {}% of their time in secondary departments, leaving nothing for their primary one", p, total),
+            EmptyPlan => write!(f, "The plan has no steps"),
+            Invalid(problems) => {
+                write!(f, "The plan has {} problem(s):", problems.len())?;
+                for (step, problem) in problems {
+                    write!(f, "\n    step {}: {}", step, problem)?;
+                }
+                Ok(())
+            },
+            Failed(e) => write!(f, "The plan could not be applied, and nothing was changed: {}", e),
+        }
+    }
+}
+
+impl Error for ReorgError {}
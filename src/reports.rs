@@ -0,0 +1,723 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::naive::NaiveDate;
+use chrono::Datelike;
+use serde_json::json;
+
+use crate::data_handling::ProgramData;
+use crate::department::Department;
+use crate::personnel::{DeptEntry, Name, Status};
+use crate::organization::Grouping;
+
+/// Length of the reporting periods a date range is split into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Month,
+    Quarter,
+}
+
+impl Period {
+    /// The first day of the period containing `date`.
+    pub fn start_of(&self, date: NaiveDate) -> NaiveDate {
+        let month = match self {
+            Period::Month => date.month(),
+            Period::Quarter => (date.month0() / 3) * 3 + 1,
+        };
+
+        NaiveDate::from_ymd(date.year(), month, 1)
+    }
+
+    /// The first day of the period following the one that starts on `start`.
+    fn next(&self, start: NaiveDate) -> NaiveDate {
+        let months = match self {
+            Period::Month => 1,
+            Period::Quarter => 3,
+        };
+
+        let month0 = start.month0() + months;
+        NaiveDate::from_ymd(start.year() + (month0 / 12) as i32, month0 % 12 + 1, 1)
+    }
+
+    /// A short label for the period starting on `start`, e.g. "2019-04" or "2019-Q2".
+    pub fn label(&self, start: NaiveDate) -> String {
+        match self {
+            Period::Month => format!("{}", start.format("%Y-%m")),
+            Period::Quarter => format!("{}-Q{}", start.year(), start.month0() / 3 + 1),
+        }
+    }
+}
+
+impl FromStr for Period {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Period, ()> {
+        match s.to_lowercase().as_str() {
+            "month" | "monthly" => Ok(Period::Month),
+            "quarter" | "quarterly" => Ok(Period::Quarter),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Period::Month => write!(f, "month"),
+            Period::Quarter => write!(f, "quarter"),
+        }
+    }
+}
+
+/// Label used in place of a department alias for company-wide totals.
+pub const ALL_DEPARTMENTS: &str = "(all)";
+
+/// Headcount and movement figures for one department over one period.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeriodStats {
+    pub period: String,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub department: String,
+    pub headcount_start: u32,
+    pub headcount_end: u32,
+    /// Full-time equivalent of the people counted in headcount_start and headcount_end, plus the
+    /// share of time given to the department by secondary members.  People split between
+    /// departments count towards each in proportion to their allocation.
+    pub fte_start: f64,
+    pub fte_end: f64,
+    /// People on leave at the end of the period, whether or not they are counted in headcount.
+    pub on_leave: u32,
+    pub hires: u32,
+    pub transfers_in: u32,
+    pub transfers_out: u32,
+    pub terminations: u32,
+}
+
+impl PeriodStats {
+    fn new(period: String, start: NaiveDate, end: NaiveDate, department: &str) -> Self {
+        PeriodStats {
+            period,
+            start,
+            end,
+            department: String::from(department),
+            headcount_start: 0,
+            headcount_end: 0,
+            fte_start: 0.0,
+            fte_end: 0.0,
+            on_leave: 0,
+            hires: 0,
+            transfers_in: 0,
+            transfers_out: 0,
+            terminations: 0,
+        }
+    }
+
+    /// Terminations as a percentage of the average headcount over the period.
+    pub fn turnover_rate(&self) -> f64 {
+        let average = f64::from(self.headcount_start + self.headcount_end) / 2.0;
+
+        if average == 0.0 {
+            0.0
+        } else {
+            f64::from(self.terminations) / average * 100.0
+        }
+    }
+}
+
+/// Headcount, hires, transfers and terminations per department for each period in a date range.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeadcountReport {
+    period: Period,
+    from: NaiveDate,
+    to: NaiveDate,
+    exclude_leave: bool,
+    rows: Vec<PeriodStats>,
+}
+
+impl HeadcountReport {
+    /// Build a report covering every period that overlaps `from` through `to`, inclusive.  With
+    /// `exclude_leave`, people on leave at the start or end of a period aren't counted in that
+    /// headcount or FTE.
+    pub fn build(data: &ProgramData, period: Period, from: NaiveDate, to: NaiveDate, exclude_leave: bool) -> Self {
+        let departments: Vec<(String, Rc<RefCell<Department>>)> = data.dept_list()
+            .map(|d| (d.alias().clone(), d.clone_pointer()))
+            .collect();
+        let index = |department: Option<Rc<RefCell<Department>>>| {
+            department.and_then(|d| departments.iter().position(|(_, p)| Rc::ptr_eq(p, &d)))
+        };
+
+        let mut rows = Vec::new();
+        let mut start = period.start_of(from);
+
+        while start <= to {
+            let next = period.next(start);
+            let end = next.pred();
+            let label = period.label(start);
+
+            let mut stats: Vec<PeriodStats> = departments.iter()
+                .map(|(alias, _)| PeriodStats::new(label.clone(), start, end, alias))
+                .collect();
+
+            for entry in data.employee_list() {
+                let person = entry.pointer();
+                let person = person.borrow();
+                let hired = person.date_of_hire();
+
+                let counted = |date: NaiveDate| !(exclude_leave && person.status_on(date) == Some(Status::OnLeave));
+
+                // Headcount goes to the primary department, the first allocation; FTE is shared
+                let day = start.pred();
+                for (n, (department, allocation)) in person.allocations_on(day).into_iter().enumerate() {
+                    if let Some(i) = index(Some(department)) {
+                        if counted(day) {
+                            stats[i].headcount_start += u32::from(n == 0);
+                            stats[i].fte_start += person.fte_on(day) * f64::from(allocation) / 100.0;
+                        }
+                    }
+                }
+
+                for (n, (department, allocation)) in person.allocations_on(end).into_iter().enumerate() {
+                    if let Some(i) = index(Some(department)) {
+                        if counted(end) {
+                            stats[i].headcount_end += u32::from(n == 0);
+                            stats[i].fte_end += person.fte_on(end) * f64::from(allocation) / 100.0;
+                        }
+                        if n == 0 && person.status_on(end) == Some(Status::OnLeave) {
+                            stats[i].on_leave += 1;
+                        }
+                    }
+                }
+
+                if hired >= start && hired <= end {
+                    if let Some(i) = index(person.department_on(hired)) {
+                        stats[i].hires += 1;
+                    }
+                }
+
+                let mut moves: Vec<&DeptEntry> = person.department_history().iter().collect();
+                moves.sort_by_key(|e| e.date());
+
+                for pair in moves.windows(2) {
+                    let date = pair[1].date();
+
+                    if date < start || date > end || date <= hired {
+                        continue;
+                    }
+
+                    let (from_dept, to_dept) = (pair[0].department(), pair[1].department());
+                    if Rc::ptr_eq(&from_dept, &to_dept) {
+                        continue;
+                    }
+
+                    if let Some(i) = index(Some(from_dept)) {
+                        stats[i].transfers_out += 1;
+                    }
+                    if let Some(i) = index(Some(to_dept)) {
+                        stats[i].transfers_in += 1;
+                    }
+                }
+
+                if let Some(terminated) = person.termination_date() {
+                    if terminated >= start && terminated <= end {
+                        if let Some(i) = index(person.department_on(terminated.pred())) {
+                            stats[i].terminations += 1;
+                        }
+                    }
+                }
+            }
+
+            let mut total = PeriodStats::new(label, start, end, ALL_DEPARTMENTS);
+            for s in &stats {
+                total.headcount_start += s.headcount_start;
+                total.headcount_end += s.headcount_end;
+                total.fte_start += s.fte_start;
+                total.fte_end += s.fte_end;
+                total.on_leave += s.on_leave;
+                total.hires += s.hires;
+                total.transfers_in += s.transfers_in;
+                total.transfers_out += s.transfers_out;
+                total.terminations += s.terminations;
+            }
+
+            rows.append(&mut stats);
+            rows.push(total);
+
+            start = next;
+        }
+
+        HeadcountReport {
+            period,
+            from,
+            to,
+            exclude_leave,
+            rows,
+        }
+    }
+
+    pub fn period(&self) -> Period {
+        self.period
+    }
+
+    pub fn from(&self) -> NaiveDate {
+        self.from
+    }
+
+    pub fn to(&self) -> NaiveDate {
+        self.to
+    }
+
+    /// Whether people on leave were left out of headcount and FTE.
+    pub fn exclude_leave(&self) -> bool {
+        self.exclude_leave
+    }
+
+    pub fn rows(&self) -> &Vec<PeriodStats> {
+        &self.rows
+    }
+
+    /// Column headers matching the cells returned by table_rows().
+    pub fn headers() -> Vec<String> {
+        ["period", "dept", "start", "end", "fte start", "fte end", "on leave", "hires", "in", "out", "terms",
+         "turnover %"].iter()
+            .map(|h| String::from(*h))
+            .collect()
+    }
+
+    /// The report as rows of display text, for printing as a table.
+    pub fn table_rows(&self) -> Vec<Vec<String>> {
+        self.rows.iter()
+            .map(|r| vec![
+                r.period.clone(),
+                r.department.clone(),
+                r.headcount_start.to_string(),
+                r.headcount_end.to_string(),
+                format!("{:.2}", r.fte_start),
+                format!("{:.2}", r.fte_end),
+                r.on_leave.to_string(),
+                r.hires.to_string(),
+                r.transfers_in.to_string(),
+                r.transfers_out.to_string(),
+                r.terminations.to_string(),
+                format!("{:.1}", r.turnover_rate()),
+            ])
+            .collect()
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let rows: Vec<serde_json::Value> = self.rows.iter()
+            .map(|r| json!({
+                "period": r.period,
+                "start": r.start.to_string(),
+                "end": r.end.to_string(),
+                "department": r.department,
+                "headcount_start": r.headcount_start,
+                "headcount_end": r.headcount_end,
+                "fte_start": r.fte_start,
+                "fte_end": r.fte_end,
+                "on_leave": r.on_leave,
+                "hires": r.hires,
+                "transfers_in": r.transfers_in,
+                "transfers_out": r.transfers_out,
+                "terminations": r.terminations,
+                "turnover_rate": r.turnover_rate(),
+            }))
+            .collect();
+
+        json!({
+            "period": self.period.to_string(),
+            "from": self.from.to_string(),
+            "to": self.to.to_string(),
+            "exclude_leave": self.exclude_leave,
+            "rows": rows,
+        })
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("period,start,end,department,headcount_start,headcount_end,\
+            fte_start,fte_end,on_leave,hires,transfers_in,transfers_out,terminations,turnover_rate\n");
+
+        for r in &self.rows {
+            csv.push_str(&format!("{},{},{},{},{},{},{:.2},{:.2},{},{},{},{},{},{:.2}\n",
+                r.period, r.start, r.end, csv_field(&r.department), r.headcount_start,
+                r.headcount_end, r.fte_start, r.fte_end, r.on_leave, r.hires, r.transfers_in, r.transfers_out, r.terminations,
+                r.turnover_rate()));
+        }
+
+        csv
+    }
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break.
+pub fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        String::from(text)
+    }
+}
+
+/// Label used in place of a title for people with no recorded position.
+pub const NO_TITLE: &str = "(no title)";
+
+/// One person listed under a title in a PositionReport.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionHolder {
+    pub alias: String,
+    pub employee_id: String,
+    pub name: Name,
+    pub level: Option<String>,
+    /// Full-time equivalent given to the department or group the person is listed under.
+    pub fte: f64,
+    /// Percentage of the person's time given to the department they are listed under.
+    pub allocation: u32,
+    /// False if the person is listed through a secondary membership.
+    pub primary: bool,
+}
+
+/// Everyone in one department holding one title.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TitleGroup {
+    pub department: String,
+    pub title: String,
+    pub people: Vec<PositionHolder>,
+}
+
+impl TitleGroup {
+    /// People whose primary department this is.  Secondary members add to fte() only.
+    pub fn headcount(&self) -> usize {
+        self.people.iter().filter(|p| p.primary).count()
+    }
+
+    pub fn fte(&self) -> f64 {
+        self.people.iter().map(|p| p.fte).sum()
+    }
+}
+
+/// Employees of each department grouped by job title, as of a given date.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionReport {
+    as_of: NaiveDate,
+    groups: Vec<TitleGroup>,
+}
+
+impl PositionReport {
+    /// Group everyone employed on `as_of` by department, then by title.  Departments are in alias
+    /// order, titles alphabetical with untitled people last, and people sorted by name.  Only the
+    /// department with alias `department` is included if one is given.  Secondary members are
+    /// listed in each department they belong to, with their share of FTE.
+    pub fn build(data: &ProgramData, as_of: NaiveDate, department: Option<&str>) -> Self {
+        let collation = data.collation();
+        let mut groups = Vec::new();
+
+        for dept in data.dept_list() {
+            if department.is_some_and(|alias| !dept.aliases().iter().any(|a| *a == alias)) {
+                continue;
+            }
+
+            let mut dept_groups: Vec<TitleGroup> = Vec::new();
+
+            for entry in data.employee_list() {
+                let person = entry.pointer();
+                let person = person.borrow();
+
+                let allocations = person.allocations_on(as_of);
+                let (n, allocation) = match allocations.iter().position(|(d, _)| Rc::ptr_eq(d, dept.borrow_pointer())) {
+                    Some(n) => (n, allocations[n].1),
+                    None => continue,
+                };
+
+                let position = person.position_on(as_of);
+                let title = position.map_or(String::from(NO_TITLE), |p| p.title.clone());
+
+                let holder = PositionHolder {
+                    alias: entry.alias().clone(),
+                    employee_id: data.employee_id(&person),
+                    name: person.name().clone(),
+                    level: position.and_then(|p| p.level.clone()),
+                    fte: person.fte_on(as_of) * f64::from(allocation) / 100.0,
+                    allocation,
+                    primary: n == 0,
+                };
+
+                match dept_groups.iter_mut().find(|g| collation.compare_primary(&g.title, &title) == Ordering::Equal) {
+                    Some(group) => group.people.push(holder),
+                    None => dept_groups.push(TitleGroup {
+                        department: dept.alias().clone(),
+                        title,
+                        people: vec![holder],
+                    }),
+                }
+            }
+
+            dept_groups.sort_by(|a, b| {
+                (a.title == NO_TITLE).cmp(&(b.title == NO_TITLE))
+                    .then_with(|| collation.compare_text(&a.title, &b.title))
+            });
+
+            for group in &mut dept_groups {
+                group.people.sort_by(|a, b| collation.compare_names(&a.name, &b.name)
+                    .then_with(|| a.employee_id.cmp(&b.employee_id)));
+            }
+
+            groups.append(&mut dept_groups);
+        }
+
+        PositionReport { as_of, groups }
+    }
+
+    pub fn as_of(&self) -> NaiveDate {
+        self.as_of
+    }
+
+    pub fn groups(&self) -> &Vec<TitleGroup> {
+        &self.groups
+    }
+
+    /// Column headers matching the cells returned by table_rows().
+    pub fn headers() -> Vec<String> {
+        ["dept", "title", "heads", "fte"].iter()
+            .map(|h| String::from(*h))
+            .collect()
+    }
+
+    /// Headcount and FTE per title, as rows of display text.
+    pub fn table_rows(&self) -> Vec<Vec<String>> {
+        self.groups.iter()
+            .map(|g| vec![
+                g.department.clone(),
+                g.title.clone(),
+                g.headcount().to_string(),
+                format!("{:.2}", g.fte()),
+            ])
+            .collect()
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let groups: Vec<serde_json::Value> = self.groups.iter()
+            .map(|g| json!({
+                "department": g.department,
+                "title": g.title,
+                "headcount": g.headcount(),
+                "fte": g.fte(),
+                "people": g.people.iter().map(|p| json!({
+                    "alias": p.alias,
+                    "employee_id": p.employee_id,
+                    "name": p.name.to_string(),
+                    "level": p.level,
+                    "fte": p.fte,
+                    "allocation": p.allocation,
+                    "primary": p.primary,
+                })).collect::<Vec<serde_json::Value>>(),
+            }))
+            .collect();
+
+        json!({
+            "as_of": self.as_of.to_string(),
+            "groups": groups,
+        })
+    }
+
+    /// One line per person, with their department and title.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("department,title,employee_id,alias,name,level,fte,allocation,primary\n");
+
+        for g in &self.groups {
+            for p in &g.people {
+                csv.push_str(&format!("{},{},{},{},{},{},{:.2},{},{}\n",
+                    csv_field(&g.department), csv_field(&g.title), csv_field(&p.employee_id),
+                    csv_field(&p.alias), csv_field(&p.name.to_string()),
+                    csv_field(p.level.as_deref().unwrap_or_default()), p.fte, p.allocation, p.primary));
+            }
+        }
+
+        csv
+    }
+}
+
+/// Label used in place of a code for people with no location or cost center.
+pub const UNASSIGNED: &str = "(none)";
+
+/// Everyone at one location or billed to one cost center.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    pub code: String,
+    pub name: String,
+    /// Aliases of the departments these people belong to.
+    pub departments: Vec<String>,
+    pub people: Vec<PositionHolder>,
+    pub on_leave: u32,
+}
+
+impl Group {
+    pub fn headcount(&self) -> usize {
+        self.people.len()
+    }
+
+    pub fn fte(&self) -> f64 {
+        self.people.iter().map(|p| p.fte).sum()
+    }
+}
+
+/// Employees grouped by location or by cost center, as of a given date.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupReport {
+    grouping: Grouping,
+    as_of: NaiveDate,
+    groups: Vec<Group>,
+}
+
+impl GroupReport {
+    /// Group everyone employed on `as_of` by the location or cost center that applies to them.
+    /// Every defined location or cost center is listed, in code order, followed by UNASSIGNED if
+    /// anyone has none.
+    pub fn build(data: &ProgramData, grouping: Grouping, as_of: NaiveDate) -> Self {
+        let collation = data.collation();
+
+        let mut groups: Vec<Group> = match grouping {
+            Grouping::Location => data.locations().iter()
+                .map(|l| (l.code.clone(), l.name.clone()))
+                .collect::<Vec<(String, String)>>(),
+            Grouping::CostCenter => data.cost_centers().iter()
+                .map(|c| (c.code.clone(), c.name.clone()))
+                .collect(),
+        }.into_iter()
+            .map(|(code, name)| Group { code, name, departments: Vec::new(), people: Vec::new(), on_leave: 0 })
+            .collect();
+
+        let mut unassigned = Group {
+            code: String::from(UNASSIGNED),
+            name: String::new(),
+            departments: Vec::new(),
+            people: Vec::new(),
+            on_leave: 0,
+        };
+
+        for entry in data.employee_list() {
+            let person = entry.pointer();
+            let person = person.borrow();
+
+            let department = match person.department_on(as_of) {
+                Some(department) => department,
+                None => continue,
+            };
+
+            let code = data.group_code(&person, &department.borrow(), grouping);
+            let group = match code.and_then(|code| groups.iter_mut().find(|g| g.code == code)) {
+                Some(group) => group,
+                None => &mut unassigned,
+            };
+
+            let dept_alias = data.dept_alias_of(&department).cloned().unwrap_or_default();
+            if !group.departments.contains(&dept_alias) {
+                group.departments.push(dept_alias);
+            }
+
+            if person.status_on(as_of) == Some(Status::OnLeave) {
+                group.on_leave += 1;
+            }
+
+            group.people.push(PositionHolder {
+                alias: entry.alias().clone(),
+                employee_id: data.employee_id(&person),
+                name: person.name().clone(),
+                level: person.position_on(as_of).and_then(|p| p.level.clone()),
+                fte: person.fte_on(as_of),
+                allocation: 100,
+                primary: true,
+            });
+        }
+
+        if !unassigned.people.is_empty() {
+            groups.push(unassigned);
+        }
+
+        for group in &mut groups {
+            group.departments.sort_by(|a, b| collation.compare_text(a, b));
+            group.people.sort_by(|a, b| collation.compare_names(&a.name, &b.name)
+                .then_with(|| a.employee_id.cmp(&b.employee_id)));
+        }
+
+        GroupReport { grouping, as_of, groups }
+    }
+
+    pub fn grouping(&self) -> Grouping {
+        self.grouping
+    }
+
+    pub fn as_of(&self) -> NaiveDate {
+        self.as_of
+    }
+
+    pub fn groups(&self) -> &Vec<Group> {
+        &self.groups
+    }
+
+    /// Column headers matching the cells returned by table_rows().
+    pub fn headers(&self) -> Vec<String> {
+        let first = match self.grouping {
+            Grouping::Location => "location",
+            Grouping::CostCenter => "cost center",
+        };
+
+        [first, "name", "heads", "fte", "on leave", "depts"].iter()
+            .map(|h| String::from(*h))
+            .collect()
+    }
+
+    pub fn table_rows(&self) -> Vec<Vec<String>> {
+        self.groups.iter()
+            .map(|g| vec![
+                g.code.clone(),
+                g.name.clone(),
+                g.headcount().to_string(),
+                format!("{:.2}", g.fte()),
+                g.on_leave.to_string(),
+                g.departments.join(" "),
+            ])
+            .collect()
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let groups: Vec<serde_json::Value> = self.groups.iter()
+            .map(|g| json!({
+                "code": g.code,
+                "name": g.name,
+                "headcount": g.headcount(),
+                "fte": g.fte(),
+                "on_leave": g.on_leave,
+                "departments": g.departments,
+                "people": g.people.iter().map(|p| json!({
+                    "alias": p.alias,
+                    "employee_id": p.employee_id,
+                    "name": p.name.to_string(),
+                    "fte": p.fte,
+                })).collect::<Vec<serde_json::Value>>(),
+            }))
+            .collect();
+
+        json!({
+            "grouping": self.grouping.to_string(),
+            "as_of": self.as_of.to_string(),
+            "groups": groups,
+        })
+    }
+
+    /// One line per person, with their location or cost center.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("code,name,employee_id,alias,person,fte\n");
+
+        for g in &self.groups {
+            for p in &g.people {
+                csv.push_str(&format!("{},{},{},{},{},{:.2}\n",
+                    csv_field(&g.code), csv_field(&g.name), csv_field(&p.employee_id),
+                    csv_field(&p.alias), csv_field(&p.name.to_string()), p.fte));
+            }
+        }
+
+        csv
+    }
+}
@@ -0,0 +1,55 @@
+//! Transfers entered ahead of time.  A pending transfer is held by ProgramData until its
+//! effective date arrives, when ProgramData::apply_due_transfers() carries it out.
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::fmt;
+
+use chrono::naive::NaiveDate;
+
+use crate::department::Department;
+use crate::personnel::Person;
+
+/// A transfer waiting for its effective date.
+#[derive(Debug, Clone)]
+pub struct PendingTransfer {
+    id: u32,
+    person: Rc<RefCell<Person>>,
+    department: Rc<RefCell<Department>>,
+    date: NaiveDate,
+}
+
+impl PendingTransfer {
+    pub(crate) fn new(id: u32, person: Rc<RefCell<Person>>, department: Rc<RefCell<Department>>, date: NaiveDate) -> Self {
+        PendingTransfer {
+            id,
+            person,
+            department,
+            date,
+        }
+    }
+
+    /// Number identifying the transfer, used to cancel it.  Unique within ProgramData.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn person(&self) -> Rc<RefCell<Person>> {
+        Rc::clone(&self.person)
+    }
+
+    /// The department the person will move to.
+    pub fn department(&self) -> Rc<RefCell<Department>> {
+        Rc::clone(&self.department)
+    }
+
+    /// The date the transfer takes effect.
+    pub fn date(&self) -> NaiveDate {
+        self.date
+    }
+}
+
+impl fmt::Display for PendingTransfer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}: {} to {}, {}", self.id, self.person.borrow().name(), self.department.borrow().name(), self.date)
+    }
+}
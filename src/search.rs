@@ -0,0 +1,282 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::personnel::Person;
+use crate::department::Department;
+use crate::collation::Collation;
+
+/// How closely a search term matched a field.  Variants are listed from strongest to weakest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchKind {
+    Exact,
+    Prefix,
+    Substring,
+    /// Candidate is within the given edit distance of the search term.
+    Typo(usize),
+    /// Every character of the search term appears in the candidate, in order.
+    Subsequence,
+}
+
+impl MatchKind {
+    /// Relative strength of a match, used to rank search results.  Higher is better.
+    pub fn score(&self) -> u32 {
+        match self {
+            MatchKind::Exact => 100,
+            MatchKind::Prefix => 80,
+            MatchKind::Substring => 60,
+            MatchKind::Typo(distance) => 50u32.saturating_sub(10 * *distance as u32),
+            MatchKind::Subsequence => 20,
+        }
+    }
+}
+
+impl fmt::Display for MatchKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MatchKind::Exact => write!(f, "exact"),
+            MatchKind::Prefix => write!(f, "prefix"),
+            MatchKind::Substring => write!(f, "partial"),
+            MatchKind::Typo(d) => write!(f, "fuzzy, {} edit(s)", d),
+            MatchKind::Subsequence => write!(f, "fuzzy, abbreviation"),
+        }
+    }
+}
+
+/// The field of a person or department that a search term matched against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchField {
+    PersonAlias,
+    FirstName,
+    MiddleName,
+    LastName,
+    PreferredName,
+    FullName,
+    DeptAlias,
+    DeptName,
+}
+
+impl fmt::Display for MatchField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MatchField::PersonAlias | MatchField::DeptAlias => write!(f, "alias"),
+            MatchField::FirstName => write!(f, "first name"),
+            MatchField::MiddleName => write!(f, "middle name"),
+            MatchField::LastName => write!(f, "last name"),
+            MatchField::PreferredName => write!(f, "preferred name"),
+            MatchField::FullName => write!(f, "full name"),
+            MatchField::DeptName => write!(f, "department name"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SearchTarget {
+    Person(Rc<RefCell<Person>>),
+    Department(Rc<RefCell<Department>>),
+}
+
+/// A single hit returned by ProgramData::search().  Carries the alias of the entity found, the
+/// field and text that matched, and how well it matched.
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    alias: String,
+    target: SearchTarget,
+    field: MatchField,
+    matched: String,
+    kind: MatchKind,
+}
+
+impl SearchResult {
+    pub fn alias(&self) -> &String {
+        &self.alias
+    }
+
+    pub fn target(&self) -> &SearchTarget {
+        &self.target
+    }
+
+    pub fn field(&self) -> MatchField {
+        self.field
+    }
+
+    pub fn matched_text(&self) -> &String {
+        &self.matched
+    }
+
+    pub fn kind(&self) -> MatchKind {
+        self.kind
+    }
+
+    pub fn score(&self) -> u32 {
+        self.kind.score()
+    }
+}
+
+impl fmt::Display for SearchResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.target {
+            SearchTarget::Person(p) => write!(f, "Employee \"{}\": {}", self.alias, p.borrow().name())?,
+            SearchTarget::Department(d) => write!(f, "Dept. \"{}\": {}", self.alias, d.borrow().name())?,
+        };
+
+        write!(f, " ({} \"{}\", {})", self.field, self.matched, self.kind)
+    }
+}
+
+/// Collects the best match per entity while a search is in progress.
+pub(crate) struct Searcher {
+    term: String,
+    collation: Collation,
+    results: Vec<SearchResult>,
+}
+
+impl Searcher {
+    pub(crate) fn new(term: &str, collation: Collation) -> Self {
+        Searcher {
+            term: term.trim().to_lowercase(),
+            collation,
+            results: Vec::new(),
+        }
+    }
+
+    /// Match the search term against every searchable field of a person, keeping the best hit.
+    pub(crate) fn person(&mut self, alias: &str, others: &[String], person: &Rc<RefCell<Person>>) {
+        let candidates = {
+            let p = person.borrow();
+            let name = p.name();
+
+            let mut fields = vec![
+                (MatchField::PersonAlias, alias.to_string()),
+                (MatchField::FirstName, name.first.clone()),
+                (MatchField::LastName, name.last.clone()),
+                (MatchField::FullName, name.display_name()),
+                (MatchField::FullName, name.full_name()),
+            ];
+            for mid in &name.middle {
+                fields.push((MatchField::MiddleName, mid.clone()));
+            }
+            if let Some(preferred) = &name.preferred {
+                fields.push((MatchField::PreferredName, preferred.clone()));
+            }
+            for other in others {
+                fields.push((MatchField::PersonAlias, other.clone()));
+            }
+            fields
+        };
+
+        self.best_of(alias, SearchTarget::Person(Rc::clone(person)), candidates);
+    }
+
+    /// Match the search term against a department's aliases and full name, keeping the best hit.
+    pub(crate) fn department(&mut self, alias: &str, others: &[String], department: &Rc<RefCell<Department>>) {
+        let mut candidates = vec![
+            (MatchField::DeptAlias, alias.to_string()),
+            (MatchField::DeptName, department.borrow().name().clone()),
+        ];
+        for other in others {
+            candidates.push((MatchField::DeptAlias, other.clone()));
+        }
+
+        self.best_of(alias, SearchTarget::Department(Rc::clone(department)), candidates);
+    }
+
+    fn best_of(&mut self, alias: &str, target: SearchTarget, candidates: Vec<(MatchField, String)>) {
+        if self.term.is_empty() {
+            return;
+        }
+
+        let best = candidates.into_iter()
+            .filter_map(|(field, text)| match_text(&self.term, &text).map(|kind| (field, text, kind)))
+            .max_by_key(|(_, _, kind)| kind.score());
+
+        if let Some((field, matched, kind)) = best {
+            self.results.push(SearchResult {
+                alias: String::from(alias),
+                target,
+                field,
+                matched,
+                kind,
+            });
+        }
+    }
+
+    /// Consume the searcher, returning results from best to worst match.  Ties are broken
+    /// alphabetically by alias.
+    pub(crate) fn finish(mut self) -> Vec<SearchResult> {
+        let collation = self.collation;
+
+        self.results.sort_by(|a, b| {
+            match b.score().cmp(&a.score()) {
+                Ordering::Equal => collation.compare_text(&a.alias, &b.alias),
+                other => other,
+            }
+        });
+
+        self.results
+    }
+}
+
+/// Compare a lowercase search term against a candidate string, case-insensitively.  Returns the
+/// strongest kind of match found, or None if the candidate isn't a plausible hit.
+pub fn match_text(term: &str, candidate: &str) -> Option<MatchKind> {
+    let candidate = candidate.to_lowercase();
+
+    if term.is_empty() || candidate.is_empty() {
+        return None;
+    }
+
+    if candidate == term {
+        return Some(MatchKind::Exact);
+    }
+
+    if candidate.starts_with(term) {
+        return Some(MatchKind::Prefix);
+    }
+
+    if candidate.contains(term) {
+        return Some(MatchKind::Substring);
+    }
+
+    // Allow roughly one typo for every four characters typed.
+    let max_distance = (term.chars().count() / 4).max(1);
+    let distance = edit_distance(term, &candidate);
+    if distance <= max_distance {
+        return Some(MatchKind::Typo(distance));
+    }
+
+    if term.chars().count() > 1 && is_subsequence(term, &candidate) {
+        return Some(MatchKind::Subsequence);
+    }
+
+    None
+}
+
+/// Levenshtein distance between two strings, counted in chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == *cb { 0 } else { 1 };
+            current[j + 1] = substitution
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut hay = haystack.chars();
+
+    needle.chars().all(|c| hay.any(|h| h == c))
+}
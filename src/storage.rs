@@ -0,0 +1,470 @@
+//! Saving ProgramData to disk and loading it back.  Data is written as JSON, with people and
+//! departments identified by their ids rather than their aliases, so that references between them
+//! survive alias changes.
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::error::Error;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::str::FromStr;
+
+use chrono::naive::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::data_handling::{DataError, ProgramData};
+use crate::department::Department;
+use crate::personnel::{DeptEntry, EmploymentType, Membership, Name, Person, Position, PositionEntry, StatusEntry};
+use crate::dates::DateFormat;
+use crate::collation::Collation;
+use crate::ids::IdFormat;
+use crate::compensation::{CompensationEntry, Money};
+use crate::fields::{Contact, FieldDef, FieldValue};
+use crate::organization::{CostCenter, Location};
+use crate::scheduling::PendingTransfer;
+
+/// Version of the file layout written by this build.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// File used by the text interface when no other is given.
+pub const DEFAULT_FILE: &str = "roster.json";
+
+/// Everything needed to rebuild a ProgramData, in a form that can be serialized.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub settings: Settings,
+    /// The number that will be given to the next person added.
+    pub next_employee_id: u32,
+    /// Custom fields, in the order they were defined.
+    #[serde(default)]
+    pub fields: Vec<FieldDef>,
+    #[serde(default)]
+    pub locations: Vec<Location>,
+    #[serde(default)]
+    pub cost_centers: Vec<CostCenter>,
+    pub departments: Vec<SavedDepartment>,
+    pub people: Vec<SavedPerson>,
+    /// Transfers waiting for their effective date.
+    #[serde(default)]
+    pub pending: Vec<SavedTransfer>,
+}
+
+/// User preferences, stored in the same text form the `set` command accepts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    pub date_format: String,
+    pub collation: String,
+    pub id_format: String,
+    /// Salted hash of the HR passphrase.  See compensation::hash_passphrase().
+    #[serde(default)]
+    pub hr_passphrase: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedDepartment {
+    pub id: u32,
+    pub alias: String,
+    #[serde(default)]
+    pub other_aliases: Vec<String>,
+    pub name: String,
+    #[serde(default)]
+    pub budget: Option<Money>,
+    #[serde(default)]
+    pub location: Option<String>,
+    #[serde(default)]
+    pub cost_center: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedPerson {
+    pub id: u32,
+    pub alias: String,
+    #[serde(default)]
+    pub other_aliases: Vec<String>,
+    pub name: Name,
+    pub hired: NaiveDate,
+    pub history: Vec<SavedEntry>,
+    #[serde(default)]
+    pub positions: Vec<SavedPosition>,
+    #[serde(default)]
+    pub compensation: Vec<CompensationEntry>,
+    #[serde(default)]
+    pub contact: Contact,
+    #[serde(default)]
+    pub custom_fields: BTreeMap<String, FieldValue>,
+    #[serde(default)]
+    pub employment_type: EmploymentType,
+    /// Status changes, including any termination.
+    #[serde(default)]
+    pub status: Vec<StatusEntry>,
+    /// Location and cost center codes overriding the department's.
+    #[serde(default)]
+    pub location: Option<String>,
+    #[serde(default)]
+    pub cost_center: Option<String>,
+    /// Secondary department memberships.
+    #[serde(default)]
+    pub memberships: Vec<SavedMembership>,
+    pub terminated: Option<NaiveDate>,
+}
+
+/// One PositionEntry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedPosition {
+    #[serde(flatten)]
+    pub position: Position,
+    pub date: NaiveDate,
+}
+
+/// One DeptEntry, naming the department by id.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedEntry {
+    pub department: u32,
+    pub date: NaiveDate,
+}
+
+/// One Membership, naming the department by id.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedMembership {
+    pub department: u32,
+    pub allocation: u32,
+    pub start: NaiveDate,
+    #[serde(default)]
+    pub end: Option<NaiveDate>,
+}
+
+/// One PendingTransfer, naming the person and department by id.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedTransfer {
+    pub id: u32,
+    pub person: u32,
+    pub department: u32,
+    pub date: NaiveDate,
+}
+
+impl Snapshot {
+    /// Capture the current state of `data`.
+    pub fn take(data: &ProgramData) -> Self {
+        let departments = data.dept_list()
+            .map(|entry| {
+                let department = entry.borrow_pointer().borrow();
+                SavedDepartment {
+                    id: department.id(),
+                    alias: entry.alias().clone(),
+                    other_aliases: entry.other_aliases().clone(),
+                    name: department.name().clone(),
+                    budget: department.budget().cloned(),
+                    location: department.location().cloned(),
+                    cost_center: department.cost_center().cloned(),
+                }
+            })
+            .collect();
+
+        let people = data.employee_list()
+            .map(|entry| {
+                let person = entry.pointer();
+                let person = person.borrow();
+                SavedPerson {
+                    id: person.id(),
+                    alias: entry.alias().clone(),
+                    other_aliases: entry.other_aliases().clone(),
+                    name: person.name().clone(),
+                    hired: person.date_of_hire(),
+                    history: person.department_history().iter()
+                        .map(|e| SavedEntry {
+                            department: e.department().borrow().id(),
+                            date: e.date(),
+                        })
+                        .collect(),
+                    positions: person.position_history().iter()
+                        .map(|e| SavedPosition {
+                            position: e.position().clone(),
+                            date: e.date(),
+                        })
+                        .collect(),
+                    compensation: person.compensation_history().clone(),
+                    contact: person.contact().clone(),
+                    custom_fields: person.custom_fields().clone(),
+                    employment_type: person.employment_type(),
+                    status: person.status_history().clone(),
+                    location: person.location().cloned(),
+                    cost_center: person.cost_center().cloned(),
+                    memberships: person.memberships().iter()
+                        .map(|m| SavedMembership {
+                            department: m.department().borrow().id(),
+                            allocation: m.allocation(),
+                            start: m.start(),
+                            end: m.end(),
+                        })
+                        .collect(),
+                    terminated: person.termination_date(),
+                }
+            })
+            .collect();
+
+        Snapshot {
+            version: FORMAT_VERSION,
+            settings: Settings {
+                date_format: data.date_format().to_string(),
+                collation: data.collation().to_string(),
+                id_format: data.id_format().to_string(),
+                hr_passphrase: data.hr_passphrase().cloned(),
+            },
+            next_employee_id: data.next_employee_id(),
+            fields: data.schema().fields().clone(),
+            locations: data.locations().clone(),
+            cost_centers: data.cost_centers().clone(),
+            departments,
+            people,
+            pending: data.pending_transfers().iter()
+                .map(|t| SavedTransfer {
+                    id: t.id(),
+                    person: t.person().borrow().id(),
+                    department: t.department().borrow().id(),
+                    date: t.date(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Build a new ProgramData from this snapshot.  Fails if the snapshot refers to departments
+    /// that don't exist, or repeats an alias or id.
+    pub fn restore(&self) -> Result<ProgramData, StorageError> {
+        if self.version > FORMAT_VERSION {
+            return Err(StorageError::Version(self.version));
+        }
+
+        let mut data = ProgramData::init();
+
+        let date_format = DateFormat::from_str(&self.settings.date_format)
+            .map_err(|_| StorageError::Invalid(format!("date format \"{}\"", self.settings.date_format)))?;
+        let collation = Collation::from_str(&self.settings.collation)
+            .map_err(|_| StorageError::Invalid(format!("collation \"{}\"", self.settings.collation)))?;
+        let id_format = IdFormat::from_str(&self.settings.id_format)
+            .map_err(|_| StorageError::Invalid(format!("employee id format \"{}\"", self.settings.id_format)))?;
+
+        data.set_date_format(date_format);
+        data.set_collation(collation);
+        data.set_id_format(id_format);
+        data.restore_hr_passphrase(self.settings.hr_passphrase.clone());
+
+        for def in &self.fields {
+            data.define_field(&def.name, def.kind.clone())
+                .map_err(|e| StorageError::Invalid(e.to_string()))?;
+        }
+
+        for location in &self.locations {
+            data.add_location(&location.code, &location.name)
+                .map_err(|e| StorageError::Invalid(e.to_string()))?;
+        }
+
+        for cost_center in &self.cost_centers {
+            data.add_cost_center(&cost_center.code, &cost_center.name)
+                .map_err(|e| StorageError::Invalid(e.to_string()))?;
+        }
+
+        let mut departments: HashMap<u32, Rc<RefCell<Department>>> = HashMap::new();
+
+        for saved in &self.departments {
+            if departments.contains_key(&saved.id) {
+                return Err(StorageError::Invalid(format!("department id {} used twice", saved.id)));
+            }
+
+            let department = data.restore_dept(&saved.alias, &saved.name, saved.id)
+                .map_err(|_| StorageError::Invalid(format!("department alias \"{}\" used twice", saved.alias)))?;
+            department.borrow_mut().set_budget(saved.budget.clone());
+            departments.insert(saved.id, department);
+        }
+
+        for saved in &self.departments {
+            for alias in &saved.other_aliases {
+                data.add_alias(&saved.alias, alias)
+                    .map_err(|e| StorageError::Invalid(e.to_string()))?;
+            }
+
+            restore_grouping(&mut data, &saved.alias, saved.location.as_deref(), saved.cost_center.as_deref())?;
+        }
+
+        let mut people: HashMap<u32, Rc<RefCell<Person>>> = HashMap::new();
+
+        for saved in &self.people {
+            if saved.id == 0 || people.contains_key(&saved.id) {
+                return Err(StorageError::Invalid(format!("employee number {} missing or used twice", saved.id)));
+            }
+
+            let mut history = Vec::new();
+            for entry in &saved.history {
+                let department = departments.get(&entry.department)
+                    .ok_or_else(|| StorageError::Invalid(format!("\"{}\" refers to unknown department {}",
+                        saved.alias, entry.department)))?;
+                history.push(DeptEntry::new(Rc::clone(department), entry.date));
+            }
+
+            if history.is_empty() {
+                return Err(StorageError::Invalid(format!("\"{}\" has no department history", saved.alias)));
+            }
+
+            let mut memberships = Vec::new();
+            for membership in &saved.memberships {
+                let department = departments.get(&membership.department)
+                    .ok_or_else(|| StorageError::Invalid(format!("\"{}\" refers to unknown department {}",
+                        saved.alias, membership.department)))?;
+                if membership.allocation == 0 || membership.allocation >= 100 {
+                    return Err(StorageError::Invalid(format!("\"{}\" has an allocation of {}%", saved.alias, membership.allocation)));
+                }
+                memberships.push(Membership::new(Rc::clone(department), membership.allocation, membership.start, membership.end));
+            }
+
+            let mut positions = Vec::new();
+            for entry in &saved.positions {
+                if !Position::valid_fte(entry.position.fte) {
+                    return Err(StorageError::Invalid(format!("\"{}\" has a position of {} FTE", saved.alias, entry.position.fte)));
+                }
+                positions.push(PositionEntry::new(entry.position.clone(), entry.date));
+            }
+
+            let mut person = Person::restore(saved.id, saved.name.clone(), saved.hired, history, positions, saved.terminated);
+            person.restore_compensation(saved.compensation.clone());
+            person.restore_details(saved.contact.clone(), saved.custom_fields.clone());
+            person.restore_employment(saved.employment_type, saved.status.clone());
+            person.restore_memberships(memberships);
+            person.check_memberships()
+                .map_err(|e| StorageError::Invalid(format!("\"{}\": {}", saved.alias, e)))?;
+
+            let person = data.restore_person(&saved.alias, person)
+                .map_err(|e| match e {
+                    DataError::AddPerson => StorageError::Invalid(format!("employee alias \"{}\" used twice", saved.alias)),
+                    e => StorageError::Invalid(format!("\"{}\": {}", saved.alias, e)),
+                })?;
+            people.insert(saved.id, person);
+
+            for alias in &saved.other_aliases {
+                data.add_alias(&saved.alias, alias)
+                    .map_err(|e| StorageError::Invalid(e.to_string()))?;
+            }
+
+            restore_grouping(&mut data, &saved.alias, saved.location.as_deref(), saved.cost_center.as_deref())?;
+        }
+
+        for saved in &self.pending {
+            let person = people.get(&saved.person)
+                .ok_or_else(|| StorageError::Invalid(format!("pending transfer #{} refers to unknown employee {}",
+                    saved.id, saved.person)))?;
+            let department = departments.get(&saved.department)
+                .ok_or_else(|| StorageError::Invalid(format!("pending transfer #{} refers to unknown department {}",
+                    saved.id, saved.department)))?;
+
+            data.restore_transfer(PendingTransfer::new(saved.id, Rc::clone(person), Rc::clone(department), saved.date));
+        }
+
+        data.set_next_employee_id(self.next_employee_id);
+
+        Ok(data)
+    }
+
+    /// Whether any person has pay recorded or any department has a budget.
+    pub fn has_pay(&self) -> bool {
+        self.people.iter().any(|p| !p.compensation.is_empty()) || self.departments.iter().any(|d| d.budget.is_some())
+    }
+
+    /// The snapshot without pay records, budgets or the HR passphrase, for a copy to be read
+    /// outside the HR role.
+    pub fn without_pay(mut self) -> Self {
+        for person in self.people.iter_mut() {
+            person.compensation.clear();
+        }
+        for department in self.departments.iter_mut() {
+            department.budget = None;
+        }
+        self.settings.hr_passphrase = None;
+
+        self
+    }
+
+    /// Write the snapshot as JSON.  It is written to a temporary file in the same directory,
+    /// which then replaces `path`, so a failed save leaves any earlier file as it was.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), StorageError> {
+        let path = path.as_ref();
+        let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+        name.push(".tmp");
+        let temporary = path.with_file_name(name);
+
+        let result = self.write(&temporary).and_then(|_| Ok(fs::rename(&temporary, path)?));
+        if result.is_err() {
+            let _ = fs::remove_file(&temporary);
+        }
+
+        result
+    }
+
+    fn write(&self, path: &Path) -> Result<(), StorageError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+
+        let file = writer.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+
+        Ok(())
+    }
+
+    /// Read a snapshot written by save().
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Snapshot, StorageError> {
+        let reader = BufReader::new(File::open(path)?);
+
+        Ok(serde_json::from_reader(reader)?)
+    }
+}
+
+/// Assign a saved location and cost center to the department or person known by `alias`.
+fn restore_grouping(data: &mut ProgramData, alias: &str, location: Option<&str>, cost_center: Option<&str>)
+                    -> Result<(), StorageError> {
+    let invalid = |e: DataError| StorageError::Invalid(format!("\"{}\": {}", alias, e));
+
+    if location.is_some() {
+        data.assign_location(alias, location).map_err(invalid)?;
+    }
+
+    if cost_center.is_some() {
+        data.assign_cost_center(alias, cost_center).map_err(invalid)?;
+    }
+
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum StorageError {
+    IOError(std::io::Error),
+    Format(serde_json::Error),
+    /// The file was written by a newer version of the program.
+    Version(u32),
+    Invalid(String),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StorageError::IOError(e) => write!(f, "{}", e),
+            StorageError::Format(e) => write!(f, "Could not read file: {}", e),
+            StorageError::Version(v) => write!(f, "File format version {} is newer than this program supports", v),
+            StorageError::Invalid(what) => write!(f, "Saved data is inconsistent: {}", what),
+        }
+    }
+}
+
+impl Error for StorageError {}
+
+impl From<std::io::Error> for StorageError {
+    fn from(error: std::io::Error) -> Self {
+        StorageError::IOError(error)
+    }
+}
+
+impl From<serde_json::Error> for StorageError {
+    fn from(error: serde_json::Error) -> Self {
+        StorageError::Format(error)
+    }
+}
@@ -0,0 +1,215 @@
+use std::fmt;
+use std::rc::Rc;
+
+use chrono::naive::NaiveDate;
+use chrono::Datelike;
+
+use crate::data_handling::ProgramData;
+use crate::dates::add_months;
+use crate::personnel::Name;
+
+/// A length of service, broken down into whole calendar years, months and days.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Tenure {
+    pub years: u32,
+    pub months: u32,
+    pub days: u32,
+}
+
+impl Tenure {
+    /// Length of service from `start` through `end`.  Returns zero if `end` is before `start`.
+    pub fn between(start: NaiveDate, end: NaiveDate) -> Tenure {
+        if end <= start {
+            return Tenure { years: 0, months: 0, days: 0 };
+        }
+
+        let mut months = (end.year() - start.year()) * 12 + end.month() as i32 - start.month() as i32;
+        if end.day() < start.day() {
+            months -= 1;
+        }
+
+        let anchor = add_months(start, months).expect("anniversary lies between two valid dates");
+        let days = end.signed_duration_since(anchor).num_days() as u32;
+
+        Tenure {
+            years: months as u32 / 12,
+            months: months as u32 % 12,
+            days,
+        }
+    }
+
+    /// Tenure in fractional years, for bucketing and averages.
+    pub fn in_years(&self) -> f64 {
+        f64::from(self.years) + f64::from(self.months) / 12.0 + f64::from(self.days) / 365.25
+    }
+}
+
+impl fmt::Display for Tenure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}y {}m {}d", self.years, self.months, self.days)
+    }
+}
+
+/// Tenure figures for one active employee.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TenureRecord {
+    pub alias: String,
+    pub employee_id: String,
+    pub name: Name,
+    pub department: String,
+    pub hired: NaiveDate,
+    /// Date the employee joined their current department.
+    pub joined_department: NaiveDate,
+    pub company: Tenure,
+    pub in_department: Tenure,
+}
+
+/// A service anniversary falling within an upcoming window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Anniversary {
+    pub alias: String,
+    pub name: Name,
+    pub date: NaiveDate,
+    pub years: u32,
+}
+
+/// Upper bounds, in years, of the buckets used by TenureReport::histogram().
+const HISTOGRAM_BUCKETS: [(f64, &str); 5] = [
+    (1.0, "< 1 year"),
+    (2.0, "1-2 years"),
+    (5.0, "2-5 years"),
+    (10.0, "5-10 years"),
+    (f64::INFINITY, "10+ years"),
+];
+
+/// Tenure of every active employee as of a given date.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TenureReport {
+    as_of: NaiveDate,
+    records: Vec<TenureRecord>,
+}
+
+impl TenureReport {
+    /// Compute tenure for everyone employed on `as_of`.  Records are ordered by seniority, most
+    /// senior first.
+    pub fn build(data: &ProgramData, as_of: NaiveDate) -> Self {
+        let mut records = Vec::new();
+
+        for entry in data.employee_list() {
+            let person = entry.pointer();
+            let person = person.borrow();
+
+            let department = match person.department_on(as_of) {
+                Some(d) => d,
+                None => continue,
+            };
+
+            let joined_department = person.department_history().iter()
+                .filter(|e| e.date() <= as_of && Rc::ptr_eq(&e.department(), &department))
+                .map(|e| e.date())
+                .max()
+                .unwrap_or_else(|| person.date_of_hire());
+
+            records.push(TenureRecord {
+                alias: entry.alias().clone(),
+                employee_id: data.employee_id(&person),
+                name: person.name().clone(),
+                department: data.dept_alias_of(&department).cloned().unwrap_or_default(),
+                hired: person.date_of_hire(),
+                joined_department,
+                company: Tenure::between(person.date_of_hire(), as_of),
+                in_department: Tenure::between(joined_department, as_of),
+            });
+        }
+
+        let collation = data.collation();
+        records.sort_by(|a, b| a.hired.cmp(&b.hired).then_with(|| collation.compare_names(&a.name, &b.name)));
+
+        TenureReport { as_of, records }
+    }
+
+    pub fn as_of(&self) -> NaiveDate {
+        self.as_of
+    }
+
+    /// All records, most senior first.
+    pub fn records(&self) -> &Vec<TenureRecord> {
+        &self.records
+    }
+
+    /// Members of one department ranked by company seniority, most senior first.  The rank is
+    /// 1-based, and people hired on the same day share a rank.
+    pub fn seniority(&self, dept_alias: &str) -> Vec<(usize, &TenureRecord)> {
+        let mut ranked: Vec<(usize, &TenureRecord)> = Vec::new();
+
+        for (i, record) in self.records.iter().filter(|r| r.department == dept_alias).enumerate() {
+            let rank = match ranked.last() {
+                Some((rank, previous)) if previous.hired == record.hired => *rank,
+                _ => i + 1,
+            };
+            ranked.push((rank, record));
+        }
+
+        ranked
+    }
+
+    /// Service anniversaries falling after the report date and within the next `days` days,
+    /// soonest first.  A window reaching past the last representable date ends there.
+    pub fn anniversaries(&self, days: u32) -> Vec<Anniversary> {
+        let last_day = self.as_of.checked_add_signed(chrono::Duration::days(i64::from(days)))
+            .unwrap_or(chrono::naive::MAX_DATE);
+        let mut upcoming = Vec::new();
+
+        for record in &self.records {
+            // Start from this year's anniversary and work forward until one falls outside the window.
+            let mut years = (self.as_of.year() - record.hired.year()).max(1);
+
+            while let Some(date) = add_months(record.hired, years * 12) {
+                if date > last_day {
+                    break;
+                }
+
+                if date > self.as_of {
+                    upcoming.push(Anniversary {
+                        alias: record.alias.clone(),
+                        name: record.name.clone(),
+                        date,
+                        years: years as u32,
+                    });
+                }
+                years += 1;
+            }
+        }
+
+        upcoming.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.name.cmp(&b.name)));
+
+        upcoming
+    }
+
+    /// Count of employees in each tenure bucket, from newest to longest serving.
+    pub fn histogram(&self) -> Vec<(&'static str, usize)> {
+        let mut counts: Vec<(&'static str, usize)> = HISTOGRAM_BUCKETS.iter()
+            .map(|(_, label)| (*label, 0))
+            .collect();
+
+        for record in &self.records {
+            let years = record.company.in_years();
+
+            if let Some(i) = HISTOGRAM_BUCKETS.iter().position(|(limit, _)| years < *limit) {
+                counts[i].1 += 1;
+            }
+        }
+
+        counts
+    }
+
+    /// Mean company tenure in years, or None if nobody is employed.
+    pub fn average_years(&self) -> Option<f64> {
+        if self.records.is_empty() {
+            return None;
+        }
+
+        let total: f64 = self.records.iter().map(|r| r.company.in_years()).sum();
+        Some(total / self.records.len() as f64)
+    }
+}
@@ -2,22 +2,60 @@ use std::rc::Rc;
 use std::cell::RefCell;
 use chrono::naive::NaiveDate;
 use chrono::prelude::*;
-use std::io::{self, prelude::*, Stderr, Stdin, Stdout};
+use std::io::{self, prelude::*, Stdout};
 use std::str::FromStr;
 use std::process;
 use std::fmt;
 use std::error::Error;
 
 use crate::department::Department;
-use crate::personnel::{Person, Name};
+use crate::personnel::{EmploymentType, Person, Name, NameOrder, Position, Status};
 use crate::data_handling::ProgramData;
+use crate::reports::{HeadcountReport, Period, PositionReport};
+use crate::tenure::TenureRecord;
+use crate::dates::DateFormat;
+use crate::collation::Collation;
+use crate::query::Value;
+use crate::ids::IdFormat;
+use crate::storage::{DEFAULT_FILE, Snapshot};
+use crate::compensation::{Money, PayBasis, PayrollReport};
+use crate::fields::{Contact, FieldDef, FieldType, FieldValue};
+use crate::organization::Grouping;
+use crate::scheduling::PendingTransfer;
+use crate::reorg::{ReorgPlan, Step};
+use crate::diff::RosterDiff;
+use crate::merge::{RosterMerge, Side};
+use crate::lineedit::LineEditor;
+use crate::help::{self, DocFormat, HelpPage, Topic};
 
 pub type Result<T> = std::result::Result<T, TextInterfaceError>;
 
+/// Line of the employee wizard showing the first custom field.
+const CUSTOM_LINE: usize = 18;
+
+/// Commands that show or change pay, available only in the HR role.
+const HR_COMMANDS: [&str; 2] = ["pay", "budget"];
+
+/// What the current session may see.  Compensation is shown only in the HR role.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Standard,
+    Hr,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Role::Standard => write!(f, "standard"),
+            Role::Hr => write!(f, "HR"),
+        }
+    }
+}
+
 struct Command {
     keyword: String,
     short_desc: String,
-    long_desc: String,
+    help: HelpPage,
     operation: fn(&mut TextInterface, std::str::SplitWhitespace) -> Result<()>,
 }
 
@@ -25,187 +63,2293 @@ pub struct TextInterface {
     io: TextIO,
     data: ProgramData,
     commands: Vec<Command>,
+    /// File used by SAVE and LOAD when none is given.
+    data_file: String,
+    role: Role,
+    /// Reorganization being drawn up with REORG.
+    plan: ReorgPlan,
+    /// The command being carried out, as it was typed.
+    line: String,
+    /// The role and alias generation that Tab completions were last offered for.
+    completions: Option<(Role, (u64, u64))>,
 }
 
-impl TextInterface {
-    pub fn init() -> Self {
-        let mut commands: Vec<Command> = Vec::new();
-
-        commands.push(Command {
-            keyword: String::from("help"),
-            short_desc: String::from("Print this list.  Use \"help [COMMAND]\" for details on a command."),
-            long_desc: String::from("Coming soon!"),
-            operation: TextInterface::help,
-        });
-
-        commands.push(Command {
-            keyword: String::from("new"),
-            short_desc: String::from("Add a new employee or department entry."),
-            long_desc: String::from("NEW [EMPLOYEE|DEPARTMENT]\n\n\
-Ex:  NEW EMPLOYEE\n     NEW DEPARTMENT"),
-            operation: TextInterface::new,
-        });
-
-        commands.push(Command {
-            keyword: String::from("quit"),
-            short_desc: String::from("Exit the program."),
-            long_desc: String::from("QUIT\n\n\
-            Exit out of this program when you no longer want to use the program.  Why\n\
-            you would want to do that could be for one or more of several reasons. A\n\
-            few possibilities include:\n\
-            - Tired of using the program.\n\
-            - Program doesn't work.\n\
-            - Don't know how to use program.\n\
-            - All my friends use the other program.\n\
-            - Don't like the guy who wrote the program.\n\
-            - Not into hip hop.\n\
-            - Time to go to bed.\n\
-            - Time to go to work.\n\
-            - Break time.\n\
-            - Need to use restroom.\n\
-            - Erection lasting longer than four hours."),
-            operation: TextInterface::quit,
-        });
-
-        commands.push(Command {
-            keyword: String::from("list"),
-            short_desc: String::from("Print a list of departments or employees"),
-            long_desc: String::from("LIST [DEPARTMENTS|EMPLOYEES]\n\n\
-            Prints a list of departments or employees, in alphamabetical order."),
-            operation: TextInterface::list,
-        });
+impl TextInterface {
+    pub fn init() -> Self {
+        let commands: Vec<Command> = vec![
+            Command {
+                keyword: String::from("help"),
+                short_desc: String::from("Print this list.  Use \"help [COMMAND]\" for details on a command."),
+                help: HelpPage::new(Topic::General)
+                    .usage("HELP")
+                    .usage("HELP [COMMAND|TOPIC]")
+                    .usage("HELP TOPICS")
+                    .usage("HELP EXPORT [MARKDOWN|MAN] [FILE]")
+                    .description("With no arguments, lists every command with a line on what it does.  Given a\n\
+                    COMMAND, prints its full description: the ways it can be written, what it does,\n\
+                    what its arguments mean, examples, and related commands.  TOPICS lists the\n\
+                    commands grouped by subject, and given a TOPIC, prints the commands on it.\n\n\
+                    EXPORT writes the help for every command as a Markdown document or a man page,\n\
+                    to FILE if one is given.  At a terminal, Tab completes commands and aliases, and\n\
+                    Ctrl-R searches earlier commands.")
+                    .argument("COMMAND", "A command keyword, such as TRANSFER.")
+                    .argument("TOPIC", "GENERAL, PEOPLE, DEPARTMENTS, FINDING, REPORTS, FILES or SETTINGS.")
+                    .argument("FILE", "File to write the exported help to.")
+                    .example("HELP TRANSFER")
+                    .example("HELP TOPICS")
+                    .example("HELP REPORTS")
+                    .example("HELP EXPORT MARKDOWN commands.md")
+                    .example("HELP EXPORT MAN depart_mental.1"),
+                operation: TextInterface::help,
+            },
+            Command {
+                keyword: String::from("new"),
+                short_desc: String::from("Add a new employee or department entry."),
+                help: HelpPage::new(Topic::People)
+                    .usage("NEW EMPLOYEE")
+                    .usage("NEW DEPARTMENT")
+                    .description("Opens a form for the new employee or department.  Each field is shown with a line\n\
+                    number; enter a number to fill in or change that field, and COMMIT when done.\n\
+                    Fields marked * are required.\n\n\
+                    The employee form has: 1 Alias*, 2 First Name*, 3 Middle Names, 4 Last Name*,\n\
+                    5 Date of Hire (today if not given), 6 Department* (by alias), 7 Prefix, 8 Suffix,\n\
+                    9 Preferred name, 10 Name Order, 11 Job Title, 12 Level, 13 FTE (1.00 if not\n\
+                    given), 14 Email, 15 Phone, 16 Office and 17 Type, followed by any custom fields\n\
+                    (see FIELD).  Entering 10 switches between given name first and family name first,\n\
+                    and entering 17 steps through FULL-TIME, PART-TIME and CONTRACTOR.  New employees\n\
+                    are given the next employee number.\n\n\
+                    The department form has: 1 Unique identifier*, the alias the department is known\n\
+                    by, and 2 Full name*.")
+                    .example("NEW EMPLOYEE")
+                    .example("NEW DEPARTMENT")
+                    .related(&["field", "transfer", "alias"]),
+                operation: TextInterface::new,
+            },
+            Command {
+                keyword: String::from("quit"),
+                short_desc: String::from("Exit the program."),
+                help: HelpPage::new(Topic::General)
+                    .usage("QUIT")
+                    .description("Exit out of this program when you no longer want to use the program.  Why\n\
+                    you would want to do that could be for one or more of several reasons. A\n\
+                    few possibilities include:\n\
+                    - Tired of using the program.\n\
+                    - Program doesn't work.\n\
+                    - Don't know how to use program.\n\
+                    - All my friends use the other program.\n\
+                    - Don't like the guy who wrote the program.\n\
+                    - Not into hip hop.\n\
+                    - Time to go to bed.\n\
+                    - Time to go to work.\n\
+                    - Break time.\n\
+                    - Need to use restroom.\n\
+                    - Erection lasting longer than four hours.\n\n\
+                    Unsaved changes are lost.  The end of input, or Ctrl-D at a terminal, also quits.")
+                    .related(&["save"]),
+                operation: TextInterface::quit,
+            },
+            Command {
+                keyword: String::from("list"),
+                short_desc: String::from("Print a list of departments or employees"),
+                help: HelpPage::new(Topic::Finding)
+                    .usage("LIST DEPARTMENTS")
+                    .usage("LIST EMPLOYEES [DEPARTMENT]")
+                    .usage("LIST DUPLICATES")
+                    .usage("LIST LOCATIONS|COSTCENTERS")
+                    .description("Prints a list of departments or employees, in alphabetical order according to the\n\
+                    current collation (see SET).  Given a DEPARTMENT, lists its current employees\n\
+                    grouped by job title, including secondary members (see MEMBER).  DUPLICATES lists\n\
+                    current employees who share a family and given name, in case someone has been\n\
+                    entered twice.  LOCATIONS and COSTCENTERS list current employees grouped by the\n\
+                    location or cost center that applies to them.")
+                    .argument("DEPARTMENT", "Alias of the department to list.")
+                    .example("LIST DEPARTMENTS")
+                    .example("LIST EMPLOYEES")
+                    .example("LIST EMPLOYEES eng")
+                    .example("LIST DUPLICATES")
+                    .related(&["find", "query", "set"]),
+                operation: TextInterface::list,
+            },
+            Command {
+                keyword: String::from("find"),
+                short_desc: String::from("Search for employees or departments by partial name."),
+                help: HelpPage::new(Topic::Finding)
+                    .usage("FIND [TEXT]")
+                    .description("Searches employee aliases and names, and department aliases and names, for TEXT.\n\
+                    Matching ignores case and tolerates partial words and small typos.  Results are\n\
+                    listed best match first, along with the field that matched.")
+                    .argument("TEXT", "Words to search for.")
+                    .example("FIND smith")
+                    .example("FIND eng")
+                    .related(&["lookup", "query", "alias"]),
+                operation: TextInterface::find,
+            },
+            Command {
+                keyword: String::from("query"),
+                short_desc: String::from("Run an ad-hoc query against employees or departments."),
+                help: HelpPage::new(Topic::Finding)
+                    .usage("QUERY [PEOPLE|DEPARTMENTS] [WHERE ...] [ORDER BY ...] [LIMIT N] [SELECT ...]")
+                    .description("Filters, sorts and prints people or departments.  Conditions compare a field to a\n\
+                    value with =, !=, <, <=, >, >= or ~ (contains), and can be combined with AND, OR,\n\
+                    NOT and parentheses.  Dates are written YYYY-MM-DD.\n\n\
+                    People fields:       alias, first, middle, last, prefix, suffix, preferred,\n\
+                    \x20                    display, name, hired, dept, dept_name,\n\
+                    \x20                    since, transfers, history, email, phone, office,\n\
+                    \x20                    type, status, location, cost_center,\n\
+                    \x20                    and any custom field (see FIELD)\n\
+                    Department fields:   alias, name, id, headcount, fte, location, cost_center")
+                    .argument("WHERE", "Conditions a person or department must meet.")
+                    .argument("ORDER BY", "Fields to sort by, each followed by ASC or DESC if wanted.")
+                    .argument("LIMIT N", "Print at most N results.")
+                    .argument("SELECT", "Fields to print, separated by commas.")
+                    .example("QUERY people where dept = eng and hired < 2018-01-01 order by last")
+                    .example("QUERY people where history = sales select alias, name, dept limit 5")
+                    .example("QUERY departments order by headcount desc")
+                    .related(&["find", "list", "field"]),
+                operation: TextInterface::query,
+            },
+            Command {
+                keyword: String::from("report"),
+                short_desc: String::from("Print headcount and turnover reports."),
+                help: HelpPage::new(Topic::Reports)
+                    .usage("REPORT HEADCOUNT [MONTH|QUARTER] [FROM] [TO] [ACTIVE] [TABLE|JSON|CSV] [FILE]")
+                    .usage("REPORT TENURE [DEPARTMENT|ANNIVERSARIES [DAYS]|HISTOGRAM]")
+                    .usage("REPORT POSITIONS [DEPARTMENT] [TABLE|JSON|CSV] [FILE]")
+                    .usage("REPORT PAYROLL [DATE] [TABLE|JSON|CSV] [FILE]")
+                    .usage("REPORT LOCATIONS|COSTCENTERS [DATE] [TABLE|JSON|CSV] [FILE]")
+                    .description("HEADCOUNT shows, per department and per month or quarter between FROM and TO, the\n\
+                    headcount and FTE at the start and end of the period, people on leave at the end,\n\
+                    hires, transfers in and out, terminations and turnover rate.  ACTIVE leaves people\n\
+                    on leave out of headcount and FTE.  Output is a table by default; JSON and CSV may\n\
+                    be written to FILE.\n\n\
+                    TENURE lists company and department tenure for every current employee.  Given a\n\
+                    department, it ranks that department's members by seniority.  ANNIVERSARIES lists\n\
+                    service anniversaries in the next DAYS days (default 30), and HISTOGRAM shows how\n\
+                    tenure is distributed.\n\n\
+                    POSITIONS shows headcount and FTE for each job title within each department, or\n\
+                    within one DEPARTMENT.  CSV output lists every employee with their title.\n\n\
+                    PAYROLL shows the yearly cost of each department's pay on DATE, or today, against\n\
+                    its budget.  Only available in the HR role (see ROLE).\n\n\
+                    LOCATIONS and COSTCENTERS show headcount, FTE, people on leave and departments for\n\
+                    each location or cost center on DATE, or today.  An employee's own location or cost\n\
+                    center is used in place of their department's.")
+                    .argument("FROM, TO", "First and last day covered by the report.")
+                    .argument("DEPARTMENT", "Alias of a department to report on alone.")
+                    .argument("DAYS", "How many days ahead to look for anniversaries.")
+                    .argument("DATE", "Day the report is as of; today if not given.")
+                    .argument("FILE", "File to write the report to.")
+                    .example("REPORT HEADCOUNT QUARTER 2018-01-01 2018-12-31")
+                    .example("REPORT HEADCOUNT MONTH 01/01/2019 06/30/2019 CSV headcount.csv")
+                    .example("REPORT HEADCOUNT MONTH 2019-01-01 2019-12-31 ACTIVE")
+                    .example("REPORT TENURE eng")
+                    .example("REPORT TENURE ANNIVERSARIES 60")
+                    .example("REPORT POSITIONS eng")
+                    .example("REPORT PAYROLL 2019-12-31 CSV payroll.csv")
+                    .example("REPORT COSTCENTERS JSON")
+                    .related(&["position", "budget", "location", "costcenter"]),
+                operation: TextInterface::report,
+            },
+            Command {
+                keyword: String::from("transfer"),
+                short_desc: String::from("Move an employee to another department, now or later."),
+                help: HelpPage::new(Topic::People)
+                    .usage("TRANSFER [EMPLOYEE] [DEPARTMENT] [DATE]")
+                    .description("Makes DEPARTMENT the primary department of EMPLOYEE as of DATE, or today if no\n\
+                    date is given.  A transfer dated after today is held as pending until that date\n\
+                    arrives, and then made automatically; see PENDING.")
+                    .argument("EMPLOYEE", "Alias of the employee to move.")
+                    .argument("DEPARTMENT", "Alias of the department to move them to.")
+                    .argument("DATE", "Day the transfer takes effect; today if not given.")
+                    .example("TRANSFER jsmith ops")
+                    .example("TRANSFER jsmith ops 2019-03-01")
+                    .related(&["pending", "member", "reorg"]),
+                operation: TextInterface::transfer,
+            },
+            Command {
+                keyword: String::from("pending"),
+                short_desc: String::from("List or cancel transfers scheduled for a later date."),
+                help: HelpPage::new(Topic::People)
+                    .usage("PENDING")
+                    .usage("PENDING CANCEL [NUMBER]")
+                    .description("Lists transfers waiting for their effective date, earliest first, each with a\n\
+                    NUMBER.  Pending transfers are made automatically once their date arrives, checked\n\
+                    on LOAD and before every command.  CANCEL drops a pending transfer.")
+                    .argument("NUMBER", "Number of the pending transfer, as listed by PENDING.")
+                    .example("PENDING")
+                    .example("PENDING CANCEL 3")
+                    .related(&["transfer"]),
+                operation: TextInterface::pending,
+            },
+            Command {
+                keyword: String::from("reorg"),
+                short_desc: String::from("Plan a reorganization, preview it, then apply it all at once."),
+                help: HelpPage::new(Topic::Departments)
+                    .usage("REORG")
+                    .usage("REORG CREATE [ALIAS] [NAME]")
+                    .usage("REORG RENAME [DEPARTMENT] [NAME]")
+                    .usage("REORG TRANSFER [EMPLOYEE] [DEPARTMENT]")
+                    .usage("REORG MERGE [DEPARTMENT] [INTO]")
+                    .usage("REORG REMOVE [STEP]")
+                    .usage("REORG PREVIEW")
+                    .usage("REORG CHECK [DATE]")
+                    .usage("REORG APPLY [DATE]")
+                    .usage("REORG DISCARD")
+                    .description("Builds up a plan of department creations, renames, merges and transfers, one step\n\
+                    at a time.  Nothing changes until the plan is applied.  With no arguments, lists\n\
+                    the steps so far; REMOVE takes one out by number.  MERGE moves everyone in\n\
+                    DEPARTMENT, including secondary members, into INTO; the emptied department is kept\n\
+                    for its history.\n\n\
+                    PREVIEW shows how department rosters change.  CHECK looks for problems with the\n\
+                    plan as a whole as if applied on DATE, or today.  APPLY makes every change with\n\
+                    DATE, or today, as the effective date, and clears the plan.  If anything is wrong,\n\
+                    nothing is changed.  DISCARD throws the plan away.")
+                    .argument("ALIAS, NAME", "Alias and full name of a new department, or a new name.")
+                    .argument("DEPARTMENT", "Alias of a department, or of one created earlier in the plan.")
+                    .argument("EMPLOYEE", "Alias of an employee.")
+                    .argument("INTO", "Alias of the department to merge into.")
+                    .argument("STEP", "Number of a step, as listed by REORG.")
+                    .argument("DATE", "Day the plan takes effect; today if not given.")
+                    .example("REORG CREATE platform Platform Engineering")
+                    .example("REORG TRANSFER jsmith platform")
+                    .example("REORG MERGE qa eng")
+                    .example("REORG PREVIEW")
+                    .example("REORG APPLY 2019-07-01")
+                    .related(&["transfer", "new", "alias"]),
+                operation: TextInterface::reorg,
+            },
+            Command {
+                keyword: String::from("terminate"),
+                short_desc: String::from("Record that an employee has left the company."),
+                help: HelpPage::new(Topic::People)
+                    .usage("TERMINATE [EMPLOYEE] [DATE]")
+                    .description("Removes EMPLOYEE from their department as of DATE, or today if no date is given.\n\
+                    Their history is kept for reporting, and any pending transfers are cancelled.")
+                    .argument("EMPLOYEE", "Alias of the employee leaving.")
+                    .argument("DATE", "Their last day; today if not given.")
+                    .example("TERMINATE jsmith 2019-03-31")
+                    .related(&["status", "pending"]),
+                operation: TextInterface::terminate,
+            },
+            Command {
+                keyword: String::from("position"),
+                short_desc: String::from("Show or change an employee's job title, level and FTE."),
+                help: HelpPage::new(Topic::People)
+                    .usage("POSITION [EMPLOYEE]")
+                    .usage("POSITION [EMPLOYEE] CHANGE [DATE]")
+                    .description("With only EMPLOYEE, prints their current position and position history.  CHANGE\n\
+                    records a promotion or other change of position as of DATE, or today if no date\n\
+                    is given.  You will be asked for the new title, level and FTE; press enter to keep\n\
+                    the current value, or enter - to clear the level.")
+                    .argument("EMPLOYEE", "Alias of the employee.")
+                    .argument("DATE", "Day the change takes effect; today if not given.")
+                    .example("POSITION jsmith")
+                    .example("POSITION jsmith CHANGE 2019-07-01")
+                    .related(&["status", "pay", "report"]),
+                operation: TextInterface::position,
+            },
+            Command {
+                keyword: String::from("status"),
+                short_desc: String::from("Show or change an employee's status and employment type."),
+                help: HelpPage::new(Topic::People)
+                    .usage("STATUS [EMPLOYEE]")
+                    .usage("STATUS [EMPLOYEE] SET [ACTIVE|LEAVE|SUSPENDED|TERMINATED] [DATE]")
+                    .usage("STATUS [EMPLOYEE] TYPE [FULL-TIME|PART-TIME|CONTRACTOR]")
+                    .description("With only EMPLOYEE, prints their employment type, current status and status\n\
+                    history.  SET records a change of status as of DATE, or today if no date is given;\n\
+                    use ACTIVE to record a return from leave or suspension.  Setting TERMINATED is the\n\
+                    same as TERMINATE.  TYPE changes the employment type.")
+                    .argument("EMPLOYEE", "Alias of the employee.")
+                    .argument("DATE", "Day the change takes effect; today if not given.")
+                    .example("STATUS jsmith")
+                    .example("STATUS jsmith SET LEAVE 2019-06-01")
+                    .example("STATUS jsmith SET ACTIVE 2019-09-01")
+                    .example("STATUS jsmith TYPE CONTRACTOR")
+                    .related(&["terminate", "position"]),
+                operation: TextInterface::status,
+            },
+            Command {
+                keyword: String::from("member"),
+                short_desc: String::from("Show or change an employee's secondary departments."),
+                help: HelpPage::new(Topic::People)
+                    .usage("MEMBER [EMPLOYEE]")
+                    .usage("MEMBER [EMPLOYEE] JOIN [DEPARTMENT] [PERCENT] [DATE]")
+                    .usage("MEMBER [EMPLOYEE] LEAVE [DEPARTMENT] [DATE]")
+                    .description("Employees belong to one primary department, which TRANSFER changes, and may give\n\
+                    part of their time to other departments as secondary members.  With only\n\
+                    EMPLOYEE, prints how their time is split and their past memberships.  JOIN gives\n\
+                    PERCENT of their time to DEPARTMENT from DATE, or today; joining a department they\n\
+                    are already a member of changes their allocation.  The primary department keeps\n\
+                    whatever is left, so secondary allocations can total at most 99%.  LEAVE ends a\n\
+                    membership.  Secondary members are listed in the rosters of both departments, and\n\
+                    FTE in reports is split according to allocation.")
+                    .argument("EMPLOYEE", "Alias of the employee.")
+                    .argument("DEPARTMENT", "Alias of the secondary department.")
+                    .argument("PERCENT", "Share of their time given to DEPARTMENT, from 1 to 99.")
+                    .argument("DATE", "Day the change takes effect; today if not given.")
+                    .example("MEMBER jsmith JOIN platform 40")
+                    .example("MEMBER jsmith JOIN platform 50 2020-01-01")
+                    .example("MEMBER jsmith LEAVE platform 2020-06-30")
+                    .related(&["transfer", "list"]),
+                operation: TextInterface::member,
+            },
+            Command {
+                keyword: String::from("pay"),
+                short_desc: String::from("Show or change an employee's pay."),
+                help: HelpPage::new(Topic::People)
+                    .usage("PAY [EMPLOYEE]")
+                    .usage("PAY [EMPLOYEE] CHANGE [DATE]")
+                    .description("With only EMPLOYEE, prints their current pay and pay history.  CHANGE records a\n\
+                    new salary or hourly rate as of DATE, or today if no date is given.  You will be\n\
+                    asked for the amount, currency, basis (ANNUAL or HOURLY) and a reason; press\n\
+                    enter to keep the current currency and basis.  Only available in the HR role\n\
+                    (see ROLE).")
+                    .argument("EMPLOYEE", "Alias of the employee.")
+                    .argument("DATE", "Day the new pay takes effect; today if not given.")
+                    .example("PAY jsmith")
+                    .example("PAY jsmith CHANGE 2019-07-01")
+                    .related(&["budget", "role", "report"]),
+                operation: TextInterface::pay,
+            },
+            Command {
+                keyword: String::from("budget"),
+                short_desc: String::from("Show or set a department's payroll budget."),
+                help: HelpPage::new(Topic::Departments)
+                    .usage("BUDGET [DEPARTMENT] [AMOUNT CURRENCY|NONE]")
+                    .description("With only DEPARTMENT, prints its yearly payroll budget.  Otherwise sets the\n\
+                    budget, or clears it with NONE.  See REPORT PAYROLL to compare budgets with\n\
+                    actual pay.  Only available in the HR role (see ROLE).")
+                    .argument("DEPARTMENT", "Alias of the department.")
+                    .argument("AMOUNT CURRENCY", "Yearly budget, such as 1,250,000 USD.")
+                    .example("BUDGET eng 1,250,000 USD")
+                    .example("BUDGET eng NONE")
+                    .related(&["pay", "report", "role"]),
+                operation: TextInterface::budget,
+            },
+            Command {
+                keyword: String::from("role"),
+                short_desc: String::from("Show or change the role of this session."),
+                help: HelpPage::new(Topic::Settings)
+                    .usage("ROLE [STANDARD|HR]")
+                    .description("With no arguments, prints the current role.  Pay and budgets are only shown,\n\
+                    saved and merged in the HR role.  You will be asked for the HR passphrase before\n\
+                    taking the HR role; if none has been set yet, you are asked to choose one (see\n\
+                    SET HRPASSPHRASE).")
+                    .example("ROLE HR")
+                    .example("ROLE STANDARD")
+                    .related(&["pay", "budget", "set"]),
+                operation: TextInterface::role,
+            },
+            Command {
+                keyword: String::from("contact"),
+                short_desc: String::from("Show or change an employee's contact details and custom fields."),
+                help: HelpPage::new(Topic::People)
+                    .usage("CONTACT [EMPLOYEE]")
+                    .usage("CONTACT [EMPLOYEE] SET [FIELD] [VALUE]")
+                    .description("With only EMPLOYEE, prints their email, phone, office and custom fields.  SET\n\
+                    changes EMAIL, PHONE, OFFICE or a custom field to VALUE, or clears it if no value\n\
+                    is given.  Email addresses and phone numbers are checked for format, and custom\n\
+                    fields must match their type.")
+                    .argument("EMPLOYEE", "Alias of the employee.")
+                    .argument("FIELD", "EMAIL, PHONE, OFFICE or the name of a custom field.")
+                    .argument("VALUE", "New value; the rest of the line is used.")
+                    .example("CONTACT jsmith")
+                    .example("CONTACT jsmith SET EMAIL john.smith@example.com")
+                    .example("CONTACT jsmith SET OFFICE Building 2, Room 210")
+                    .example("CONTACT jsmith SET SHIRT L")
+                    .related(&["field", "query"]),
+                operation: TextInterface::contact,
+            },
+            Command {
+                keyword: String::from("field"),
+                short_desc: String::from("List, define or remove custom employee fields."),
+                help: HelpPage::new(Topic::Settings)
+                    .usage("FIELD")
+                    .usage("FIELD DEFINE [NAME] [TEXT|DATE|NUMBER|ENUM CHOICES]")
+                    .usage("FIELD REMOVE [NAME]")
+                    .description("Custom fields hold extra information on every employee.  Each has a type: TEXT,\n\
+                    DATE, NUMBER, or ENUM followed by a comma separated list of allowed values.  With\n\
+                    no arguments, lists the fields defined.  Custom fields appear in the NEW EMPLOYEE\n\
+                    form, can be changed with CONTACT, and can be used in QUERY.  REMOVE deletes the\n\
+                    field and every value stored in it.")
+                    .argument("NAME", "Name of the field.")
+                    .argument("CHOICES", "Allowed values of an ENUM field, separated by commas.")
+                    .example("FIELD DEFINE badge NUMBER")
+                    .example("FIELD DEFINE shirt ENUM S,M,L,XL")
+                    .example("FIELD DEFINE review DATE")
+                    .example("FIELD REMOVE shirt")
+                    .related(&["contact", "new", "query"]),
+                operation: TextInterface::field,
+            },
+            Command {
+                keyword: String::from("location"),
+                short_desc: String::from("List, add, remove or assign locations."),
+                help: HelpPage::new(Topic::Departments)
+                    .usage("LOCATION")
+                    .usage("LOCATION ADD [CODE] [NAME]")
+                    .usage("LOCATION REMOVE [CODE]")
+                    .usage("LOCATION ASSIGN [ALIAS] [CODE|NONE]")
+                    .description("Locations are offices or sites, identified by a short CODE.  With no arguments,\n\
+                    lists every location.  ASSIGN sets the location of a department, or of a single\n\
+                    employee in place of their department's; NONE clears it.  A location can only be\n\
+                    removed once nothing is assigned to it.  See LIST LOCATIONS and REPORT LOCATIONS.")
+                    .argument("CODE", "Short code of the location.")
+                    .argument("NAME", "Full name of the location.")
+                    .argument("ALIAS", "Alias of a department or employee.")
+                    .example("LOCATION ADD NYC New York Office")
+                    .example("LOCATION ASSIGN eng NYC")
+                    .example("LOCATION ASSIGN jsmith NONE")
+                    .related(&["costcenter", "list", "report"]),
+                operation: TextInterface::location,
+            },
+            Command {
+                keyword: String::from("costcenter"),
+                short_desc: String::from("List, add, remove or assign cost centers."),
+                help: HelpPage::new(Topic::Departments)
+                    .usage("COSTCENTER")
+                    .usage("COSTCENTER ADD [CODE] [NAME]")
+                    .usage("COSTCENTER REMOVE [CODE]")
+                    .usage("COSTCENTER ASSIGN [ALIAS] [CODE|NONE]")
+                    .description("Cost centers are the accounts payroll is billed to, identified by a short CODE.\n\
+                    They work like locations: a department may be assigned one, and an employee may\n\
+                    be assigned one in place of their department's.  See LIST COSTCENTERS and REPORT\n\
+                    COSTCENTERS.")
+                    .argument("CODE", "Short code of the cost center.")
+                    .argument("NAME", "Full name of the cost center.")
+                    .argument("ALIAS", "Alias of a department or employee.")
+                    .example("COSTCENTER ADD CC-4100 Platform R&D")
+                    .example("COSTCENTER ASSIGN eng CC-4100")
+                    .related(&["location", "list", "report"]),
+                operation: TextInterface::cost_center,
+            },
+            Command {
+                keyword: String::from("lookup"),
+                short_desc: String::from("Find an employee by employee number."),
+                help: HelpPage::new(Topic::Finding)
+                    .usage("LOOKUP [ID]")
+                    .description("Prints the employee with the given employee number, including former employees.\n\
+                    The number may be written in the configured format (see SET IDFORMAT) or as a\n\
+                    bare number.")
+                    .argument("ID", "Employee number.")
+                    .example("LOOKUP EMP-000123")
+                    .example("LOOKUP 123")
+                    .related(&["find", "set"]),
+                operation: TextInterface::lookup,
+            },
+            Command {
+                keyword: String::from("alias"),
+                short_desc: String::from("Show, add, rename or remove aliases."),
+                help: HelpPage::new(Topic::Finding)
+                    .usage("ALIAS [ALIAS]")
+                    .usage("ALIAS ADD [EXISTING] [NEW]")
+                    .usage("ALIAS RENAME [OLD] [NEW]")
+                    .usage("ALIAS REMOVE [ALIAS]")
+                    .description("People and departments each have a main alias, and may have others such as a\n\
+                    nickname or email handle.  Any alias can be used wherever one is asked for.  No\n\
+                    alias may be used by two people or departments.\n\n\
+                    With a single ALIAS, prints every alias of the person or department it names.  ADD\n\
+                    gives the person or department known as EXISTING another alias.  RENAME replaces\n\
+                    one alias with another; renaming a main alias keeps it the main alias.  REMOVE\n\
+                    drops an alias other than the main one.")
+                    .argument("ALIAS, EXISTING, OLD", "An alias already in use.")
+                    .argument("NEW", "An alias not yet in use.")
+                    .example("ALIAS ADD jsmith john.smith")
+                    .example("ALIAS RENAME eng engineering")
+                    .example("ALIAS REMOVE john.smith")
+                    .related(&["find", "new"]),
+                operation: TextInterface::alias,
+            },
+            Command {
+                keyword: String::from("diff"),
+                short_desc: String::from("Show what changed between two saved files."),
+                help: HelpPage::new(Topic::Files)
+                    .usage("DIFF [FILE] [TEXT|JSON] [OUTPUT]")
+                    .usage("DIFF [OLD FILE] [NEW FILE] [TEXT|JSON] [OUTPUT]")
+                    .description("Lists departments added, removed or renamed, people hired, terminated or renamed,\n\
+                    changes of alias and new department history entries.  Given one FILE, compares it\n\
+                    with the data in memory; given two, compares OLD FILE with NEW FILE.  Departments\n\
+                    and people are matched by number, so changes of alias are shown as such.  Output is\n\
+                    TEXT by default, and is written to OUTPUT if given.")
+                    .argument("FILE", "Saved file to compare with the data in memory.")
+                    .argument("OLD FILE, NEW FILE", "Two saved files to compare.")
+                    .argument("OUTPUT", "File to write the differences to.")
+                    .example("DIFF roster.json")
+                    .example("DIFF monday.json tuesday.json JSON changes.json")
+                    .related(&["merge", "save"]),
+                operation: TextInterface::diff,
+            },
+            Command {
+                keyword: String::from("merge"),
+                short_desc: String::from("Combine two copies of a file edited separately."),
+                help: HelpPage::new(Topic::Files)
+                    .usage("MERGE [BASE] [THEIRS]")
+                    .usage("MERGE [BASE] [OURS] [THEIRS]")
+                    .description("Merges the changes made in THEIRS into the data in memory, or into OURS if given,\n\
+                    where both were edited from the saved file BASE.  Changes made on only one side,\n\
+                    such as to different people or a transfer made by one side alone, are applied\n\
+                    as they are.  When the same person or department was changed differently on both\n\
+                    sides, such as a person transferred to two different departments, each version is\n\
+                    shown and you are asked which to keep: OURS, THEIRS or BASE.  Enter CANCEL to stop\n\
+                    without changing anything.  The merge is refused if the changes contradict each\n\
+                    other, such as a transfer on one side dated after a termination on the other (see\n\
+                    VERIFY).  The merged data replaces the data in memory, after asking if it has\n\
+                    changes not yet saved; use SAVE to keep it.  Conflicting changes to pay or budgets can only be merged in the HR\n\
+                    role.")
+                    .argument("BASE", "Saved file both copies were started from.")
+                    .argument("OURS", "One edited copy; the data in memory if not given.")
+                    .argument("THEIRS", "The other edited copy.")
+                    .example("MERGE roster.json alice.json")
+                    .example("MERGE roster.json alice.json bob.json")
+                    .related(&["diff", "save", "verify"]),
+                operation: TextInterface::merge,
+            },
+            Command {
+                keyword: String::from("verify"),
+                short_desc: String::from("Check the data for inconsistencies."),
+                help: HelpPage::new(Topic::Files)
+                    .usage("VERIFY [REPAIR]")
+                    .description("Checks that each current employee is listed once, in their own department, that\n\
+                    department lists are in order, that each employee's department matches their\n\
+                    department history, that no one left before they were hired or before a change of\n\
+                    department, that every alias leads to a person or department, and that the counts\n\
+                    of employees and departments are right.  Lists any problems found.  With REPAIR,\n\
+                    also fixes them, taking department history, memberships and the main list of\n\
+                    aliases as correct.  Termination dates are never changed by REPAIR.")
+                    .example("VERIFY")
+                    .example("VERIFY REPAIR")
+                    .related(&["load", "merge"]),
+                operation: TextInterface::verify,
+            },
+            Command {
+                keyword: String::from("save"),
+                short_desc: String::from("Save all data to a file."),
+                help: HelpPage::new(Topic::Files)
+                    .usage("SAVE [FILE]")
+                    .description("Writes all departments, employees and settings to FILE as JSON.  Without FILE,\n\
+                    saves to the file last saved or loaded, or roster.json.\n\n\
+                    Outside the HR role, pay and budgets are only saved back to that file.  Saving to\n\
+                    any other FILE writes a copy without them or the HR passphrase.")
+                    .argument("FILE", "File to write.")
+                    .example("SAVE backup.json")
+                    .related(&["load", "diff"]),
+                operation: TextInterface::save,
+            },
+            Command {
+                keyword: String::from("load"),
+                short_desc: String::from("Load data saved by SAVE."),
+                help: HelpPage::new(Topic::Files)
+                    .usage("LOAD [FILE]")
+                    .description("Replaces the data in memory with the contents of FILE, or roster.json if no file\n\
+                    is given.  Unsaved changes are lost.")
+                    .argument("FILE", "File written by SAVE.")
+                    .example("LOAD backup.json")
+                    .related(&["save", "verify"]),
+                operation: TextInterface::load,
+            },
+            Command {
+                keyword: String::from("set"),
+                short_desc: String::from("View or change settings."),
+                help: HelpPage::new(Topic::Settings)
+                    .usage("SET")
+                    .usage("SET DATEFORMAT [ISO|US|EU|PATTERN]")
+                    .usage("SET COLLATION [FAMILY|GIVEN] [PREFERRED] [LOCALE]")
+                    .usage("SET IDFORMAT [PLAIN|PATTERN]")
+                    .usage("SET HRPASSPHRASE")
+                    .description("With no arguments, prints the current settings.\n\n\
+                    DATEFORMAT controls how dates are shown and read: ISO (YYYY-MM-DD), US\n\
+                    (MM/DD/YYYY), EU (DD/MM/YYYY), or a chrono pattern such as %d.%m.%Y.  Dates may\n\
+                    always be entered in ISO form, or as TODAY, YESTERDAY, TOMORROW, or an offset from\n\
+                    today such as +2w, -3d, +1m or -1y.\n\n\
+                    COLLATION controls how names are put in order: by FAMILY name then given name\n\
+                    (the default), or by GIVEN name then family name.  PREFERRED sorts by preferred\n\
+                    name in place of the given name where one has been set.  LOCALE sets the language\n\
+                    rules used for every alphabetical listing: ROOT (the default, ignoring case and\n\
+                    accents), DE (German), SV (Swedish/Finnish), ES (Spanish), or BINARY (exact\n\
+                    character codes).\n\n\
+                    IDFORMAT controls how employee numbers are shown: PLAIN numbers, or a pattern in\n\
+                    which a run of # stands for the zero-padded number.  Employee numbers themselves\n\
+                    never change.\n\n\
+                    HRPASSPHRASE asks for a new passphrase needed to take the HR role.  Only available\n\
+                    in the HR role.")
+                    .argument("PATTERN", "A chrono date pattern, or an employee number pattern using #.")
+                    .argument("LOCALE", "ROOT, DE, SV, ES or BINARY.")
+                    .example("SET DATEFORMAT EU")
+                    .example("SET DATEFORMAT %d.%m.%Y")
+                    .example("SET COLLATION GIVEN PREFERRED")
+                    .example("SET COLLATION FAMILY DE")
+                    .example("SET IDFORMAT EMP-######")
+                    .related(&["role", "list", "lookup"]),
+                operation: TextInterface::set,
+            },
+        ];
+
+        TextInterface {
+            io: TextIO {
+                editor: LineEditor::new(),
+                stdout: io::stdout(),
+            },
+            data: ProgramData::init(),
+            commands,
+            data_file: String::from(DEFAULT_FILE),
+            role: Role::Standard,
+            plan: ReorgPlan::new(),
+            line: String::new(),
+            completions: None,
+        }
+    }
+
+    /// NYI
+    pub fn run(&mut self) -> Result<()> {
+        loop {
+            self.update_completions();
+
+            let io_buff = match self.io.editor.read_command("")? {
+                Some(line) => line,
+                None => return self.quit("".split_whitespace()),
+            };
+
+            self.line = io_buff.clone();
+            let mut command = io_buff.split_whitespace();
+
+            match command.next() {
+                Some(word) => {
+                    let comm = word.to_lowercase();
+
+                    let mut op: Option<fn(&mut TextInterface, std::str::SplitWhitespace) -> Result<()>> = None;
+                    for item in &self.commands {
+                        if item.keyword == comm {
+                            op = Some(item.operation);
+                        }
+                    }
+
+                    self.apply_due_transfers();
+
+                    if op.is_some() && self.role != Role::Hr && HR_COMMANDS.contains(&comm.as_str()) {
+                        println!("{} is only available in the HR role.  See ROLE.", comm.to_uppercase());
+                    } else if let Some(op) = op {
+                        // A command that fails is reported, and the next one is read as usual.
+                        if let Err(e) = op(self, command) {
+                            eprintln!("{} failed: {}", comm.to_uppercase(), e);
+                        }
+                    } else {
+                        println!("Type HELP for a list of commands.");
+                    }
+                },
+                None => println!("Type HELP for a list of commands."),
+            };
+        }
+    }
+
+    /// Offer the commands available in the current role, and every alias, for Tab completion.
+    /// Nothing is done unless the role or an alias has changed since the last time.
+    fn update_completions(&mut self) {
+        let current = Some((self.role, self.data.alias_generation()));
+        if !self.io.editor.is_interactive() || self.completions == current {
+            return;
+        }
+        self.completions = current;
+
+        let keywords = self.visible_commands().map(|comm| comm.keyword.clone()).collect();
+        let aliases = self.data.employee_list().flat_map(|entry| entry.aliases())
+            .chain(self.data.dept_list().flat_map(|entry| entry.aliases()))
+            .cloned()
+            .collect();
+
+        self.io.editor.set_completions(keywords, aliases);
+    }
+
+
+
+    fn help(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        match args.next().map(|arg| arg.to_lowercase()) {
+            None => {
+                println!("Type HELP [COMMAND] for more information, or HELP TOPICS for commands by subject.");
+                if self.io.editor.is_interactive() {
+                    println!("Tab completes commands and aliases, and Ctrl-R searches earlier commands.");
+                }
+                println!();
+
+                for comm in self.visible_commands() {
+                    println!("{}:  {}", comm.keyword, comm.short_desc);
+                }
+            },
+            Some(ref arg) if arg == "topics" => {
+                println!("Type HELP [TOPIC] or HELP [COMMAND] for more information.");
+
+                for topic in Topic::ALL.iter() {
+                    println!();
+                    self.print_topic(*topic);
+                }
+            },
+            Some(ref arg) if arg == "export" => {
+                let format = match args.next().map(DocFormat::from_str) {
+                    Some(Ok(format)) => format,
+                    _ => {
+                        println!("Export help as MARKDOWN or MAN.");
+                        return Ok(());
+                    },
+                };
+
+                let visible: Vec<(&Command, HelpPage)> = self.visible_commands()
+                    .map(|comm| (comm, self.help_page(comm)))
+                    .collect();
+                let pages: Vec<(&str, &str, &HelpPage)> = visible.iter()
+                    .map(|(comm, page)| (comm.keyword.as_str(), comm.short_desc.as_str(), page))
+                    .collect();
+                let text = match format {
+                    DocFormat::Markdown => help::markdown(&pages),
+                    DocFormat::Man => help::man(&pages),
+                };
+
+                let path = args.next();
+                if let Err(e) = write_output(&text, path) {
+                    eprintln!("Could not export help to {}: {}", path.unwrap_or("the screen"), e);
+                }
+            },
+            Some(arg) => {
+                let page = self.visible_commands().find(|comm| comm.keyword == arg).map(|comm| self.help_page(comm).text());
+                if let Some(text) = page {
+                    self.io.stdout.write_all(text.as_bytes())?;
+                    self.io.stdout.flush()?;
+                } else if let Ok(topic) = Topic::from_str(&arg) {
+                    self.print_topic(topic);
+                } else {
+                    println!("Command not found: {}", arg);
+                }
+            },
+        };
+        Ok(())
+    }
+
+    /// Commands available in the current role.
+    fn visible_commands(&self) -> impl Iterator<Item = &Command> {
+        let role = self.role;
+        self.commands.iter().filter(move |comm| role == Role::Hr || !HR_COMMANDS.contains(&comm.keyword.as_str()))
+    }
+
+    /// A command's help as shown in the current role, without pointers to commands that aren't
+    /// available.
+    fn help_page(&self, comm: &Command) -> HelpPage {
+        match self.role {
+            Role::Hr => comm.help.clone(),
+            Role::Standard => comm.help.without_related(&HR_COMMANDS),
+        }
+    }
+
+    /// Print a topic and the commands on it, as in HELP TOPICS.
+    fn print_topic(&self, topic: Topic) {
+        let commands: Vec<&Command> = self.visible_commands().filter(|comm| comm.help.topic() == topic).collect();
+        let width = commands.iter().map(|comm| comm.keyword.len()).max().unwrap_or(0);
+
+        println!("{}:  {}", topic.keyword().to_uppercase(), topic.description());
+        for comm in commands {
+            println!("    {:width$}  {}", comm.keyword, comm.short_desc, width = width);
+        }
+    }
+
+    fn quit(&mut self, _args: std::str::SplitWhitespace) -> Result<()> {
+        println!("\nSo long, sucker!");
+        process::exit(0);
+    }
+
+    fn list(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        match args.next() {
+            Some(what) => {
+                let what = what.to_lowercase();
+
+                if what == "employees" {
+                    if let Err(e) = self.list_employees(args) {
+                        eprintln!("Error printing list: {}", e);
+                    }
+                } else if what == "departments" {
+                    for dept in self.data.dept_list() {
+                        let department = dept.borrow_pointer().borrow();
+                        let codes: Vec<&String> = department.location().into_iter()
+                            .chain(department.cost_center())
+                            .collect();
+
+                        if codes.is_empty() {
+                            println!("\"{}\": {}", dept.alias(), department);
+                        } else {
+                            let codes: Vec<&str> = codes.iter().map(|c| c.as_str()).collect();
+                            println!("\"{}\": {} [{}]", dept.alias(), department, codes.join(", "));
+                        }
+                    }
+                } else if let Ok(grouping) = Grouping::from_str(&what) {
+                    let report = self.data.group_report(grouping, Local::today().naive_local());
+
+                    for group in report.groups() {
+                        let name = if group.name.is_empty() { String::new() } else { format!(" {}", group.name) };
+                        println!("{}{} ({}, {:.2} FTE):", group.code, name, group.headcount(), group.fte());
+                        for holder in &group.people {
+                            println!("    \"{}\" ({}): {}", holder.alias, holder.employee_id, holder.name);
+                        }
+                    }
+                } else if what == "duplicates" {
+                    let groups = self.data.duplicate_names();
+
+                    if groups.is_empty() {
+                        println!("No employees share a name.");
+                    }
+
+                    for group in groups {
+                        println!("{}:", group[0].pointer().borrow().name());
+                        for entry in group {
+                            let person = entry.pointer();
+                            let person = person.borrow();
+                            println!("    \"{}\": {}, DOH: {}, {}", entry.alias(), self.data.employee_id(&person),
+                                self.data.date_format().format(&person.date_of_hire()),
+                                person.department().borrow().name());
+                        }
+                    }
+                }
+            },
+            None => Self::short_help(),
+        };
+
+        Ok(())
+    }
+
+    fn list_employees(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        match args.next() {
+            Some(dept_alias) => {
+                if !self.data.departments().contains_key(dept_alias) {
+                    return Err(TextInterfaceError::NoDepartment);
+                }
+
+                let today = Local::today().naive_local();
+                let report = self.data.position_report(today, Some(dept_alias));
+
+                for group in report.groups() {
+                    println!("{} ({}, {:.2} FTE):", group.title, group.headcount(), group.fte());
+                    for holder in &group.people {
+                        let level = holder.level.as_ref().map(|l| format!(", {}", l)).unwrap_or_default();
+                        let share = match (holder.primary, holder.allocation) {
+                            (true, 100) => String::new(),
+                            (true, allocation) => format!(" [{}%]", allocation),
+                            (false, allocation) => format!(" [secondary, {}%]", allocation),
+                        };
+                        println!("    \"{}\" ({}): {}{}{}", holder.alias, holder.employee_id, holder.name, level, share);
+                    }
+                }
+            },
+            None => {
+                let all_sorted = self.sort_employees();
+
+                for (alias, id, name) in all_sorted {
+                    println!("\"{}\" ({}): {}", alias, id, name);
+                }
+            },
+        }
+        Ok(())
+    }
+
+    fn find(&mut self, args: std::str::SplitWhitespace) -> Result<()> {
+        let text = args.collect::<Vec<&str>>().join(" ");
+
+        if text.is_empty() {
+            Self::short_help();
+            return Ok(());
+        }
+
+        let results = self.data.search(&text);
+
+        if results.is_empty() {
+            println!("No matches for \"{}\".", text);
+        }
+
+        for result in results {
+            println!("{}", result);
+        }
+
+        Ok(())
+    }
+
+    fn query(&mut self, _args: std::str::SplitWhitespace) -> Result<()> {
+        // The query is taken from the line as typed, so that spaces in quoted values are kept.
+        let line = self.line.trim();
+        let text = line[line.find(char::is_whitespace).unwrap_or(line.len())..].trim().to_string();
+
+        if text.is_empty() {
+            Self::short_help();
+            return Ok(());
+        }
+
+        let result = match self.data.query(&text) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("{}", e);
+                return Ok(());
+            },
+        };
+
+        let headers: Vec<String> = result.columns().iter().map(|c| c.to_string()).collect();
+        let rows: Vec<Vec<String>> = result.rows().iter()
+            .map(|row| row.values().iter().map(|v| match v {
+                Value::Date(d) => self.data.date_format().format(d),
+                other => other.to_string(),
+            }).collect())
+            .collect();
+
+        print!("{}", format_table(&headers, &rows));
+        println!("{} row(s)", rows.len());
+
+        Ok(())
+    }
+
+    fn report(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        match args.next().map(|a| a.to_lowercase()) {
+            Some(ref what) if what == "headcount" => {
+                if let Err(e) = self.report_headcount(args) {
+                    eprintln!("Could not build report: {}", e);
+                }
+            },
+            Some(ref what) if what == "tenure" => {
+                if let Err(e) = self.report_tenure(args) {
+                    eprintln!("Could not build report: {}", e);
+                }
+            },
+            Some(ref what) if what == "positions" => {
+                if let Err(e) = self.report_positions(args) {
+                    eprintln!("Could not build report: {}", e);
+                }
+            },
+            Some(ref what) if Grouping::from_str(what).is_ok() => {
+                let grouping = Grouping::from_str(what).unwrap_or(Grouping::Location);
+
+                if let Err(e) = self.report_groups(grouping, args) {
+                    eprintln!("Could not build report: {}", e);
+                }
+            },
+            Some(ref what) if what == "payroll" => {
+                if self.role != Role::Hr {
+                    println!("Payroll reports are only available in the HR role.  See ROLE.");
+                } else if let Err(e) = self.report_payroll(args) {
+                    eprintln!("Could not build report: {}", e);
+                }
+            },
+            _ => Self::short_help(),
+        };
+
+        Ok(())
+    }
+
+    fn report_headcount(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        let period = args.next()
+            .and_then(|p| Period::from_str(p).ok())
+            .ok_or(TextInterfaceError::InvalidArgument)?;
+        let from = self.parse_date(args.next().unwrap_or_default())?;
+        let to = self.parse_date(args.next().unwrap_or_default())?;
+
+        let mut args = args.peekable();
+        let exclude_leave = args.peek().is_some_and(|a| a.eq_ignore_ascii_case("active"));
+        if exclude_leave {
+            args.next();
+        }
+
+        let report = self.data.headcount_report(period, from, to, exclude_leave);
+
+        let output = match args.next().map(|f| f.to_lowercase()) {
+            None => format_table(&HeadcountReport::headers(), &report.table_rows()),
+            Some(ref format) if format == "table" => format_table(&HeadcountReport::headers(), &report.table_rows()),
+            Some(ref format) if format == "json" => format!("{:#}\n", report.to_json()),
+            Some(ref format) if format == "csv" => report.to_csv(),
+            Some(_) => return Err(TextInterfaceError::InvalidArgument),
+        };
+
+        write_output(&output, args.next())
+    }
+
+    fn report_positions(&mut self, args: std::str::SplitWhitespace) -> Result<()> {
+        let mut args = args.peekable();
+        let today = Local::today().naive_local();
+
+        let department = match args.peek() {
+            Some(word) if !["table", "json", "csv"].contains(&word.to_lowercase().as_str()) => {
+                if !self.data.departments().contains_key(*word) {
+                    return Err(TextInterfaceError::NoDepartment);
+                }
+                args.next()
+            },
+            _ => None,
+        };
+
+        let report = self.data.position_report(today, department);
+
+        let output = match args.next().map(|f| f.to_lowercase()) {
+            None => format_table(&PositionReport::headers(), &report.table_rows()),
+            Some(ref format) if format == "table" => format_table(&PositionReport::headers(), &report.table_rows()),
+            Some(ref format) if format == "json" => format!("{:#}\n", report.to_json()),
+            Some(ref format) if format == "csv" => report.to_csv(),
+            Some(_) => return Err(TextInterfaceError::InvalidArgument),
+        };
+
+        write_output(&output, args.next())
+    }
+
+    fn report_payroll(&mut self, args: std::str::SplitWhitespace) -> Result<()> {
+        let mut args = args.peekable();
+
+        let as_of = match args.peek() {
+            Some(word) if !["table", "json", "csv"].contains(&word.to_lowercase().as_str()) => {
+                let date = self.parse_date(word)?;
+                args.next();
+                date
+            },
+            _ => Local::today().naive_local(),
+        };
+
+        let report = self.data.payroll_report(as_of);
+
+        let output = match args.next().map(|f| f.to_lowercase()) {
+            None => format_table(&PayrollReport::headers(), &report.table_rows()),
+            Some(ref format) if format == "table" => format_table(&PayrollReport::headers(), &report.table_rows()),
+            Some(ref format) if format == "json" => format!("{:#}\n", report.to_json()),
+            Some(ref format) if format == "csv" => report.to_csv(),
+            Some(_) => return Err(TextInterfaceError::InvalidArgument),
+        };
+
+        write_output(&output, args.next())
+    }
+
+    fn report_groups(&mut self, grouping: Grouping, args: std::str::SplitWhitespace) -> Result<()> {
+        let mut args = args.peekable();
+
+        let as_of = match args.peek() {
+            Some(word) if !["table", "json", "csv"].contains(&word.to_lowercase().as_str()) => {
+                let date = self.parse_date(word)?;
+                args.next();
+                date
+            },
+            _ => Local::today().naive_local(),
+        };
+
+        let report = self.data.group_report(grouping, as_of);
+
+        let output = match args.next().map(|f| f.to_lowercase()) {
+            None => format_table(&report.headers(), &report.table_rows()),
+            Some(ref format) if format == "table" => format_table(&report.headers(), &report.table_rows()),
+            Some(ref format) if format == "json" => format!("{:#}\n", report.to_json()),
+            Some(ref format) if format == "csv" => report.to_csv(),
+            Some(_) => return Err(TextInterfaceError::InvalidArgument),
+        };
+
+        write_output(&output, args.next())
+    }
+
+    fn report_tenure(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        let today = Local::today().naive_local();
+        let report = self.data.tenure_report(today);
+        let date_format = self.data.date_format();
+
+        let headers: Vec<String> = ["rank", "id", "alias", "name", "dept", "hired", "company", "in dept"].iter()
+            .map(|h| String::from(*h))
+            .collect();
+        let row = |rank: usize, r: &TenureRecord| vec![
+            rank.to_string(),
+            r.employee_id.clone(),
+            r.alias.clone(),
+            r.name.to_string(),
+            r.department.clone(),
+            date_format.format(&r.hired),
+            r.company.to_string(),
+            r.in_department.to_string(),
+        ];
+
+        match args.next() {
+            None => {
+                let rows: Vec<Vec<String>> = report.records().iter()
+                    .enumerate()
+                    .map(|(i, r)| row(i + 1, r))
+                    .collect();
+                print!("{}", format_table(&headers, &rows));
+
+                if let Some(average) = report.average_years() {
+                    println!("Average tenure: {:.1} years", average);
+                }
+            },
+            Some(what) if what.eq_ignore_ascii_case("anniversaries") => {
+                let days = match args.next() {
+                    Some(days) => u32::from_str(days).map_err(|_| TextInterfaceError::InvalidArgument)?,
+                    None => 30,
+                };
+
+                let headers: Vec<String> = ["date", "alias", "name", "years"].iter()
+                    .map(|h| String::from(*h))
+                    .collect();
+                let rows: Vec<Vec<String>> = report.anniversaries(days).iter()
+                    .map(|a| vec![date_format.format(&a.date), a.alias.clone(), a.name.to_string(), a.years.to_string()])
+                    .collect();
+
+                if rows.is_empty() {
+                    println!("No anniversaries in the next {} days.", days);
+                } else {
+                    print!("{}", format_table(&headers, &rows));
+                }
+            },
+            Some(what) if what.eq_ignore_ascii_case("histogram") => {
+                let histogram = report.histogram();
+                let width = histogram.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+
+                for (label, count) in histogram {
+                    let line = format!("{:width$}  {:>4}  {}", label, count, "#".repeat(count), width = width);
+                    println!("{}", line.trim_end());
+                }
+            },
+            Some(dept_alias) => {
+                if !self.data.departments().contains_key(dept_alias) {
+                    return Err(TextInterfaceError::InvalidArgument);
+                }
+
+                let rows: Vec<Vec<String>> = report.seniority(dept_alias).into_iter()
+                    .map(|(rank, r)| row(rank, r))
+                    .collect();
+                print!("{}", format_table(&headers, &rows));
+            },
+        };
+
+        Ok(())
+    }
+
+    /// Make any pending transfers whose date has arrived, reporting each one.
+    fn apply_due_transfers(&mut self) {
+        for (transfer, result) in self.data.apply_due_transfers(Local::today().naive_local()) {
+            let description = self.describe_transfer(&transfer);
+
+            match result {
+                Ok(()) => println!("Made pending transfer {}.", description),
+                Err(e) => eprintln!("Could not make pending transfer {}: {}", description, e),
+            }
+        }
+    }
+
+    /// e.g. "#3: "jsmith" Smith, John to "ops", 03/01/2019"
+    fn describe_transfer(&self, transfer: &PendingTransfer) -> String {
+        let person = transfer.person();
+        let person = person.borrow();
+        let alias = self.data.employee_list()
+            .find(|e| Rc::ptr_eq(&e.pointer(), &transfer.person()))
+            .map(|e| e.alias().clone())
+            .unwrap_or_default();
+        let dept_alias = self.data.dept_alias_of(&transfer.department()).cloned().unwrap_or_default();
+
+        format!("#{}: \"{}\" {} to \"{}\", {}", transfer.id(), alias, person.name(), dept_alias,
+            self.data.date_format().format(&transfer.date()))
+    }
+
+    fn transfer(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        let (alias, dept_alias) = match (args.next(), args.next()) {
+            (Some(alias), Some(dept_alias)) => (alias, dept_alias),
+            _ => {
+                Self::short_help();
+                return Ok(());
+            },
+        };
+
+        let date = match args.next() {
+            Some(date) => match self.date_arg(date) {
+                Some(date) => Some(date),
+                None => return Ok(()),
+            },
+            None => None,
+        };
+
+        let result = match date {
+            Some(date) if date > Local::today().naive_local() => self.data.schedule_transfer(alias, dept_alias, date)
+                .map(|id| println!("Transfer #{} scheduled for {}.", id, self.data.date_format().format(&date))),
+            _ => self.data.add_to_dept(alias, dept_alias, date)
+                .map(|_| println!("Transferred \"{}\" to \"{}\".", alias, dept_alias)),
+        };
+
+        if let Err(e) = result {
+            eprintln!("Could not transfer employee: {}", e);
+        }
+
+        Ok(())
+    }
+
+    fn pending(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        match (args.next().map(|a| a.to_lowercase()), args.next()) {
+            (None, _) => {
+                if self.data.pending_transfers().is_empty() {
+                    println!("No pending transfers.");
+                }
+
+                for transfer in self.data.pending_transfers() {
+                    println!("{}", self.describe_transfer(transfer));
+                }
+            },
+            (Some(ref action), Some(id)) if action == "cancel" => {
+                let id = match u32::from_str(id.trim_start_matches('#')) {
+                    Ok(id) => id,
+                    Err(_) => {
+                        println!("\"{}\" is not a transfer number.  See PENDING for the list.", id);
+                        return Ok(());
+                    },
+                };
+
+                match self.data.cancel_transfer(id) {
+                    Ok(transfer) => println!("Cancelled transfer {}.", self.describe_transfer(&transfer)),
+                    Err(e) => eprintln!("{}", e),
+                }
+            },
+            _ => Self::short_help(),
+        };
+
+        Ok(())
+    }
+
+    fn reorg(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        let action = match args.next() {
+            Some(action) => action.to_lowercase(),
+            None => {
+                if self.plan.is_empty() {
+                    println!("The plan is empty.");
+                }
+
+                for (i, step) in self.plan.steps().iter().enumerate() {
+                    println!("{:>3}. {}", i + 1, step);
+                }
+                return Ok(());
+            },
+        };
+
+        let step = match (action.as_str(), args.next(), args.next()) {
+            ("create", Some(alias), Some(first)) => Some(Step::Create {
+                alias: String::from(alias),
+                name: std::iter::once(first).chain(args).collect::<Vec<&str>>().join(" "),
+            }),
+            ("rename", Some(department), Some(first)) => Some(Step::Rename {
+                department: String::from(department),
+                name: std::iter::once(first).chain(args).collect::<Vec<&str>>().join(" "),
+            }),
+            ("transfer", Some(person), Some(department)) => Some(Step::Transfer {
+                person: String::from(person),
+                department: String::from(department),
+            }),
+            ("merge", Some(from), Some(into)) => Some(Step::Merge {
+                from: String::from(from),
+                into: String::from(into),
+            }),
+            ("remove", Some(number), None) => {
+                if !number.chars().all(|c| c.is_ascii_digit()) {
+                    println!("\"{}\" is not a step number.", number);
+                    return Ok(());
+                }
+
+                match usize::from_str(number).ok().and_then(|n| n.checked_sub(1)).and_then(|i| self.plan.remove(i)) {
+                    Some(step) => println!("Removed step {}: {}", number, step),
+                    None => println!("The plan has no step {}.", number),
+                }
+                None
+            },
+            ("preview", None, None) => {
+                self.preview_plan();
+                None
+            },
+            ("check", date, None) | ("apply", date, None) => {
+                let date = match date {
+                    Some(date) => match self.date_arg(date) {
+                        Some(date) => date,
+                        None => return Ok(()),
+                    },
+                    None => Local::today().naive_local(),
+                };
+
+                if action == "check" {
+                    let problems = self.plan.validate(&self.data, date);
+
+                    if problems.is_empty() {
+                        println!("No problems found.");
+                    }
+                    for (step, problem) in problems {
+                        println!("Step {}: {}", step, problem);
+                    }
+                } else {
+                    match self.plan.apply(&mut self.data, date) {
+                        Ok(()) => {
+                            println!("Applied {} step(s) effective {}.", self.plan.steps().len(),
+                                self.data.date_format().format(&date));
+                            self.plan = ReorgPlan::new();
+                        },
+                        Err(e) => eprintln!("{}", e),
+                    }
+                }
+                None
+            },
+            ("discard", None, None) => {
+                self.plan = ReorgPlan::new();
+                println!("Plan discarded.");
+                None
+            },
+            _ => {
+                Self::short_help();
+                None
+            },
+        };
+
+        if let Some(step) = step {
+            println!("Step {}: {}", self.plan.steps().len() + 1, step);
+            self.plan.add(step);
+        }
+
+        Ok(())
+    }
+
+    /// Print the departments a plan changes, with the people joining and leaving each.
+    fn preview_plan(&self) {
+        let changes = self.plan.preview(&self.data).changes();
+
+        if changes.is_empty() {
+            println!("The plan doesn't change any department.");
+        }
+
+        for change in changes {
+            match &change.old_name {
+                None => println!("\"{}\": {} (new)", change.alias, change.new_name),
+                Some(old) if *old != change.new_name => println!("\"{}\": {} -> {}", change.alias, old, change.new_name),
+                Some(_) => println!("\"{}\": {}", change.alias, change.new_name),
+            };
+
+            for (alias, name, from) in &change.joined {
+                println!("    + \"{}\" {} (from \"{}\")", alias, name, from);
+            }
+            for (alias, name, to) in &change.left {
+                println!("    - \"{}\" {} (to \"{}\")", alias, name, to);
+            }
+            println!("    {} -> {} employees", change.headcount_before, change.headcount_after);
+        }
+    }
+
+    fn terminate(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        let alias = match args.next() {
+            Some(alias) => alias,
+            None => {
+                Self::short_help();
+                return Ok(());
+            },
+        };
+
+        let date = match args.next() {
+            Some(date) => match self.date_arg(date) {
+                Some(date) => Some(date),
+                None => return Ok(()),
+            },
+            None => None,
+        };
+
+        if let Err(e) = self.data.terminate(alias, date) {
+            eprintln!("Could not terminate employee: {}", e);
+        }
+
+        Ok(())
+    }
+
+    fn position(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        let alias = match args.next() {
+            Some(alias) => alias,
+            None => {
+                Self::short_help();
+                return Ok(());
+            },
+        };
+
+        let person = match self.data.personnel().get(alias) {
+            Some(person) => Rc::clone(person),
+            None => {
+                println!("No employee known as \"{}\".", alias);
+                return Ok(());
+            },
+        };
+
+        match args.next() {
+            None => {
+                let person = person.borrow();
+
+                match person.position() {
+                    Some(position) => println!("{}: {}", person.name(), position),
+                    None => println!("{}: no position recorded", person.name()),
+                };
+
+                for entry in person.position_history() {
+                    println!("    {}  {}", self.data.date_format().format(&entry.date()), entry.position());
+                }
+            },
+            Some(word) if word.eq_ignore_ascii_case("change") => {
+                let date = match args.next() {
+                    Some(date) => match self.date_arg(date) {
+                        Some(date) => Some(date),
+                        None => return Ok(()),
+                    },
+                    None => None,
+                };
+
+                let current = person.borrow().position().cloned();
+                let current_title = current.as_ref().map(|p| p.title.clone());
+                let current_level = current.as_ref().and_then(|p| p.level.clone());
+                let current_fte = current.as_ref().map_or(1.0, |p| p.fte);
+
+                let prompt = format!("job title [{}]", current_title.clone().unwrap_or_default());
+                let title = match optional_string(&prompt, &mut self.io).or(current_title) {
+                    Some(title) => title,
+                    None => {
+                        println!("A job title is required.");
+                        return Ok(());
+                    },
+                };
+
+                let prompt = format!("level [{}]", current_level.clone().unwrap_or_default());
+                let level = match optional_string(&prompt, &mut self.io) {
+                    Some(ref level) if level == "-" => None,
+                    Some(level) => Some(level),
+                    None => current_level,
+                };
+
+                let prompt = format!("FTE [{:.2}]", current_fte);
+                let fte = match optional_string(&prompt, &mut self.io).map(|text| f64::from_str(&text)) {
+                    Some(Ok(fte)) => fte,
+                    Some(Err(_)) => {
+                        println!("FTE must be a number, such as 0.5.");
+                        return Ok(());
+                    },
+                    None => current_fte,
+                };
+
+                match self.data.change_position(alias, Position { title, level, fte }, date) {
+                    Ok(()) => println!("Position updated."),
+                    Err(e) => eprintln!("{}", e),
+                };
+            },
+            Some(_) => Self::short_help(),
+        };
+
+        Ok(())
+    }
+
+    fn lookup(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        let id = match args.next() {
+            Some(id) => id,
+            None => {
+                Self::short_help();
+                return Ok(());
+            },
+        };
+
+        let entry = match self.data.lookup(id) {
+            Some(entry) => entry,
+            None => {
+                println!("No employee with number {}.", id);
+                return Ok(());
+            },
+        };
+
+        let person = entry.pointer();
+        let person = person.borrow();
+        let date_format = self.data.date_format();
+
+        println!("{} \"{}\": {}", self.data.employee_id(&person), entry.alias(), person.name());
+        println!("    Hired:      {}", date_format.format(&person.date_of_hire()));
+        match person.termination_date() {
+            Some(date) => println!("    Terminated: {}", date_format.format(&date)),
+            None => {
+                println!("    Department: {}", person.department().borrow().name());
+                for membership in person.current_memberships() {
+                    println!("    Also in:    {} ({}%)", membership.department().borrow().name(), membership.allocation());
+                }
+                println!("    Status:     {}, {}", person.employment_type(), person.status());
+                if let Some(location) = self.data.location_of(&person) {
+                    println!("    Location:   {}", location);
+                }
+                if let Some(cost_center) = self.data.cost_center_of(&person) {
+                    println!("    Cost ctr:   {}", cost_center);
+                }
+            },
+        };
+        if let Some(email) = person.contact().email() {
+            println!("    Email:      {}", email);
+        }
+        if let Some(phone) = person.contact().phone() {
+            println!("    Phone:      {}", phone);
+        }
+        if let Some(office) = person.contact().office() {
+            println!("    Office:     {}", office);
+        }
+        if self.role == Role::Hr {
+            if let Some(pay) = person.compensation() {
+                println!("    Pay:        {}", pay);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn contact(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        let alias = match args.next() {
+            Some(alias) => alias,
+            None => {
+                Self::short_help();
+                return Ok(());
+            },
+        };
+
+        let person = match self.data.personnel().get(alias) {
+            Some(person) => Rc::clone(person),
+            None => {
+                println!("No employee known as \"{}\".", alias);
+                return Ok(());
+            },
+        };
+
+        match (args.next(), args.next()) {
+            (None, _) => {
+                let person = person.borrow();
+                let contact = person.contact();
+                let none = String::from("None");
+
+                println!("{}", person.name());
+                println!("    Email:  {}", contact.email().unwrap_or(&none));
+                println!("    Phone:  {}", contact.phone().unwrap_or(&none));
+                println!("    Office: {}", contact.office().unwrap_or(&none));
+
+                for def in self.data.schema().fields() {
+                    let value = match person.custom_field(&def.name) {
+                        Some(value) => value.format(self.data.date_format()),
+                        None => none.clone(),
+                    };
+                    println!("    {}: {}", def.name, value);
+                }
+            },
+            (Some(word), Some(field)) if word.eq_ignore_ascii_case("set") => {
+                let value = args.collect::<Vec<&str>>().join(" ");
+                let value = if value.is_empty() { None } else { Some(value.as_str()) };
+
+                match self.data.set_field(alias, field, value) {
+                    Ok(()) => println!("{} updated.", field),
+                    Err(e) => eprintln!("{}", e),
+                };
+            },
+            _ => Self::short_help(),
+        };
+
+        Ok(())
+    }
+
+    fn field(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        let result = match (args.next().map(|a| a.to_lowercase()), args.next()) {
+            (None, _) => {
+                if self.data.schema().fields().is_empty() {
+                    println!("No custom fields defined.");
+                }
+
+                for def in self.data.schema().fields() {
+                    println!("{}", def);
+                }
+                Ok(())
+            },
+            (Some(ref action), Some(name)) if action == "define" => {
+                match FieldType::from_str(&args.collect::<Vec<&str>>().join(" ")) {
+                    Ok(kind) => self.data.define_field(name, kind)
+                        .map(|_| println!("Defined field \"{}\".", name)),
+                    Err(_) => {
+                        eprintln!("Field type must be TEXT, DATE, NUMBER or ENUM followed by choices, e.g. ENUM S,M,L");
+                        Ok(())
+                    },
+                }
+            },
+            (Some(ref action), Some(name)) if action == "remove" => self.data.remove_field(name)
+                .map(|_| println!("Removed field \"{}\".", name)),
+            _ => {
+                Self::short_help();
+                Ok(())
+            },
+        };
+
+        if let Err(e) = result {
+            eprintln!("{}", e);
+        }
+
+        Ok(())
+    }
+
+    fn status(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        let alias = match args.next() {
+            Some(alias) => alias,
+            None => {
+                Self::short_help();
+                return Ok(());
+            },
+        };
+
+        let person = match self.data.personnel().get(alias) {
+            Some(person) => Rc::clone(person),
+            None => {
+                println!("No employee known as \"{}\".", alias);
+                return Ok(());
+            },
+        };
+
+        let result = match (args.next().map(|a| a.to_lowercase()), args.next()) {
+            (None, _) => {
+                let person = person.borrow();
+                let date_format = self.data.date_format();
+
+                println!("{}: {}, {}", person.name(), person.employment_type(), person.status());
+                println!("    {}  hired", date_format.format(&person.date_of_hire()));
+                for entry in person.status_history() {
+                    println!("    {}  {}", date_format.format(&entry.date), entry.status);
+                }
+                Ok(())
+            },
+            (Some(ref action), Some(status)) if action == "set" => {
+                let status = match Status::from_str(status) {
+                    Ok(status) => status,
+                    Err(_) => {
+                        println!("Unknown status \"{}\".  Use ACTIVE, LEAVE, SUSPENDED or TERMINATED.", status);
+                        return Ok(());
+                    },
+                };
+                let date = match args.next() {
+                    Some(date) => match self.date_arg(date) {
+                        Some(date) => Some(date),
+                        None => return Ok(()),
+                    },
+                    None => None,
+                };
+
+                self.data.change_status(alias, status, date)
+                    .map(|_| println!("Status updated."))
+            },
+            (Some(ref action), Some(kind)) if action == "type" => {
+                let kind = match EmploymentType::from_str(kind) {
+                    Ok(kind) => kind,
+                    Err(_) => {
+                        println!("Unknown employment type \"{}\".  Use FULL-TIME, PART-TIME or CONTRACTOR.", kind);
+                        return Ok(());
+                    },
+                };
+
+                self.data.set_employment_type(alias, kind)
+                    .map(|_| println!("Employment type updated."))
+            },
+            _ => {
+                Self::short_help();
+                Ok(())
+            },
+        };
+
+        if let Err(e) = result {
+            eprintln!("{}", e);
+        }
+
+        Ok(())
+    }
+
+    fn member(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        let alias = match args.next() {
+            Some(alias) => alias,
+            None => {
+                Self::short_help();
+                return Ok(());
+            },
+        };
+
+        let person = match self.data.personnel().get(alias) {
+            Some(person) => Rc::clone(person),
+            None => {
+                println!("No employee known as \"{}\".", alias);
+                return Ok(());
+            },
+        };
+
+        let result = match (args.next().map(|a| a.to_lowercase()), args.next()) {
+            (None, _) => {
+                let person = person.borrow();
+                let date_format = self.data.date_format();
+                let dept_alias = |d: &Rc<RefCell<Department>>| self.data.dept_alias_of(d).cloned().unwrap_or_default();
+
+                println!("{}:", person.name());
+                if !person.is_terminated() {
+                    let primary = person.department();
+                    println!("    {:>3}%  \"{}\" (primary)", person.allocation(&primary), dept_alias(&primary));
+                    for membership in person.current_memberships() {
+                        println!("    {:>3}%  \"{}\" since {}", membership.allocation(), dept_alias(&membership.department()),
+                            date_format.format(&membership.start()));
+                    }
+                }
+                for membership in person.memberships() {
+                    if let Some(end) = membership.end() {
+                        println!("    {:>3}%  \"{}\" {} to {}", membership.allocation(), dept_alias(&membership.department()),
+                            date_format.format(&membership.start()), date_format.format(&end));
+                    }
+                }
+                Ok(())
+            },
+            (Some(ref action), Some(dept_alias)) if action == "join" => {
+                let allocation = match args.next().map(|p| u32::from_str(p.trim_end_matches('%'))) {
+                    Some(Ok(allocation)) => allocation,
+                    Some(Err(_)) => {
+                        println!("The allocation must be a whole percentage, such as 20.");
+                        return Ok(());
+                    },
+                    None => {
+                        Self::short_help();
+                        return Ok(());
+                    },
+                };
+                let date = match args.next() {
+                    Some(date) => match self.date_arg(date) {
+                        Some(date) => Some(date),
+                        None => return Ok(()),
+                    },
+                    None => None,
+                };
+
+                self.data.add_membership(alias, dept_alias, allocation, date)
+                    .map(|_| println!("Membership updated."))
+            },
+            (Some(ref action), Some(dept_alias)) if action == "leave" => {
+                let date = match args.next() {
+                    Some(date) => match self.date_arg(date) {
+                        Some(date) => Some(date),
+                        None => return Ok(()),
+                    },
+                    None => None,
+                };
+
+                self.data.end_membership(alias, dept_alias, date)
+                    .map(|_| println!("Membership ended."))
+            },
+            _ => {
+                Self::short_help();
+                Ok(())
+            },
+        };
+
+        if let Err(e) = result {
+            eprintln!("{}", e);
+        }
+
+        Ok(())
+    }
+
+    fn location(&mut self, args: std::str::SplitWhitespace) -> Result<()> {
+        self.organize(Grouping::Location, args)
+    }
+
+    fn cost_center(&mut self, args: std::str::SplitWhitespace) -> Result<()> {
+        self.organize(Grouping::CostCenter, args)
+    }
+
+    /// LOCATION and COSTCENTER, which work the same way.
+    fn organize(&mut self, grouping: Grouping, mut args: std::str::SplitWhitespace) -> Result<()> {
+        let result = match (args.next().map(|a| a.to_lowercase()), args.next()) {
+            (None, _) => {
+                let units: Vec<String> = match grouping {
+                    Grouping::Location => self.data.locations().iter().map(|l| l.to_string()).collect(),
+                    Grouping::CostCenter => self.data.cost_centers().iter().map(|c| c.to_string()).collect(),
+                };
+
+                if units.is_empty() {
+                    println!("No {}s defined.", grouping);
+                }
+
+                for unit in units {
+                    println!("{}", unit);
+                }
+                Ok(())
+            },
+            (Some(ref action), Some(code)) if action == "add" => {
+                let name = args.collect::<Vec<&str>>().join(" ");
+
+                if name.is_empty() {
+                    Self::short_help();
+                    return Ok(());
+                }
+
+                match grouping {
+                    Grouping::Location => self.data.add_location(code, &name),
+                    Grouping::CostCenter => self.data.add_cost_center(code, &name),
+                }.map(|_| println!("Added {} {}.", grouping, code))
+            },
+            (Some(ref action), Some(code)) if action == "remove" => {
+                match grouping {
+                    Grouping::Location => self.data.remove_location(code),
+                    Grouping::CostCenter => self.data.remove_cost_center(code),
+                }.map(|_| println!("Removed {} {}.", grouping, code))
+            },
+            (Some(ref action), Some(alias)) if action == "assign" => {
+                let code = match args.next() {
+                    Some(code) if code.eq_ignore_ascii_case("none") => None,
+                    Some(code) => Some(code),
+                    None => {
+                        Self::short_help();
+                        return Ok(());
+                    },
+                };
+
+                match grouping {
+                    Grouping::Location => self.data.assign_location(alias, code),
+                    Grouping::CostCenter => self.data.assign_cost_center(alias, code),
+                }.map(|_| println!("Updated {} of \"{}\".", grouping, alias))
+            },
+            _ => {
+                Self::short_help();
+                Ok(())
+            },
+        };
+
+        if let Err(e) = result {
+            eprintln!("{}", e);
+        }
+
+        Ok(())
+    }
+
+    fn pay(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        let alias = match args.next() {
+            Some(alias) => alias,
+            None => {
+                Self::short_help();
+                return Ok(());
+            },
+        };
+
+        let person = match self.data.personnel().get(alias) {
+            Some(person) => Rc::clone(person),
+            None => {
+                println!("No employee known as \"{}\".", alias);
+                return Ok(());
+            },
+        };
+
+        match args.next() {
+            None => {
+                let person = person.borrow();
+
+                match person.compensation() {
+                    Some(pay) => println!("{}: {}", person.name(), pay),
+                    None => println!("{}: no pay recorded", person.name()),
+                };
+
+                for entry in person.compensation_history() {
+                    println!("    {}  {}", self.data.date_format().format(&entry.date), entry);
+                }
+            },
+            Some(word) if word.eq_ignore_ascii_case("change") => {
+                let date = match args.next() {
+                    Some(date) => match self.date_arg(date) {
+                        Some(date) => Some(date),
+                        None => return Ok(()),
+                    },
+                    None => None,
+                };
+
+                let current = person.borrow().compensation().cloned();
+                let current_currency = current.as_ref().map(|c| c.pay.currency.clone());
+                let current_basis = current.as_ref().map_or(PayBasis::Annual, |c| c.basis);
+
+                let amount = get_string("amount", &mut self.io);
+
+                let prompt = format!("currency [{}]", current_currency.clone().unwrap_or_default());
+                let currency = match optional_string(&prompt, &mut self.io).or(current_currency) {
+                    Some(currency) => currency,
+                    None => {
+                        println!("A currency is required.");
+                        return Ok(());
+                    },
+                };
+
+                let pay = match Money::parse(&amount, &currency) {
+                    Ok(pay) => pay,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        return Ok(());
+                    },
+                };
+
+                let prompt = format!("basis, ANNUAL or HOURLY [{}]", current_basis);
+                let basis = match optional_string(&prompt, &mut self.io).map(|text| PayBasis::from_str(&text)) {
+                    Some(Ok(basis)) => basis,
+                    Some(Err(_)) => {
+                        println!("The basis must be ANNUAL or HOURLY.");
+                        return Ok(());
+                    },
+                    None => current_basis,
+                };
+
+                let reason = get_string("reason", &mut self.io);
+
+                match self.data.change_compensation(alias, pay, basis, &reason, date) {
+                    Ok(()) => println!("Pay updated."),
+                    Err(e) => eprintln!("{}", e),
+                };
+            },
+            Some(_) => Self::short_help(),
+        };
+
+        Ok(())
+    }
+
+    fn budget(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        let alias = match args.next() {
+            Some(alias) => alias,
+            None => {
+                Self::short_help();
+                return Ok(());
+            },
+        };
+
+        let department = match self.data.departments().get(alias) {
+            Some(department) => Rc::clone(department),
+            None => return Err(TextInterfaceError::NoDepartment),
+        };
 
-        TextInterface {
-            io: TextIO {
-                stdin: io::stdin(),
-                stdout: io::stdout(),
-                stderr: io::stderr(),
+        let budget = match (args.next(), args.next()) {
+            (None, _) => {
+                let department = department.borrow();
+                match department.budget() {
+                    Some(budget) => println!("{}: {}", department.name(), budget),
+                    None => println!("{}: no budget set", department.name()),
+                };
+                return Ok(());
             },
-            data: ProgramData::init(),
-            commands,
-        }
-    }
+            (Some(word), None) if word.eq_ignore_ascii_case("none") => None,
+            (Some(amount), Some(currency)) => match Money::parse(amount, currency) {
+                Ok(budget) => Some(budget),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return Ok(());
+                },
+            },
+            _ => {
+                Self::short_help();
+                return Ok(());
+            },
+        };
 
-    /// NYI
-    pub fn run(&mut self) -> Result<()> {
-        let mut io_buff = String::new();
-        loop {
-            self.io.stdin.read_line(&mut io_buff)?;
+        match self.data.set_budget(alias, budget) {
+            Ok(()) => println!("Budget updated."),
+            Err(e) => eprintln!("{}", e),
+        };
 
-            let mut command = io_buff.split_whitespace();
+        Ok(())
+    }
 
-            match command.next() {
-                Some(word) => {
-                    let comm = word.to_lowercase();
+    fn role(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        match args.next().map(|a| a.to_lowercase()) {
+            None => println!("Current role: {}", self.role),
+            Some(ref role) if role == "standard" => {
+                self.role = Role::Standard;
+                println!("Role is now standard.");
+            },
+            Some(ref role) if role == "hr" => {
+                if self.data.hr_passphrase().is_some() {
+                    let passphrase = get_string("HR passphrase", &mut self.io);
 
-                    let mut op: Option<fn(&mut TextInterface, std::str::SplitWhitespace) -> Result<()>> = None;
-                    for item in &self.commands {
-                        if item.keyword == comm {
-                            op = Some(item.operation);
-                        }
+                    if !self.data.check_hr_passphrase(&passphrase) {
+                        println!("Incorrect passphrase.");
+                        return Ok(());
                     }
+                } else {
+                    // The HR role is never left open: the first to take it chooses the passphrase.
+                    println!("No HR passphrase has been set.  Choose one; it will be asked for from now on.");
+                    match optional_string("new HR passphrase", &mut self.io) {
+                        Some(passphrase) => self.data.set_hr_passphrase(Some(&passphrase)),
+                        None => {
+                            println!("The HR role needs a passphrase.");
+                            return Ok(());
+                        },
+                    };
+                }
 
-                    if op.is_some() {
-                        (op.unwrap())(self, command)?;
-                    } else {
-                        println!("Type HELP for a list of commands.");
-                    }
-                },
-                None => println!("Type HELP for a list of commands."),
-            };
+                self.role = Role::Hr;
+                println!("Role is now HR.");
+            },
+            Some(_) => Self::short_help(),
+        };
 
-            io_buff.clear();
-        }
+        Ok(())
     }
 
+    fn alias(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        let first = match args.next() {
+            Some(word) => word,
+            None => {
+                Self::short_help();
+                return Ok(());
+            },
+        };
+
+        let result = match (first.to_lowercase().as_str(), args.next(), args.next()) {
+            ("add", Some(existing), Some(new)) => self.data.add_alias(existing, new)
+                .map(|_| println!("\"{}\" is now also known as \"{}\".", existing, new)),
+            ("rename", Some(old), Some(new)) => self.data.rename_alias(old, new)
+                .map(|_| println!("Renamed \"{}\" to \"{}\".", old, new)),
+            ("remove", Some(alias), None) => self.data.remove_alias(alias)
+                .map(|_| println!("Removed alias \"{}\".", alias)),
+            (_, None, None) => {
+                match self.data.alias_entry(first) {
+                    Some(entry) => {
+                        let aliases: Vec<String> = entry.aliases().iter()
+                            .map(|a| format!("\"{}\"", a))
+                            .collect();
+                        println!("{}", aliases.join(", "));
+                    },
+                    None => println!("No person or department is known as \"{}\".", first),
+                };
+                Ok(())
+            },
+            _ => {
+                Self::short_help();
+                Ok(())
+            },
+        };
+
+        if let Err(e) = result {
+            eprintln!("{}", e);
+        }
 
+        Ok(())
+    }
 
-    fn help(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
-        match args.next() {
+    fn diff(&mut self, args: std::str::SplitWhitespace) -> Result<()> {
+        let mut args = args.peekable();
+        let is_format = |word: &str| ["text", "json"].contains(&word.to_lowercase().as_str());
+
+        let old = match args.next() {
+            Some(old) => old,
             None => {
-                println!("Type HELP [COMMAND] for more information.");
-                println!();
+                Self::short_help();
+                return Ok(());
+            },
+        };
+        let new = match args.peek() {
+            Some(word) if !is_format(word) => args.next(),
+            _ => None,
+        };
 
-                for comm in &self.commands {
-                    println!("{}:  {}", comm.keyword, comm.short_desc);
-                }
+        let diff = match new {
+            Some(new) => RosterDiff::between_files(old, new),
+            None => Snapshot::load(old)
+                .map(|saved| RosterDiff::between_snapshots(&saved, &Snapshot::take(&self.data))),
+        };
+
+        let diff = match diff {
+            Ok(diff) => diff,
+            Err(e) => {
+                eprintln!("Could not compare: {}", e);
+                return Ok(());
             },
-            Some(arg) => {
-                for comm in &self.commands {
-                    if comm.keyword == arg {
-                        self.io.stdout.write(comm.long_desc.as_bytes())?;
-                        self.io.stdout.write(&[b'\n'])?;
-                        self.io.stdout.flush()?;
+        };
+
+        let output = match args.next().map(|f| f.to_lowercase()) {
+            Some(ref format) if format == "json" => format!("{:#}\n", diff.to_json()),
+            Some(ref format) if format == "text" => diff.to_text(self.data.date_format()),
+            None => diff.to_text(self.data.date_format()),
+            Some(_) => return Err(TextInterfaceError::InvalidArgument),
+        };
+
+        if diff.is_empty() && args.peek().is_none() {
+            println!("No differences.");
+            return Ok(());
+        }
+
+        write_output(&output, args.next())
+    }
+
+    fn merge(&mut self, args: std::str::SplitWhitespace) -> Result<()> {
+        let files: Vec<&str> = args.collect();
+
+        let (ours, merge) = match files.as_slice() {
+            [base, theirs] => {
+                let ours = Snapshot::take(&self.data);
+                let merge = Snapshot::load(base).and_then(|base| {
+                    Ok(RosterMerge::new(&base, &ours, &Snapshot::load(theirs)?))
+                });
+                (Ok(ours), merge)
+            },
+            [base, ours, theirs] => {
+                if self.unsaved_changes() {
+                    println!("Merging into {} replaces the data in memory, which has changes not saved to {}.",
+                        ours, self.data_file);
+                    if !get_string("YES to replace it, or anything else to stop", &mut self.io).eq_ignore_ascii_case("yes") {
+                        println!("Merge cancelled.");
                         return Ok(());
                     }
                 }
-                println!("Command not found: {}", arg);
+                (Snapshot::load(ours), RosterMerge::files(base, ours, theirs))
+            },
+            _ => {
+                Self::short_help();
+                return Ok(());
             },
         };
-        Ok(())
-    }
 
-    fn quit(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
-        println!("\nSo long, sucker!");
-        process::exit(0);
-    }
+        let (ours, mut merge) = match (ours, merge) {
+            (Ok(ours), Ok(merge)) => (ours, merge),
+            (Err(e), _) | (_, Err(e)) => {
+                eprintln!("Could not merge: {}", e);
+                return Ok(());
+            },
+        };
 
-    fn list(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
-        match args.next() {
-            Some(what) => {
-                let what = what.to_lowercase();
+        for note in merge.renumbered() {
+            println!("Added on both sides: {}", note);
+        }
 
-                if what == "employees" {
-                    if let Err(e) = self.list_employees(args) {
-                        eprintln!("Error printing list: {}", e);
-                    }
+        if self.role != Role::Hr && merge.conflicts().iter().any(|c| c.involves_pay()) {
+            for conflict in merge.conflicts().iter().filter(|c| c.involves_pay()) {
+                println!("Pay or budget changed on both sides: {}", conflict.label());
+            }
+            println!("These can only be merged in the HR role.  See ROLE.");
+            return Ok(());
+        }
+
+        let count = merge.conflicts().len();
+        for i in 0..count {
+            let conflict = &merge.conflicts()[i];
+            println!("\nConflict {} of {}: {}", i + 1, count, conflict);
+            for side in [Side::Base, Side::Ours, Side::Theirs].iter() {
+                println!("  {:<7} {}", format!("{}:", side), conflict.describe(*side));
+            }
+
+            let side = loop {
+                match get_string("version to keep (OURS, THEIRS, BASE or CANCEL)", &mut self.io).to_lowercase().as_str() {
+                    "ours" => break Side::Ours,
+                    "theirs" => break Side::Theirs,
+                    "base" => break Side::Base,
+                    // Nothing at all is also read at the end of input.
+                    "cancel" | "" => {
+                        println!("Merge cancelled.");
+                        return Ok(());
+                    },
+                    _ => println!("Please enter OURS, THEIRS, BASE or CANCEL."),
+                };
+            };
+
+            merge.resolve(i, side).expect("conflict index is in range");
+        }
+
+        match merge.restore() {
+            Ok(data) => {
+                self.data = data;
+                let changes = RosterDiff::between_snapshots(&ours, &Snapshot::take(&self.data));
+                if changes.is_empty() {
+                    println!("Merged; nothing new from THEIRS.");
+                } else {
+                    print!("Merged, with these changes:\n{}", changes.to_text(self.data.date_format()));
                 }
+                self.apply_due_transfers();
             },
-            None => Self::short_help(),
+            Err(e) => eprintln!("Could not merge: {}", e),
         };
 
         Ok(())
     }
 
-    fn list_employees(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
-        match args.next() {
-            Some(whatnow) => {
+    /// Whether the data in memory differs from the data file, or from an empty roster if the
+    /// file can't be read.
+    fn unsaved_changes(&self) -> bool {
+        let current = Snapshot::take(&self.data);
 
-            },
-            None => {
-                let all_sorted = self.sort_employees();
+        match Snapshot::load(&self.data_file) {
+            Ok(saved) => saved != current,
+            Err(_) => current != Snapshot::take(&ProgramData::init()),
+        }
+    }
 
-                for (alias, name) in all_sorted {
-                    println!("\"{}\": {}", alias, name);
-                }
+    fn verify(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        let repair = match args.next().map(|a| a.to_lowercase()) {
+            Some(ref word) if word == "repair" => true,
+            None => false,
+            Some(_) => {
+                Self::short_help();
+                return Ok(());
             },
+        };
+
+        let violations = if repair { self.data.repair() } else { self.data.verify() };
+
+        if violations.is_empty() {
+            println!("No problems found.");
+            return Ok(());
+        }
+
+        for violation in &violations {
+            println!("{}", violation);
         }
+
+        if repair {
+            let left = self.data.verify().len();
+            println!("Repaired {} problem(s); {} left.", violations.len() - left.min(violations.len()), left);
+        } else {
+            println!("{} problem(s) found.  Use VERIFY REPAIR to fix them.", violations.len());
+        }
+
         Ok(())
     }
 
-    fn sort_employees(&self) -> Vec<(String, Name)> {
-        let mut list: Vec<(String, Name)> = Vec::new();
+    fn save(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        let path = args.next().map(String::from).unwrap_or_else(|| self.data_file.clone());
+        let snapshot = Snapshot::take(&self.data);
 
-        for employee in self.data.employee_list() {
-            let name = employee.pointer().borrow().name().clone();
+        // Outside the HR role, pay and budgets are only written back to the file they came from.
+        // A copy saved anywhere else leaves them out.
+        if self.role != Role::Hr && snapshot.has_pay() && path != self.data_file {
+            match snapshot.without_pay().save(&path) {
+                Ok(()) => println!("Saved a copy to {} without pay or budgets, which are only saved in the HR role.", path),
+                Err(e) => eprintln!("Could not save {}: {}", path, e),
+            };
+            return Ok(());
+        }
 
-            let search_result = list.binary_search_by(|(_, entry)| (*entry).cmp(&name));
+        match snapshot.save(&path) {
+            Ok(()) => {
+                println!("Saved to {}", path);
+                self.data_file = path;
+            },
+            Err(e) => eprintln!("Could not save {}: {}", path, e),
+        };
 
-            if let Err(index) = search_result {
-                list.insert(index, (employee.alias().clone(), name));
-            }
-        }
+        Ok(())
+    }
+
+    fn load(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        let path = args.next().map(String::from).unwrap_or_else(|| self.data_file.clone());
+
+        match ProgramData::load(&path) {
+            Ok(data) => {
+                self.data = data;
+                self.data_file = path;
+                println!("Loaded {} departments and {} employees from {}",
+                    self.data.dept_list().len(), self.data.employee_list().len(), self.data_file);
+                self.apply_due_transfers();
+            },
+            Err(e) => eprintln!("Could not load {}: {}", path, e),
+        };
+
+        Ok(())
+    }
 
-        list
+    /// Every employee as (alias, employee number, name), sorted by name.
+    fn sort_employees(&self) -> Vec<(String, String, Name)> {
+        self.data.employees_by_name().into_iter()
+            .map(|employee| {
+                let person = employee.pointer();
+                let person = person.borrow();
+                (employee.alias().clone(), self.data.employee_id(&person), person.name().clone())
+            })
+            .collect()
     }
 
+    // Named for the NEW command, so not a constructor.
+    #[allow(clippy::new_ret_no_self, clippy::wrong_self_convention)]
     fn new(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
         match args.next() {
             Some(thing) => {
                 let thing = thing.to_lowercase();
 
                 if thing == "employee" {
-                    if self.data.dept_list().len() == 0 {
+                    if self.data.dept_list().next().is_none() {
                         println!("Cannot add employee: No departments found.");
                     } else {
                         if let Err(e) = self.add_employee() {
@@ -226,7 +2370,7 @@ Ex:  NEW EMPLOYEE\n     NEW DEPARTMENT"),
     }
 
     fn add_employee(&mut self) -> Result<()> {
-        if self.data.dept_list().len() == 0 {
+        if self.data.dept_list().next().is_none() {
             return Err(TextInterfaceError::NoDepartment);
         }
 
@@ -236,49 +2380,88 @@ Ex:  NEW EMPLOYEE\n     NEW DEPARTMENT"),
         let mut name_mid: Option<String> = None;
         let mut doh: Option<NaiveDate> = None;
         let mut department: Option<Rc<RefCell<Department>>> = None;
+        let mut name_prefix: Option<String> = None;
+        let mut name_suffix: Option<String> = None;
+        let mut name_preferred: Option<String> = None;
+        let mut name_order = NameOrder::GivenFirst;
+        let mut title: Option<String> = None;
+        let mut level: Option<String> = None;
+        let mut fte = 1.0;
+        let mut contact = Contact::default();
+        let mut employment_type = EmploymentType::default();
+        let mut custom: Vec<(FieldDef, Option<FieldValue>)> = self.data.schema().fields().iter()
+            .map(|def| (def.clone(), None))
+            .collect();
 
         let none = String::from("None");
         let today = Local::today().naive_local();
 
-        let mut io_buffer = String::new();
-
         loop {
             println!("1: Alias*:       {}", match &alias {
-                Some(name) => &name,
+                Some(name) => name,
                 None => &none,
             });
             println!("2: First Name*:  {}", match &name_first {
-                Some(name) => &name,
+                Some(name) => name,
                 None => &none,
             });
-            println!("3: Middle Name:  {}", match &name_mid {
-                Some(name) => &name,
+            println!("3: Middle Names: {}", match &name_mid {
+                Some(name) => name,
                 None => &none,
             });
             println!("4: Last Name*:   {}", match &name_last {
-                Some(name) => &name,
+                Some(name) => name,
                 None => &none,
             });
             println!("5: Date of Hire: {}", match &doh {
-                Some(date) => format_date_us(date),
-                None => format_date_us(&today),
+                Some(date) => self.data.date_format().format(date),
+                None => self.data.date_format().format(&today),
             });
             println!("6: Department*:  {}", match &department {
                 Some(dept) => dept.borrow().name().clone(),
-                None => (&none).clone(),
+                None => none.clone(),
+            });
+            println!("7: Prefix:       {}", match &name_prefix {
+                Some(prefix) => prefix,
+                None => &none,
+            });
+            println!("8: Suffix:       {}", match &name_suffix {
+                Some(suffix) => suffix,
+                None => &none,
+            });
+            println!("9: Preferred:    {}", match &name_preferred {
+                Some(name) => name,
+                None => &none,
+            });
+            println!("10: Name Order:  {}", match name_order {
+                NameOrder::GivenFirst => "Given name first",
+                NameOrder::FamilyFirst => "Family name first",
+            });
+            println!("11: Job Title:   {}", match &title {
+                Some(title) => title,
+                None => &none,
+            });
+            println!("12: Level:       {}", match &level {
+                Some(level) => level,
+                None => &none,
             });
+            println!("13: FTE:         {:.2}", fte);
+            println!("14: Email:       {}", contact.email().unwrap_or(&none));
+            println!("15: Phone:       {}", contact.phone().unwrap_or(&none));
+            println!("16: Office:      {}", contact.office().unwrap_or(&none));
+            println!("17: Type:        {}", employment_type);
+
+            for (i, (def, value)) in custom.iter().enumerate() {
+                println!("{}: {:13}{}", CUSTOM_LINE + i, format!("{}:", def.name), match value {
+                    Some(value) => value.format(self.data.date_format()),
+                    None => none.clone(),
+                });
+            }
 
             println!();
 
             println!("Enter a line number to modify, or \"commit\" to finish.");
-            self.io.stdout.write(b"?> ")?;
-            self.io.stdout.flush()?;
-
-            io_buffer.clear();
-
-            self.io.stdin.read_line(&mut io_buffer)?;
-
-            let mut option = 0u32;
+            let io_buffer = self.io.editor.read_line("?> ")?.unwrap_or_default();
 
 //            let mut get_string = |prnt: &str| {
 //                self.io.stdout.write(format!("Enter {}: ", prnt).as_bytes());
@@ -309,12 +2492,45 @@ Ex:  NEW EMPLOYEE\n     NEW DEPARTMENT"),
                         person.date_of_hire(today);
                     }
 
-                    if let Some(name) = &name_mid {
-                        person.middle_name(name);
+                    if let Some(names) = &name_mid {
+                        for name in names.split_whitespace() {
+                            person.middle_name(name);
+                        }
+                    }
+
+                    if let Some(prefix) = &name_prefix {
+                        person.prefix(prefix);
+                    }
+
+                    if let Some(suffix) = &name_suffix {
+                        person.suffix(suffix);
+                    }
+
+                    if let Some(name) = &name_preferred {
+                        person.preferred_name(name);
+                    }
+
+                    person.name_order(name_order);
+
+                    if let Some(title) = &title {
+                        person.position(Position {
+                            title: title.clone(),
+                            level: level.clone(),
+                            fte,
+                        });
+                    }
+
+                    person.contact(contact.clone())
+                        .employment_type(employment_type);
+
+                    for (def, value) in &custom {
+                        if let Some(value) = value {
+                            person.custom_field(&def.name, value.clone());
+                        }
                     }
 
                     let person = person.build();
-                    let mut person_final: Person;
+                    let person_final: Person;
                     if let Ok(val) = person {
                         person_final = val;
                     } else {
@@ -323,15 +2539,23 @@ Ex:  NEW EMPLOYEE\n     NEW DEPARTMENT"),
 
                     let p_alias = alias.clone().unwrap_or(person_final.first_name().clone());
 
-                    if self.data.add_person(&p_alias, person_final).is_err() {
-                        eprintln!("Error on add.  Review fields and try again.");
+                    let namesakes: Vec<String> = self.data.namesakes(person_final.name()).iter()
+                        .map(|entry| format!("\"{}\"", entry.alias()))
+                        .collect();
+
+                    if let Err(e) = self.data.add_person(&p_alias, person_final) {
+                        eprintln!("Error on add: {}.  Review fields and try again.", e);
                         continue;
                     }
 
+                    if !namesakes.is_empty() {
+                        println!("Warning: employee \"{}\" has the same name as {}.", p_alias, namesakes.join(", "));
+                    }
+
                     return Ok(());
                 }
             } else {
-                option = match u32::from_str(&io_buffer.trim()) {
+                let option = match u32::from_str(io_buffer.trim()) {
                     Err(_) => {
                         println!("Invalid input");
                         continue;
@@ -347,8 +2571,8 @@ Ex:  NEW EMPLOYEE\n     NEW DEPARTMENT"),
                         name_first = Some(get_string("first name", &mut self.io));
                     },
                     3 => {
-                        let entry = get_string("middle name", &mut self.io);
-                        if entry.len() > 0 {
+                        let entry = get_string("middle names, separated by spaces", &mut self.io);
+                        if !entry.is_empty() {
                             name_mid = Some(entry);
                         } else {
                             name_mid = None;
@@ -358,13 +2582,14 @@ Ex:  NEW EMPLOYEE\n     NEW DEPARTMENT"),
                         name_last = Some(get_string("last name", &mut self.io));
                     },
                     5 => {
-                        let doh_string = get_string("date of hire(MM/DD/YYYY)", &mut self.io);
-                        if doh_string.len() == 0 {
+                        let prompt = format!("date of hire ({})", self.data.date_format().hint());
+                        let doh_string = get_string(&prompt, &mut self.io);
+                        if doh_string.is_empty() {
                             doh = None;
                             continue;
                         }
 
-                        match parse_date_us(&doh_string) {
+                        match self.parse_date(&doh_string) {
                             Ok(date) => doh = Some(date),
                             Err(_) => {
                                 println!("Invalid date format");
@@ -375,13 +2600,71 @@ Ex:  NEW EMPLOYEE\n     NEW DEPARTMENT"),
                     6 => {
                         let dept_string = get_string("initial department", &mut self.io);
 
-                        for value in self.data.dept_list() {
-                            if value.alias() == &dept_string {
-                                department = Some(value.clone_pointer());
-                                break;
-                            }
+                        if let Some(dept) = self.data.departments().get(&dept_string) {
+                            department = Some(Rc::clone(dept));
+                        }
+                    },
+                    7 => {
+                        name_prefix = optional_string("prefix (e.g. Dr.)", &mut self.io);
+                    },
+                    8 => {
+                        name_suffix = optional_string("suffix (e.g. Jr., III)", &mut self.io);
+                    },
+                    9 => {
+                        name_preferred = optional_string("preferred name", &mut self.io);
+                    },
+                    10 => {
+                        name_order = match name_order {
+                            NameOrder::GivenFirst => NameOrder::FamilyFirst,
+                            NameOrder::FamilyFirst => NameOrder::GivenFirst,
+                        };
+                    },
+                    11 => {
+                        title = optional_string("job title", &mut self.io);
+                    },
+                    12 => {
+                        level = optional_string("level or grade", &mut self.io);
+                    },
+                    13 => {
+                        match f64::from_str(&get_string("FTE (e.g. 0.5 for half time)", &mut self.io)) {
+                            Ok(value) if Position::valid_fte(value) => fte = value,
+                            _ => println!("FTE must be more than 0 and at most 1"),
+                        };
+                    },
+                    14 => {
+                        if let Err(e) = contact.set_email(optional_string("email", &mut self.io).as_deref()) {
+                            println!("{}", e);
+                        }
+                    },
+                    15 => {
+                        if let Err(e) = contact.set_phone(optional_string("phone", &mut self.io).as_deref()) {
+                            println!("{}", e);
                         }
                     },
+                    16 => {
+                        contact.set_office(optional_string("office", &mut self.io).as_deref());
+                    },
+                    17 => {
+                        let next = EmploymentType::ALL.iter()
+                            .position(|t| *t == employment_type)
+                            .map_or(0, |i| (i + 1) % EmploymentType::ALL.len());
+                        employment_type = EmploymentType::ALL[next];
+                    },
+                    n if n as usize >= CUSTOM_LINE && (n as usize) < CUSTOM_LINE + custom.len() => {
+                        let (def, value) = &mut custom[n as usize - CUSTOM_LINE];
+                        let prompt = match def.kind {
+                            FieldType::Date => format!("{} ({})", def.name, self.data.date_format().hint()),
+                            _ => format!("{} ({})", def.name, def.kind),
+                        };
+
+                        match optional_string(&prompt, &mut self.io) {
+                            Some(text) => match def.parse_value(&text, self.data.date_format()) {
+                                Ok(parsed) => *value = Some(parsed),
+                                Err(e) => println!("{}", e),
+                            },
+                            None => *value = None,
+                        };
+                    },
                     _ => {
                         println!("Invalid input");
                         continue;
@@ -389,33 +2672,6 @@ Ex:  NEW EMPLOYEE\n     NEW DEPARTMENT"),
                 };
             }
         }
-        Ok(())
-    }
-
-    fn select_department(&mut self) -> Rc<RefCell<Department>> {
-        for (index, department) in self.data.dept_list().iter().enumerate() {
-            println!("{}: {}", index + 1, department.alias());
-        }
-
-        let mut choice = String::new();
-        loop {
-            self.io.stdout.write(b"Pick a department: ")
-                .expect("IO ERROR");
-            self.io.stdout.flush()
-                .expect("IO ERROR");
-
-            self.io.stdin.read_line(&mut choice)
-                .expect("IO ERROR");
-
-            let dept = self.data.departments().get(&choice);
-
-            if let None = dept {
-                choice.clear();
-                continue;
-            } else {
-                return Rc::clone(dept.unwrap());
-            }
-        }
     }
 
     fn add_department(&mut self) -> Result<()> {
@@ -424,11 +2680,7 @@ Ex:  NEW EMPLOYEE\n     NEW DEPARTMENT"),
 
         let none = String::from("none");
 
-        let mut io_buffer = String::new();
-
         loop {
-            io_buffer.clear();
-
             println!("1: Unique identifier: {}", match &department_alias {
                 Some(id) => id,
                 None => &none,
@@ -441,9 +2693,7 @@ Ex:  NEW EMPLOYEE\n     NEW DEPARTMENT"),
             println!();
 
             println!("Enter a line number to modify, or \"commit\" to finish.");
-            self.io.stdout.write(b"?> ")?;
-            self.io.stdout.flush()?;
-            self.io.stdin.read_line(&mut io_buffer)?;
+            let io_buffer = self.io.editor.read_line("?> ")?.unwrap_or_default();
 
             let input = io_buffer.trim();
 
@@ -486,6 +2736,80 @@ Ex:  NEW EMPLOYEE\n     NEW DEPARTMENT"),
         Ok(())
     }
 
+    /// Read a date typed by the user, in the configured format, ISO 8601, or relative to today.
+    fn parse_date(&self, text: &str) -> Result<NaiveDate> {
+        self.data.date_format().parse(text).map_err(|_| TextInterfaceError::InvalidDate)
+    }
+
+    /// Read a date argument of a command, reporting it if it isn't a date.
+    fn date_arg(&self, text: &str) -> Option<NaiveDate> {
+        let date = self.parse_date(text).ok();
+        if date.is_none() {
+            eprintln!("\"{}\" is not a date.  Dates are written {}.", text, self.data.date_format().hint());
+        }
+        date
+    }
+
+    fn set(&mut self, mut args: std::str::SplitWhitespace) -> Result<()> {
+        match args.next().map(|a| a.to_lowercase()) {
+            None => {
+                println!("dateformat:  {} ({})", self.data.date_format(), self.data.date_format().hint());
+                println!("collation:   {}", self.data.collation());
+                println!("idformat:    {} (next: {})", self.data.id_format(),
+                    self.data.id_format().format(self.data.next_employee_id()));
+            },
+            Some(ref setting) if setting == "hrpassphrase" => {
+                if self.role != Role::Hr {
+                    println!("The HR passphrase can only be changed in the HR role.  See ROLE.");
+                    return Ok(());
+                }
+
+                match optional_string("new HR passphrase", &mut self.io) {
+                    Some(passphrase) => {
+                        self.data.set_hr_passphrase(Some(&passphrase));
+                        println!("HR passphrase set.");
+                    },
+                    None => println!("The HR passphrase can't be removed; it is unchanged."),
+                };
+            },
+            Some(ref setting) if setting == "idformat" => {
+                match args.next().map(IdFormat::from_str) {
+                    Some(Ok(format)) => {
+                        println!("Employee numbers will be shown as {}.", format.format(self.data.next_employee_id()));
+                        self.data.set_id_format(format);
+                    },
+                    _ => eprintln!("Employee number format must be PLAIN or a pattern ending in #, e.g. EMP-######"),
+                };
+            },
+            Some(ref setting) if setting == "collation" => {
+                let collation = match Collation::from_str(&args.collect::<Vec<&str>>().join(" ")) {
+                    Ok(collation) => collation,
+                    Err(option) => {
+                        eprintln!("Unknown collation option: {}", option);
+                        return Ok(());
+                    },
+                };
+
+                println!("Names will be sorted by {}.", collation);
+                self.data.set_collation(collation);
+            },
+            Some(ref setting) if setting == "dateformat" => {
+                let value = args.collect::<Vec<&str>>().join(" ");
+
+                match DateFormat::from_str(&value) {
+                    Ok(format) => {
+                        println!("Dates will be shown as {}.", format.hint());
+                        self.data.set_date_format(format);
+                    },
+                    Err(e) => eprintln!("{}", e),
+                };
+            },
+            Some(_) => Self::short_help(),
+        };
+
+        Ok(())
+    }
+
     fn short_help() {
         println!("Type HELP [COMMAND] for more information.");
         println!();
@@ -493,47 +2817,75 @@ Ex:  NEW EMPLOYEE\n     NEW DEPARTMENT"),
 }
 
 fn get_string(prnt: &str, io: &mut TextIO) -> String {
-    let mut io_buffer = String::new();
-
-    io.stdout.write(format!("Enter {}: ", prnt).as_bytes()).expect("IO ERROR");
-    io.stdout.flush().expect("IO ERROR");
-    io.stdin.read_line(&mut io_buffer).expect("IO ERROR");
+    let io_buffer = io.editor.read_line(&format!("Enter {}: ", prnt)).expect("IO ERROR").unwrap_or_default();
 
     String::from(io_buffer.trim())
 }
 
-fn format_date_us(date: &NaiveDate) -> String {
-    let date_format = date.format("%m/%d/%Y");
-    format!("{}", date_format)
+/// Like get_string(), but an empty entry clears the value.
+fn optional_string(prnt: &str, io: &mut TextIO) -> Option<String> {
+    let entry = get_string(prnt, io);
+
+    if entry.is_empty() {
+        None
+    } else {
+        Some(entry)
+    }
 }
 
-fn parse_date_us(date_string: &str) -> Result<NaiveDate> {
-    const ERR: TextInterfaceError = TextInterfaceError::InvalidDate;
+/// Lay out rows of text in left-aligned columns under a header line.
+fn format_table(headers: &[String], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+
+    for row in rows {
+        for (i, cell) in row.iter().enumerate() {
+            if i < widths.len() {
+                widths[i] = widths[i].max(cell.chars().count());
+            }
+        }
+    }
 
-    let values: Vec<&str> = date_string.split('/').collect();
+    let line = |cells: &[String]| -> String {
+        let padded: Vec<String> = cells.iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = *width))
+            .collect();
+        format!("{}\n", padded.join("  ").trim_end())
+    };
 
-    //let err_value = TextInterfaceError::InvalidDate;
+    let mut table = line(headers);
+    let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+    table.push_str(&line(&rule));
 
-    if values.len() != 3 {
-        return Err(ERR);
+    for row in rows {
+        table.push_str(&line(row));
     }
 
-    let month = u32::from_str(values[0]).map_err(|_| ERR)?;
-    let day = u32::from_str(values[1]).map_err(|_| ERR)?;
-    let year = i32::from_str(values[2]).map_err(|_| ERR)?;
+    table
+}
 
-    NaiveDate::from_ymd_opt(year, month, day).ok_or(ERR)
+/// Print text to stdout, or write it to a file if a path was given.
+fn write_output(text: &str, path: Option<&str>) -> Result<()> {
+    match path {
+        Some(path) => {
+            std::fs::write(path, text)?;
+            println!("Wrote {}", path);
+        },
+        None => print!("{}", text),
+    };
+
+    Ok(())
 }
 
 struct TextIO {
-    stdin: Stdin,
+    editor: LineEditor,
     stdout: Stdout,
-    stderr: Stderr,
 }
 
 #[derive(Debug)]
 pub enum TextInterfaceError {
     InvalidDate,
+    InvalidArgument,
     NoDepartment,
     IOError(io::Error),
 }
@@ -546,6 +2898,7 @@ impl fmt::Display for TextInterfaceError {
 
         match self {
             InvalidDate => write!(f, "Invalid Date"),
+            InvalidArgument => write!(f, "Invalid Argument"),
             NoDepartment => write!(f, "No Department"),
             IOError(e) => write!(f, "IO Error({})", e),
         }
@@ -0,0 +1,93 @@
+//! Reading dates in each format and relative to a given day, adding months, and custom formats,
+//! including input far outside the range of representable dates.
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+
+use depart_mental::dates::{add_months, DateError, DateFormat};
+
+fn ymd(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd(year, month, day)
+}
+
+fn today() -> NaiveDate {
+    ymd(2020, 1, 31)
+}
+
+#[test]
+fn formats_and_iso() {
+    assert_eq!(DateFormat::Us.parse_on("02/03/2001", today()), Ok(ymd(2001, 2, 3)));
+    assert_eq!(DateFormat::Eu.parse_on("02/03/2001", today()), Ok(ymd(2001, 3, 2)));
+    assert_eq!(DateFormat::Iso.parse_on("2001-02-03", today()), Ok(ymd(2001, 2, 3)));
+
+    // ISO 8601 is read whatever the format, and surrounding space is ignored.
+    assert_eq!(DateFormat::Us.parse_on(" 2001-02-03 ", today()), Ok(ymd(2001, 2, 3)));
+    assert_eq!(DateFormat::custom("%d.%m.%Y").unwrap().parse_on("03.02.2001", today()), Ok(ymd(2001, 2, 3)));
+}
+
+#[test]
+fn invalid_dates() {
+    for text in ["", "notadate", "13/01/2001", "02/30/2001", "2001-02-30", "+", "+d", "+1", "1d", "+1q", "+1é", "-x1d", "+-5d", "--5d", "-+5d", "+ 5d"] {
+        assert_eq!(DateFormat::Us.parse_on(text, today()), Err(DateError::Invalid(String::from(text.trim()))), "{:?}", text);
+    }
+}
+
+#[test]
+fn relative_words() {
+    assert_eq!(DateFormat::Us.parse_on("today", today()), Ok(today()));
+    assert_eq!(DateFormat::Us.parse_on("Yesterday", today()), Ok(ymd(2020, 1, 30)));
+    assert_eq!(DateFormat::Us.parse_on("TOMORROW", today()), Ok(ymd(2020, 2, 1)));
+}
+
+#[test]
+fn relative_offsets() {
+    assert_eq!(DateFormat::Us.parse_on("+10d", today()), Ok(ymd(2020, 2, 10)));
+    assert_eq!(DateFormat::Us.parse_on("-2w", today()), Ok(ymd(2020, 1, 17)));
+    assert_eq!(DateFormat::Us.parse_on("+1m", today()), Ok(ymd(2020, 2, 29)));
+    assert_eq!(DateFormat::Us.parse_on("-1Y", today()), Ok(ymd(2019, 1, 31)));
+    assert_eq!(DateFormat::Us.parse_on("+0d", today()), Ok(today()));
+}
+
+#[test]
+fn relative_offsets_out_of_range() {
+    for text in ["+99999999d", "-99999999d", "+999999999w", "+999999999m", "-999999999m", "+999999999y",
+                 "-999999999y", "+2147483647y", "+99999999999d", "--2147483648d", "-2147483648d"] {
+        assert_eq!(DateFormat::Us.parse_on(text, today()), Err(DateError::Invalid(String::from(text))), "{:?}", text);
+    }
+}
+
+#[test]
+fn months_clamp_to_shorter_months() {
+    assert_eq!(add_months(ymd(2020, 1, 31), 1), Some(ymd(2020, 2, 29)));
+    assert_eq!(add_months(ymd(2021, 1, 31), 1), Some(ymd(2021, 2, 28)));
+    assert_eq!(add_months(ymd(2020, 3, 31), 1), Some(ymd(2020, 4, 30)));
+    assert_eq!(add_months(ymd(2020, 2, 29), 12), Some(ymd(2021, 2, 28)));
+    assert_eq!(add_months(ymd(2020, 2, 29), 48), Some(ymd(2024, 2, 29)));
+}
+
+#[test]
+fn months_across_years() {
+    assert_eq!(add_months(ymd(2020, 11, 15), 3), Some(ymd(2021, 2, 15)));
+    assert_eq!(add_months(ymd(2020, 1, 15), -1), Some(ymd(2019, 12, 15)));
+    assert_eq!(add_months(ymd(2020, 1, 15), -25), Some(ymd(2017, 12, 15)));
+    assert_eq!(add_months(ymd(2020, 1, 15), 0), Some(ymd(2020, 1, 15)));
+}
+
+#[test]
+fn months_out_of_range() {
+    assert_eq!(add_months(ymd(2020, 1, 31), 999_999_999), None);
+    assert_eq!(add_months(ymd(2020, 1, 31), -999_999_999), None);
+    assert_eq!(add_months(ymd(2020, 1, 15), i32::MAX), None);
+    assert_eq!(add_months(ymd(2020, 1, 15), i32::MIN), None);
+}
+
+#[test]
+fn custom_patterns() {
+    assert!(DateFormat::custom("%Y%m%d").is_ok());
+    assert!(DateFormat::from_str("%d %B %Y").is_ok());
+
+    // Unknown specifiers, times, and patterns that can't be read back are all refused.
+    for pattern in ["%Q", "%Y-%m-%d %H:%M", "%d/%m", "%", "%Y-%"] {
+        assert_eq!(DateFormat::custom(pattern), Err(DateError::BadPattern(String::from(pattern))), "{:?}", pattern);
+    }
+}
@@ -0,0 +1,34 @@
+//! Reading amounts of money: separators, cents, and the inputs that are refused.
+use depart_mental::compensation::{CompensationError, Money};
+
+fn invalid(amount: &str) -> bool {
+    matches!(Money::parse(amount, "usd"), Err(CompensationError::InvalidAmount(a)) if a == amount)
+}
+
+#[test]
+fn amounts() {
+    assert_eq!(Money::parse("85000", "usd").unwrap(), Money::new(8_500_000, "USD"));
+    assert_eq!(Money::parse("85,000.00", "USD").unwrap(), Money::new(8_500_000, "USD"));
+    assert_eq!(Money::parse(" 23.5 ", "eur").unwrap(), Money::new(2350, "EUR"));
+    assert_eq!(Money::parse("0.05", "usd").unwrap(), Money::new(5, "USD"));
+    assert_eq!(Money::parse("92233720368547758.07", "usd").unwrap(), Money::new(i64::MAX, "USD"));
+}
+
+#[test]
+fn signs_and_junk_are_refused() {
+    for amount in ["-0.50", "-5", "+5", "+0.50", "", ".50", "5.123", "5.-1", "1e3", "5 000", "92233720368547758.08"] {
+        assert!(invalid(amount), "{:?}", amount);
+    }
+}
+
+#[test]
+fn currencies() {
+    assert!(matches!(Money::parse("5", "US"), Err(CompensationError::InvalidCurrency(_))));
+    assert!(matches!(Money::parse("5", "U$D"), Err(CompensationError::InvalidCurrency(_))));
+}
+
+#[test]
+fn negative_amounts_display() {
+    assert_eq!(Money::new(-150, "USD").to_string(), "-1.50 USD");
+    assert_eq!(Money::new(i64::MIN, "USD").to_string(), "-92233720368547758.08 USD");
+}
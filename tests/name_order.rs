@@ -0,0 +1,244 @@
+//! Regression cases for names that collide under Ord for Name and Collation::compare_names.
+//! Department lists are searched with binary_search_by, so two different names comparing equal
+//! would let one person be found, moved or removed in place of another.
+use std::rc::Rc;
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use proptest::prelude::*;
+
+use depart_mental::collation::Collation;
+use depart_mental::data_handling::ProgramData;
+use depart_mental::personnel::{Name, NameOrder, Person};
+
+const COLLATIONS: [&str; 7] = ["family", "given", "given preferred", "family de", "family sv", "family es", "family binary"];
+
+fn name(first: &str, last: &str) -> Name {
+    Name {
+        last: String::from(last),
+        middle: Vec::new(),
+        first: String::from(first),
+        prefix: None,
+        suffix: None,
+        preferred: None,
+        order: NameOrder::GivenFirst,
+    }
+}
+
+fn collations() -> Vec<Collation> {
+    COLLATIONS.iter().map(|c| Collation::from_str(c).unwrap()).collect()
+}
+
+fn assert_distinct(names: &[Name]) {
+    for a in names {
+        for b in names {
+            assert_eq!(a.cmp(b) == Ordering::Equal, a == b, "{:?} and {:?}", a, b);
+            for collation in collations() {
+                assert_eq!(collation.compare_names(a, b) == Ordering::Equal, a == b,
+                    "{:?} and {:?} under {}", a, b, collation);
+            }
+        }
+    }
+}
+
+#[test]
+fn case_and_accents_break_ties() {
+    assert_distinct(&[
+        name("Ann", "Lee"),
+        name("ann", "Lee"),
+        name("Ann", "lee"),
+        name("Ann", "Lée"),
+        name("Ánn", "Lee"),
+        name("Ann", "LEE"),
+    ]);
+}
+
+#[test]
+fn folded_spellings_break_ties() {
+    // German folds "ü" to "ue", Spanish and Swedish treat some accented letters as their own.
+    assert_distinct(&[
+        name("Jürgen", "Müller"),
+        name("Juergen", "Mueller"),
+        name("Jurgen", "Muller"),
+        name("Iñigo", "Núñez"),
+        name("Inigo", "Nunez"),
+        name("Åsa", "Öberg"),
+        name("Asa", "Oberg"),
+    ]);
+}
+
+#[test]
+fn every_part_of_a_name_breaks_ties() {
+    let plain = name("Ann", "Lee");
+    let mut names = vec![plain.clone()];
+
+    let mut middle = plain.clone();
+    middle.middle = vec![String::from("Marie")];
+    names.push(middle);
+
+    let mut joined = plain.clone();
+    joined.middle = vec![String::from("Marie Jo")];
+    names.push(joined);
+
+    let mut split = plain.clone();
+    split.middle = vec![String::from("Marie"), String::from("Jo")];
+    names.push(split);
+
+    let mut prefix = plain.clone();
+    prefix.prefix = Some(String::from("Dr."));
+    names.push(prefix);
+
+    let mut suffix = plain.clone();
+    suffix.suffix = Some(String::from("Jr."));
+    names.push(suffix);
+
+    let mut empty_suffix = plain.clone();
+    empty_suffix.suffix = Some(String::new());
+    names.push(empty_suffix);
+
+    let mut preferred = plain.clone();
+    preferred.preferred = Some(String::from("Annie"));
+    names.push(preferred);
+
+    let mut order = plain.clone();
+    order.order = NameOrder::FamilyFirst;
+    names.push(order);
+
+    assert_distinct(&names);
+}
+
+#[test]
+fn preferred_names_dont_collide_with_given_names() {
+    // Sorted by preferred name, "Annie Lee" and "Ann (Annie) Lee" have the same primary key.
+    let mut nickname = name("Ann", "Lee");
+    nickname.preferred = Some(String::from("Annie"));
+
+    assert_distinct(&[nickname, name("Annie", "Lee")]);
+}
+
+#[test]
+fn namesakes_are_kept_apart_in_a_department() {
+    let mut data = ProgramData::init();
+    let eng = data.add_dept("eng", "Engineering").unwrap();
+    data.add_dept("ops", "Operations").unwrap();
+    let hired = NaiveDate::from_ymd(2018, 3, 1);
+
+    let mut people = Vec::new();
+    for alias in ["lee1", "lee2", "lee3"].iter() {
+        let mut builder = Person::builder();
+        builder.first_name("Ann").last_name("Lee").date_of_hire(hired).department(Rc::clone(&eng));
+        people.push(data.add_person(alias, builder.build().unwrap()).unwrap());
+    }
+
+    assert_eq!(eng.borrow().employees().len(), 3);
+    assert_eq!(data.namesakes(&name("Ann", "Lee")).len(), 3);
+
+    // Moving the middle namesake must move that person, not whichever one the search lands on.
+    data.add_to_dept("lee2", "ops", Some(NaiveDate::from_ymd(2019, 1, 1))).unwrap();
+    assert!(eng.borrow().employees().iter().all(|p| !Rc::ptr_eq(p, &people[1])));
+    assert!(Rc::ptr_eq(&people[1].borrow().department(), &data.departments()["ops"]));
+
+    data.terminate("lee3", Some(NaiveDate::from_ymd(2020, 1, 1))).unwrap();
+    let remaining = eng.borrow().employees().clone();
+    assert_eq!(remaining.len(), 1);
+    assert!(Rc::ptr_eq(&remaining[0], &people[0]));
+
+    assert!(data.verify().is_empty(), "{:?}", data.verify());
+}
+
+#[test]
+fn namesakes_survive_a_change_of_collation() {
+    let mut data = ProgramData::init();
+    let eng = data.add_dept("eng", "Engineering").unwrap();
+    let hired = NaiveDate::from_ymd(2018, 3, 1);
+
+    for (alias, first, last) in [("a", "Ann", "Lee"), ("b", "ann", "lee"), ("c", "Ann", "Lee"), ("d", "Ánn", "Lée")].iter() {
+        let mut builder = Person::builder();
+        builder.first_name(first).last_name(last).date_of_hire(hired).department(Rc::clone(&eng));
+        data.add_person(alias, builder.build().unwrap()).unwrap();
+    }
+
+    for collation in collations() {
+        data.set_collation(collation);
+        assert!(data.verify().is_empty(), "under {}: {:?}", collation, data.verify());
+        assert_eq!(eng.borrow().employees().len(), 4);
+    }
+}
+
+#[test]
+fn name_order_follows_new_people_and_collation() {
+    let mut data = ProgramData::init();
+    let eng = data.add_dept("eng", "Engineering").unwrap();
+    let hired = NaiveDate::from_ymd(2018, 3, 1);
+    let hire = |data: &mut ProgramData, alias: &str, first: &str, last: &str| {
+        let mut builder = Person::builder();
+        builder.first_name(first).last_name(last).date_of_hire(hired).department(Rc::clone(&eng));
+        data.add_person(alias, builder.build().unwrap()).unwrap();
+    };
+    let order = |data: &ProgramData| -> Vec<String> {
+        data.employees_by_name().iter().map(|entry| entry.alias().clone()).collect()
+    };
+
+    hire(&mut data, "a", "Ann", "Zed");
+    hire(&mut data, "b", "Bob", "Abe");
+    data.set_collation(Collation::from_str("family").unwrap());
+    assert_eq!(order(&data), ["b", "a"]);
+
+    hire(&mut data, "c", "Cy", "Mo");
+    assert_eq!(order(&data), ["b", "c", "a"]);
+
+    data.set_collation(Collation::from_str("given").unwrap());
+    assert_eq!(order(&data), ["a", "b", "c"]);
+}
+
+fn part() -> impl Strategy<Value = String> {
+    proptest::sample::select(vec!["", "a", "A", "á", "Ä", "ae", "ñ", "n", "å", "z", "Lee", "lee", "Lée"])
+        .prop_map(String::from)
+}
+
+fn arbitrary_name() -> impl Strategy<Value = Name> {
+    (part(), part(), proptest::collection::vec(part(), 0..3), proptest::option::of(part()),
+     proptest::option::of(part()), proptest::option::of(part()), any::<bool>())
+        .prop_map(|(first, last, middle, prefix, suffix, preferred, family_first)| Name {
+            last,
+            middle,
+            first,
+            prefix,
+            suffix,
+            preferred,
+            order: if family_first { NameOrder::FamilyFirst } else { NameOrder::GivenFirst },
+        })
+}
+
+proptest! {
+    #[test]
+    fn only_identical_names_compare_equal(a in arbitrary_name(), b in arbitrary_name()) {
+        prop_assert_eq!(a.cmp(&b) == Ordering::Equal, a == b);
+        for collation in collations() {
+            prop_assert_eq!(collation.compare_names(&a, &b) == Ordering::Equal, a == b);
+            prop_assert_eq!(collation.compare_names(&a, &b), collation.compare_names(&b, &a).reverse());
+        }
+    }
+
+    #[test]
+    fn name_order_is_transitive(a in arbitrary_name(), b in arbitrary_name(), c in arbitrary_name()) {
+        let names = [&a, &b, &c];
+        for collation in collations() {
+            let le = |x: &Name, y: &Name| collation.compare_names(x, y) != Ordering::Greater;
+            for (x, y, z) in [(0, 1, 2), (0, 2, 1), (1, 0, 2), (1, 2, 0), (2, 0, 1), (2, 1, 0)].iter() {
+                if le(names[*x], names[*y]) && le(names[*y], names[*z]) {
+                    prop_assert!(le(names[*x], names[*z]));
+                }
+            }
+        }
+    }
+    #[test]
+    fn sort_keys_agree_with_comparisons(a in arbitrary_name(), b in arbitrary_name(),
+                                        x in part(), y in part()) {
+        for collation in collations() {
+            prop_assert_eq!(collation.name_key(&a).cmp(&collation.name_key(&b)), collation.compare_names(&a, &b));
+            prop_assert_eq!(collation.text_key(&x).cmp(&collation.text_key(&y)), collation.compare_text(&x, &y));
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! Hashing and checking HR passphrases.
+use depart_mental::compensation::{check_passphrase, hash_passphrase, is_legacy_hash};
+
+#[test]
+fn matches_pbkdf2_test_vectors() {
+    // PBKDF2-HMAC-SHA256 of "password" with the salt "salt" (hex 73616c74).
+    assert!(check_passphrase("password",
+        "pbkdf2-sha256$1$73616c74$120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b"));
+    assert!(check_passphrase("password",
+        "pbkdf2-sha256$4096$73616c74$c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a"));
+    assert!(!check_passphrase("passw0rd",
+        "pbkdf2-sha256$4096$73616c74$c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a"));
+}
+
+#[test]
+fn hashes_are_salted() {
+    let first = hash_passphrase("open sesame");
+    let second = hash_passphrase("open sesame");
+
+    assert_ne!(first, second);
+    assert!(!is_legacy_hash(&first));
+    assert!(check_passphrase("open sesame", &first));
+    assert!(check_passphrase("open sesame", &second));
+    assert!(!check_passphrase("open sesame!", &first));
+    assert!(!check_passphrase("", &first));
+}
+
+#[test]
+fn long_passphrases() {
+    let long = "correct horse battery staple ".repeat(4);
+    let hash = hash_passphrase(&long);
+
+    assert!(check_passphrase(&long, &hash));
+    assert!(!check_passphrase(&long[1..], &hash));
+}
+
+#[test]
+fn unsalted_hashes_from_earlier_versions() {
+    // SHA-256 of "secret".
+    let legacy = "2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b";
+
+    assert!(is_legacy_hash(legacy));
+    assert!(check_passphrase("secret", legacy));
+    assert!(!check_passphrase("Secret", legacy));
+}
+
+#[test]
+fn malformed_hashes_match_nothing() {
+    for stored in ["", "pbkdf2-sha256$0$73616c74$00", "pbkdf2-sha256$x$73616c74$00", "pbkdf2-sha256$1$7$00",
+                   "pbkdf2-sha256$1$zz$00", "md5$1$2", "pbkdf2-sha256$1$73616c74"] {
+        assert!(!check_passphrase("", stored), "{:?}", stored);
+        assert!(!check_passphrase("password", stored), "{:?}", stored);
+    }
+}
@@ -0,0 +1,127 @@
+//! Parsing of QUERY text: clauses, operator precedence, literals and the errors reported for
+//! malformed queries.
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+
+use depart_mental::query::{CompareOp, Condition, Field, Query, QueryError, SortKey, Source, Value};
+
+fn parse(text: &str) -> Result<Query, QueryError> {
+    Query::from_str(text)
+}
+
+fn syntax_error(text: &str) -> String {
+    match parse(text) {
+        Err(QueryError::Syntax(e)) => e,
+        other => panic!("expected a syntax error for {:?}, got {:?}", text, other),
+    }
+}
+
+fn compare(field: Field, op: CompareOp, value: &str) -> Condition {
+    Condition::Compare(field, op, Value::Text(String::from(value)))
+}
+
+#[test]
+fn source_alone_uses_default_columns() {
+    let query = parse("people").unwrap();
+    assert_eq!(query.source, Source::People);
+    assert_eq!(query.filter, None);
+    assert!(query.order.is_empty());
+    assert_eq!(query.limit, None);
+    assert_eq!(query.projection, vec![Field::Alias, Field::Name, Field::Hired, Field::Dept]);
+
+    let query = parse("DEPARTMENTS").unwrap();
+    assert_eq!(query.source, Source::Departments);
+    assert_eq!(query.projection, vec![Field::Alias, Field::Name, Field::Headcount]);
+}
+
+#[test]
+fn every_clause() {
+    let query = parse("people where hired >= 2019-03-01 order by last desc, first limit 5 select alias, title").unwrap();
+
+    assert_eq!(query.filter, Some(Condition::Compare(Field::Hired, CompareOp::Ge,
+                                                     Value::Date(NaiveDate::from_ymd(2019, 3, 1)))));
+    assert_eq!(query.order, vec![SortKey { field: Field::Last, descending: true },
+                                 SortKey { field: Field::First, descending: false }]);
+    assert_eq!(query.limit, Some(5));
+    assert_eq!(query.projection, vec![Field::Alias, Field::Title]);
+}
+
+#[test]
+fn clauses_in_any_order() {
+    let query = parse("people limit 2 select alias where dept = eng").unwrap();
+
+    assert_eq!(query.filter, Some(compare(Field::Dept, CompareOp::Eq, "eng")));
+    assert_eq!(query.limit, Some(2));
+    assert_eq!(query.projection, vec![Field::Alias]);
+}
+
+#[test]
+fn and_binds_tighter_than_or() {
+    let query = parse("people where dept = a or dept = b and title ~ lead").unwrap();
+
+    let expected = Condition::Or(
+        Box::new(compare(Field::Dept, CompareOp::Eq, "a")),
+        Box::new(Condition::And(Box::new(compare(Field::Dept, CompareOp::Eq, "b")),
+                                Box::new(compare(Field::Title, CompareOp::Contains, "lead")))));
+    assert_eq!(query.filter, Some(expected));
+}
+
+#[test]
+fn parentheses_and_not() {
+    let query = parse("people where not (dept = a or dept != b)").unwrap();
+
+    let expected = Condition::Not(Box::new(Condition::Or(
+        Box::new(compare(Field::Dept, CompareOp::Eq, "a")),
+        Box::new(compare(Field::Dept, CompareOp::Ne, "b")))));
+    assert_eq!(query.filter, Some(expected));
+}
+
+#[test]
+fn operators_without_spaces() {
+    let query = parse("departments where headcount<=12").unwrap();
+    assert_eq!(query.filter, Some(Condition::Compare(Field::Headcount, CompareOp::Le, Value::Number(12))));
+}
+
+#[test]
+fn quoted_literals_keep_their_spaces() {
+    let query = parse("people where name = \"Ann  van  Lee\"").unwrap();
+    assert_eq!(query.filter, Some(compare(Field::Name, CompareOp::Eq, "Ann  van  Lee")));
+
+    let query = parse("people where title = 'Lead, Ops (EMEA)'").unwrap();
+    assert_eq!(query.filter, Some(compare(Field::Title, CompareOp::Eq, "Lead, Ops (EMEA)")));
+}
+
+#[test]
+fn repeated_clauses_are_named() {
+    assert_eq!(syntax_error("people where dept = a where dept = b"), "WHERE given more than once");
+    assert_eq!(syntax_error("people order by last order by first"), "ORDER BY given more than once");
+    assert_eq!(syntax_error("people limit 1 limit 2"), "LIMIT given more than once");
+    assert_eq!(syntax_error("people select alias select name"), "SELECT given more than once");
+}
+
+#[test]
+fn operators_are_shown_as_written() {
+    assert_eq!(syntax_error("people where hired < < 2019-01-01"), "expected value, found '<'");
+    assert_eq!(syntax_error("people where >= 2019-01-01"), "expected field name, found '>='");
+    assert_eq!(syntax_error("people where dept ~"), "expected value, found end of query");
+}
+
+#[test]
+fn malformed_queries() {
+    assert_eq!(syntax_error("staff"), "query must start with PEOPLE or DEPARTMENTS");
+    assert_eq!(syntax_error("people where name = \"Ann"), "unterminated string");
+    assert_eq!(syntax_error("people where dept ! eng"), "expected '!='");
+    assert_eq!(syntax_error("people where (dept = a"), "expected ')', found end of query");
+    assert_eq!(syntax_error("people order last"), "expected by, found 'last'");
+    assert_eq!(syntax_error("people limit five"), "LIMIT expects a number, found 'five'");
+    assert_eq!(syntax_error("people group by dept"), "expected WHERE, ORDER BY, LIMIT or SELECT, found 'group'");
+}
+
+#[test]
+fn unknown_fields_and_bad_values() {
+    assert!(matches!(parse("people where shoe_size = 9"), Err(QueryError::UnknownField(f)) if f == "shoe_size"));
+    assert!(matches!(parse("departments where hired > 2019-01-01"), Err(QueryError::UnknownField(_))));
+    assert!(matches!(parse("people where hired > soon"), Err(QueryError::InvalidValue(Field::Hired, v)) if v == "soon"));
+    assert!(matches!(parse("departments where headcount = many"), Err(QueryError::InvalidValue(Field::Headcount, _))));
+}
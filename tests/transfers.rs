@@ -0,0 +1,179 @@
+//! Generated histories of hires, transfers, memberships and terminations, checking after every
+//! step that each current employee is listed exactly once, in their own department, that the
+//! lists stay sorted, and that department history agrees with the current department.
+use std::rc::Rc;
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use chrono::{Duration, NaiveDate};
+use proptest::prelude::*;
+
+use depart_mental::collation::Collation;
+use depart_mental::data_handling::ProgramData;
+use depart_mental::personnel::Person;
+use depart_mental::storage::Snapshot;
+
+const LAST_NAMES: [&str; 6] = ["Lee", "lee", "Lée", "Müller", "Mueller", "Ng"];
+const FIRST_NAMES: [&str; 4] = ["Ann", "ann", "Anne", "Ánn"];
+const COLLATIONS: [&str; 5] = ["family", "given", "given preferred", "family de", "family binary"];
+
+#[derive(Debug, Clone)]
+enum Op {
+    AddDept,
+    Hire { last: usize, first: usize, preferred: bool, dept: usize, day: i64 },
+    Transfer { person: usize, dept: usize, day: i64 },
+    Join { person: usize, dept: usize, allocation: u32, day: i64 },
+    Leave { person: usize, dept: usize, day: i64 },
+    Terminate { person: usize, day: i64 },
+    SetCollation(usize),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let day = 0..1500i64;
+    prop_oneof![
+        1 => Just(Op::AddDept),
+        3 => (0..LAST_NAMES.len(), 0..FIRST_NAMES.len(), any::<bool>(), 0..8usize, day.clone())
+            .prop_map(|(last, first, preferred, dept, day)| Op::Hire { last, first, preferred, dept, day }),
+        4 => (0..20usize, 0..8usize, day.clone()).prop_map(|(person, dept, day)| Op::Transfer { person, dept, day }),
+        2 => (0..20usize, 0..8usize, 0..110u32, day.clone())
+            .prop_map(|(person, dept, allocation, day)| Op::Join { person, dept, allocation, day }),
+        1 => (0..20usize, 0..8usize, day.clone()).prop_map(|(person, dept, day)| Op::Leave { person, dept, day }),
+        1 => (0..20usize, day).prop_map(|(person, day)| Op::Terminate { person, day }),
+        1 => (0..COLLATIONS.len()).prop_map(Op::SetCollation),
+    ]
+}
+
+fn date(day: i64) -> NaiveDate {
+    NaiveDate::from_ymd(2015, 1, 1) + Duration::days(day)
+}
+
+/// Carry out one step.  Returns false if it was rejected.
+fn apply(data: &mut ProgramData, op: &Op) -> bool {
+    let dept = |data: &ProgramData, i: usize| {
+        let mut list = data.dept_list();
+        let n = list.len();
+        if n == 0 { None } else { list.nth(i % n).map(|entry| entry.alias().clone()) }
+    };
+    let person = |data: &ProgramData, i: usize| {
+        let mut list = data.employee_list();
+        let n = list.len();
+        if n == 0 { None } else { list.nth(i % n).map(|entry| entry.alias().clone()) }
+    };
+
+    match *op {
+        Op::AddDept => {
+            let n = data.dept_list().len();
+            data.add_dept(&format!("d{}", n), &format!("Department {}", n)).is_ok()
+        },
+        Op::Hire { last, first, preferred, dept: d, day } => {
+            let department = match dept(data, d) {
+                Some(alias) => Rc::clone(&data.departments()[&alias]),
+                None => return false,
+            };
+            let mut builder = Person::builder();
+            builder.last_name(LAST_NAMES[last])
+                .first_name(FIRST_NAMES[first])
+                .date_of_hire(date(day))
+                .department(department);
+            if preferred {
+                builder.preferred_name("Annie");
+            }
+            let alias = format!("p{}", data.next_employee_id());
+            data.add_person(&alias, builder.build().expect("complete person")).is_ok()
+        },
+        Op::Transfer { person: p, dept: d, day } => match (person(data, p), dept(data, d)) {
+            (Some(p), Some(d)) => data.add_to_dept(&p, &d, Some(date(day))).is_ok(),
+            _ => false,
+        },
+        Op::Join { person: p, dept: d, allocation, day } => match (person(data, p), dept(data, d)) {
+            (Some(p), Some(d)) => data.add_membership(&p, &d, allocation, Some(date(day))).is_ok(),
+            _ => false,
+        },
+        Op::Leave { person: p, dept: d, day } => match (person(data, p), dept(data, d)) {
+            (Some(p), Some(d)) => data.end_membership(&p, &d, Some(date(day))).is_ok(),
+            _ => false,
+        },
+        Op::Terminate { person: p, day } => match person(data, p) {
+            Some(p) => data.terminate(&p, Some(date(day))).is_ok(),
+            None => false,
+        },
+        Op::SetCollation(i) => {
+            data.set_collation(Collation::from_str(COLLATIONS[i]).expect("valid collation"));
+            true
+        },
+    }
+}
+
+fn check(data: &ProgramData) -> Result<(), TestCaseError> {
+    let violations = data.verify();
+    prop_assert!(violations.is_empty(), "{:?}", violations);
+
+    let collation = data.collation();
+
+    for entry in data.dept_list() {
+        let dept = entry.borrow_pointer().borrow();
+        for list in [dept.employees(), dept.members()].iter() {
+            for pair in list.windows(2) {
+                prop_assert_eq!(collation.compare_people(&pair[0].borrow(), &pair[1].borrow()), Ordering::Less,
+                    "{} is out of order", entry.alias());
+            }
+        }
+    }
+
+    for entry in data.employee_list() {
+        let pointer = entry.pointer();
+        let person = pointer.borrow();
+        let history = person.department_history();
+
+        prop_assert!(history.windows(2).all(|pair| pair[0].date() <= pair[1].date()),
+            "history of {} is out of order", entry.alias());
+        prop_assert!(Rc::ptr_eq(&history.last().expect("history").department(), &person.department()),
+            "{} is not in their latest department", entry.alias());
+
+        let listings: usize = data.dept_list()
+            .map(|d| d.borrow_pointer().borrow().employees().iter().filter(|p| Rc::ptr_eq(p, &pointer)).count())
+            .sum();
+
+        if person.is_terminated() {
+            prop_assert_eq!(listings, 0, "{} left but is still listed", entry.alias());
+            prop_assert!(person.current_memberships().is_empty());
+        } else {
+            prop_assert_eq!(listings, 1, "{} is listed {} times", entry.alias(), listings);
+            let dept = person.department();
+            let dept = dept.borrow();
+            let found = dept.employees()
+                .binary_search_by(|p| collation.compare_people(&p.borrow(), &person))
+                .map(|i| Rc::ptr_eq(&dept.employees()[i], &pointer));
+            prop_assert_eq!(found, Ok(true), "{} can't be found in their department", entry.alias());
+        }
+
+        let secondary: u32 = person.current_memberships().iter().map(|m| m.allocation()).sum();
+        prop_assert!(secondary < 100, "{} is allocated {}% elsewhere", entry.alias(), secondary);
+        prop_assert!(person.current_memberships().iter().all(|m| !Rc::ptr_eq(&m.department(), &person.department())));
+    }
+
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(200))]
+
+    #[test]
+    fn invariants_hold_after_every_step(ops in proptest::collection::vec(op(), 1..60)) {
+        let mut data = ProgramData::init();
+        data.add_dept("d0", "Department 0").unwrap();
+
+        for op in &ops {
+            let before = Snapshot::take(&data);
+            if !apply(&mut data, op) {
+                prop_assert_eq!(&Snapshot::take(&data), &before, "rejected {:?} changed the data", op);
+            }
+            check(&data)?;
+        }
+
+        let saved = Snapshot::take(&data);
+        let restored = saved.restore().expect("saved data restores");
+        prop_assert_eq!(&Snapshot::take(&restored), &saved);
+        check(&restored)?;
+    }
+}